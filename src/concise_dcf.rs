@@ -0,0 +1,491 @@
+//! The concise DCF of CiA 302: a binary list of values to write into the
+//! object dictionary of a node.
+//!
+//! A concise DCF is made of the number of entries, as an UNSIGNED32, followed
+//! by the entries. Each entry holds the index, sub-index, size in bytes as an
+//! UNSIGNED32 and the value, all little-endian.
+
+use embedded_can::nb::Can;
+
+use crate::{
+    node::NodeId,
+    object_dictionary::{EntryId, ObjectDictionary},
+    sdo::{SdoAbortCode, SdoClient, SdoError},
+    time::Clock,
+};
+
+/// Configuration of the nodes by concise DCF. Sub-index n holds the concise
+/// DCF of node n.
+pub const CONCISE_DCF_INDEX: u16 = 0x1F22;
+
+/// Largest concise DCF accepted by a node, in bytes.
+pub const CONCISE_DCF_CAPACITY: usize = 256;
+
+const COUNT_SIZE: usize = 4;
+const HEADER_SIZE: usize = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConciseDcfError {
+    /// The data ends within an entry or before the announced number of
+    /// entries.
+    Truncated,
+    /// The data goes on after the announced number of entries.
+    TrailingBytes,
+}
+
+/// Builds a concise DCF.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConciseDcf {
+    data: std::vec::Vec<u8>,
+}
+
+impl Default for ConciseDcf {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConciseDcf {
+    pub fn new() -> Self {
+        Self {
+            data: std::vec![0; COUNT_SIZE],
+        }
+    }
+
+    /// Validates an encoded concise DCF.
+    pub fn from_bytes(data: std::vec::Vec<u8>) -> Result<Self, ConciseDcfError> {
+        entries(&data)?.try_for_each(|entry| entry.map(|_| ()))?;
+        Ok(Self { data })
+    }
+
+    /// Appends the value `data` of the entry `id`.
+    pub fn push(&mut self, id: EntryId, data: &[u8]) {
+        let count = self.len() as u32 + 1;
+        self.data[..COUNT_SIZE].copy_from_slice(&count.to_le_bytes());
+        self.data.extend_from_slice(&id.to_le_bytes());
+        self.data
+            .extend_from_slice(&(data.len() as u32).to_le_bytes());
+        self.data.extend_from_slice(data);
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        u32::from_le_bytes(self.data[..COUNT_SIZE].try_into().unwrap()) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (EntryId, &[u8])> {
+        entries(&self.data).unwrap().map(|entry| entry.unwrap())
+    }
+}
+
+/// Iterates over the entries of an encoded concise DCF, in order.
+pub struct Entries<'a> {
+    data: &'a [u8],
+    remaining: u32,
+}
+
+impl<'a> Iterator for Entries<'a> {
+    type Item = Result<(EntryId, &'a [u8]), ConciseDcfError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            if self.data.is_empty() {
+                return None;
+            }
+            self.data = &[];
+            return Some(Err(ConciseDcfError::TrailingBytes));
+        }
+        self.remaining -= 1;
+        let entry = self.decode_entry();
+        if entry.is_err() {
+            self.remaining = 0;
+            self.data = &[];
+        }
+        Some(entry)
+    }
+}
+
+impl<'a> Entries<'a> {
+    fn decode_entry(&mut self) -> Result<(EntryId, &'a [u8]), ConciseDcfError> {
+        if self.data.len() < HEADER_SIZE {
+            return Err(ConciseDcfError::Truncated);
+        }
+        let id = EntryId::from_bytes(self.data[0..3].try_into().unwrap());
+        let size = u32::from_le_bytes(self.data[3..HEADER_SIZE].try_into().unwrap()) as usize;
+        let value = self.data[HEADER_SIZE..]
+            .get(..size)
+            .ok_or(ConciseDcfError::Truncated)?;
+        self.data = &self.data[HEADER_SIZE + size..];
+        Ok((id, value))
+    }
+}
+
+/// Decodes the number of entries of a concise DCF, returning an iterator over
+/// them.
+pub fn entries(data: &[u8]) -> Result<Entries<'_>, ConciseDcfError> {
+    let count = data.get(..COUNT_SIZE).ok_or(ConciseDcfError::Truncated)?;
+    Ok(Entries {
+        data: &data[COUNT_SIZE..],
+        remaining: u32::from_le_bytes(count.try_into().unwrap()),
+    })
+}
+
+/// Writes every entry of a concise DCF into `od`, or none of them if one
+/// cannot be written.
+///
+/// PDO parameters are written in order, as each of them is checked against
+/// the ones before: remapping a PDO takes disabling it, clearing its mapping,
+/// writing the new one and enabling it again. Domains are refused, as their
+/// handlers cannot check a value without writing it.
+pub(crate) fn apply<const ENTRY_COUNT: usize, const RPDO_COUNT: usize, const TPDO_COUNT: usize>(
+    od: &mut ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+    data: &[u8],
+) -> Result<(), SdoAbortCode> {
+    let pdo_configurations = od.pdo_configurations();
+    let checked = check_and_write_pdo_parameters(od, data);
+    if checked.is_err() {
+        od.restore_pdo_configurations(pdo_configurations);
+        return checked;
+    }
+    for (id, value) in entries(data).unwrap().map(Result::unwrap) {
+        if !od.is_pdo_parameter(id.index()) {
+            od.write_raw(id, value)
+                .expect("entries are checked before being written");
+        }
+    }
+    Ok(())
+}
+
+/// Writes the PDO parameters of a concise DCF and checks its other entries.
+fn check_and_write_pdo_parameters<
+    const ENTRY_COUNT: usize,
+    const RPDO_COUNT: usize,
+    const TPDO_COUNT: usize,
+>(
+    od: &mut ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+    data: &[u8],
+) -> Result<(), SdoAbortCode> {
+    for entry in entries(data).map_err(|_| SdoAbortCode::InvalidValue)? {
+        let (id, value) = entry.map_err(|_| SdoAbortCode::InvalidValue)?;
+        if id.index() == CONCISE_DCF_INDEX {
+            return Err(SdoAbortCode::InvalidValue);
+        }
+        if od.domain(id).is_some() {
+            return Err(SdoAbortCode::UnsupportedAccess);
+        }
+        if od.is_pdo_parameter(id.index()) {
+            od.write_raw(id, value)?;
+        } else {
+            od.check_write(id, value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Downloads a concise DCF to the node `node_id`, which applies it at once.
+pub fn download<B: Can>(
+    client: &mut SdoClient,
+    bus: &mut B,
+    clock: &impl Clock,
+    node_id: NodeId,
+    dcf: &ConciseDcf,
+) -> Result<(), SdoError> {
+    let id = EntryId::new(CONCISE_DCF_INDEX, node_id.raw());
+    client.download(bus, clock, node_id, id, dcf.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use heapless::Vec;
+
+    use crate::node::NodeId;
+    use crate::object_dictionary::{
+        AccessType, CobId, DomainHandler, EntryId, FrameId, ObjectDictionary, PdoMapability,
+        Variable, VariableType,
+    };
+    use crate::parameter_coder::*;
+    use crate::pdo::{PdoConfiguration, PdoEntryMapping, PdoTransmissionType};
+    use crate::sdo::{SdoAbortCode, SdoClient, SdoError};
+    use crate::test_utils::VirtualBus;
+    use crate::time::StdClock;
+
+    use super::{apply, download, entries, ConciseDcf, ConciseDcfError, CONCISE_DCF_CAPACITY};
+
+    fn test_od() -> ObjectDictionary<3, 0, 1> {
+        let mut mappings = [PdoEntryMapping::default(); 8];
+        mappings[0] = PdoEntryMapping::new(0x2000, 0x1, 32);
+        let tpdo = PdoConfiguration::new(
+            CobId::new(true, false, FrameId::Standard(0x185)),
+            PdoTransmissionType::EventDriven,
+            1,
+            mappings,
+            0,
+        );
+        ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [tpdo],
+            [],
+            Vec::from_slice(&[
+                Variable::builder(EntryId::new(0x1017, 0x0))
+                    .name("Producer heartbeat time")
+                    .access(AccessType::ReadWrite)
+                    .pdo(PdoMapability::None)
+                    .value(VariableType::UInt16(0, &DefaultU16Coder))
+                    .unwrap(),
+                Variable::builder(EntryId::new(0x2000, 0x1))
+                    .name("rw_i32")
+                    .access(AccessType::ReadWrite)
+                    .pdo(PdoMapability::Tpdo)
+                    .value(VariableType::Int32(0, &DefaultI32Coder))
+                    .unwrap(),
+                Variable::builder(EntryId::new(0x2001, 0x0))
+                    .name("ro_u8")
+                    .access(AccessType::ReadOnly)
+                    .pdo(PdoMapability::Tpdo)
+                    .value(VariableType::UInt8(7, &DefaultU8Coder))
+                    .unwrap(),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::new(5).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_encoding() {
+        let mut dcf = ConciseDcf::new();
+        assert!(dcf.is_empty());
+        dcf.push(EntryId::new(0x1017, 0x0), &1000u16.to_le_bytes());
+        dcf.push(EntryId::new(0x2000, 0x1), &(-2i32).to_le_bytes());
+        assert_eq!(dcf.len(), 2);
+        assert_eq!(
+            dcf.as_bytes(),
+            [
+                2, 0, 0, 0, //
+                0x17, 0x10, 0x00, 2, 0, 0, 0, 0xE8, 0x03, //
+                0x00, 0x20, 0x01, 4, 0, 0, 0, 0xFE, 0xFF, 0xFF, 0xFF,
+            ]
+        );
+        let decoded: std::vec::Vec<_> = dcf.entries().collect();
+        assert_eq!(
+            decoded,
+            [
+                (EntryId::new(0x1017, 0x0), &[0xE8, 0x03][..]),
+                (EntryId::new(0x2000, 0x1), &[0xFE, 0xFF, 0xFF, 0xFF][..]),
+            ]
+        );
+        assert_eq!(ConciseDcf::from_bytes(dcf.as_bytes().to_vec()), Ok(dcf));
+    }
+
+    #[test]
+    fn test_decoding_errors() {
+        assert!(entries(&[1, 0, 0]).is_err());
+        let truncated = [1, 0, 0, 0, 0x17, 0x10, 0x00, 2, 0, 0, 0, 0xE8];
+        assert_eq!(
+            entries(&truncated).unwrap().collect::<std::vec::Vec<_>>(),
+            [Err(ConciseDcfError::Truncated)]
+        );
+        assert_eq!(
+            ConciseDcf::from_bytes(std::vec![0, 0, 0, 0, 1]),
+            Err(ConciseDcfError::TrailingBytes)
+        );
+    }
+
+    #[test]
+    fn test_apply_is_atomic() {
+        let mut od = test_od();
+        let mut dcf = ConciseDcf::new();
+        dcf.push(EntryId::new(0x1017, 0x0), &1000u16.to_le_bytes());
+        dcf.push(EntryId::new(0x2001, 0x0), &[1]);
+        assert_eq!(
+            apply(&mut od, dcf.as_bytes()),
+            Err(SdoAbortCode::ReadOnlyError)
+        );
+        assert_eq!(od.read_unsigned(EntryId::new(0x1017, 0x0)), Some(0));
+
+        let mut dcf = ConciseDcf::new();
+        dcf.push(EntryId::new(0x1017, 0x0), &1000u16.to_le_bytes());
+        dcf.push(EntryId::new(0x2000, 0x1), &[1, 2]);
+        assert_eq!(apply(&mut od, dcf.as_bytes()), Err(SdoAbortCode::TooShort));
+        assert_eq!(od.read_unsigned(EntryId::new(0x1017, 0x0)), Some(0));
+    }
+
+    /// A domain refusing every value.
+    struct RejectingDomain;
+
+    impl DomainHandler for RejectingDomain {
+        fn size(&self) -> usize {
+            0
+        }
+
+        fn capacity(&self) -> usize {
+            16
+        }
+
+        fn read(&self, _offset: usize, _buf: &mut [u8]) -> Result<(), SdoAbortCode> {
+            Ok(())
+        }
+
+        fn write(&self, _offset: usize, _data: &[u8]) -> Result<(), SdoAbortCode> {
+            Err(SdoAbortCode::InvalidValue)
+        }
+
+        fn finish_write(&self, _size: usize) -> Result<(), SdoAbortCode> {
+            Err(SdoAbortCode::InvalidValue)
+        }
+    }
+
+    #[test]
+    fn test_refuses_domains() {
+        static DOMAIN: RejectingDomain = RejectingDomain;
+        let mut od: ObjectDictionary<2, 0, 0> = ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [],
+            Vec::from_slice(&[
+                Variable::builder(EntryId::new(0x1017, 0x0))
+                    .access(AccessType::ReadWrite)
+                    .value(VariableType::UInt16(0, &DefaultU16Coder))
+                    .unwrap(),
+                Variable::builder(EntryId::new(0x1F50, 0x1))
+                    .access(AccessType::ReadWrite)
+                    .value(VariableType::Domain(&DOMAIN))
+                    .unwrap(),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::new(5).unwrap(),
+        );
+        let mut dcf = ConciseDcf::new();
+        dcf.push(EntryId::new(0x1017, 0x0), &1000u16.to_le_bytes());
+        dcf.push(EntryId::new(0x1F50, 0x1), &[1, 2, 3]);
+        assert_eq!(
+            od.write_raw(EntryId::new(0x1F22, 5), dcf.as_bytes()),
+            Err(SdoAbortCode::UnsupportedAccess)
+        );
+        assert_eq!(od.read_unsigned(EntryId::new(0x1017, 0x0)), Some(0));
+    }
+
+    fn tpdo_mappings(od: &ObjectDictionary<3, 0, 1>) -> std::vec::Vec<EntryId> {
+        let tpdo = od.tpdo_configuration(0).unwrap();
+        tpdo.mappings().iter().map(|m| m.entry_id()).collect()
+    }
+
+    #[test]
+    fn test_remap_tpdo() {
+        let mut od = test_od();
+        let disabled = CobId::new(false, false, FrameId::Standard(0x185)).raw();
+        let enabled = CobId::new(true, false, FrameId::Standard(0x185)).raw();
+        let remap = |dcf: &mut ConciseDcf, mappings: &[u32]| {
+            dcf.push(EntryId::new(0x1800, 0x1), &disabled.to_le_bytes());
+            dcf.push(EntryId::new(0x1A00, 0x0), &[0]);
+            for (sub_index, mapping) in (1..).zip(mappings) {
+                dcf.push(EntryId::new(0x1A00, sub_index), &mapping.to_le_bytes());
+            }
+            dcf.push(EntryId::new(0x1A00, 0x0), &[mappings.len() as u8]);
+            dcf.push(EntryId::new(0x1800, 0x1), &enabled.to_le_bytes());
+        };
+
+        let mut dcf = ConciseDcf::new();
+        remap(&mut dcf, &[0x2001_0008, 0x2000_0120]);
+        dcf.push(EntryId::new(0x1017, 0x0), &1000u16.to_le_bytes());
+        assert_eq!(apply(&mut od, dcf.as_bytes()), Ok(()));
+        assert_eq!(
+            tpdo_mappings(&od),
+            [EntryId::new(0x2001, 0x0), EntryId::new(0x2000, 0x1)]
+        );
+        assert!(od.tpdo_configuration(0).unwrap().cob_id().is_valid());
+        assert_eq!(
+            od.read_raw(EntryId::new(0x1A00, 0x1)).unwrap(),
+            [8, 0, 1, 0x20]
+        );
+        assert_eq!(od.read_unsigned(EntryId::new(0x1017, 0x0)), Some(1000));
+
+        // The mapping can only change while the PDO is disabled and
+        // sub-index 0 is 0.
+        let mut dcf = ConciseDcf::new();
+        dcf.push(EntryId::new(0x1A00, 0x0), &[0]);
+        assert_eq!(
+            apply(&mut od, dcf.as_bytes()),
+            Err(SdoAbortCode::UnsupportedAccess)
+        );
+        let mut dcf = ConciseDcf::new();
+        dcf.push(EntryId::new(0x1800, 0x1), &disabled.to_le_bytes());
+        dcf.push(EntryId::new(0x1A00, 0x1), &0x2000_0120u32.to_le_bytes());
+        assert_eq!(
+            apply(&mut od, dcf.as_bytes()),
+            Err(SdoAbortCode::UnsupportedAccess)
+        );
+        assert!(od.tpdo_configuration(0).unwrap().cob_id().is_valid());
+
+        // A failed remap leaves the PDO as it was.
+        let mut dcf = ConciseDcf::new();
+        remap(&mut dcf, &[0x1017_0010]);
+        assert_eq!(
+            apply(&mut od, dcf.as_bytes()),
+            Err(SdoAbortCode::ObjectCannotBeMapped)
+        );
+        let mut dcf = ConciseDcf::new();
+        remap(&mut dcf, &[0x2000_0120, 0x2000_0120, 0x2001_0008]);
+        assert_eq!(
+            apply(&mut od, dcf.as_bytes()),
+            Err(SdoAbortCode::PDOOverflow)
+        );
+        assert_eq!(
+            tpdo_mappings(&od),
+            [EntryId::new(0x2001, 0x0), EntryId::new(0x2000, 0x1)]
+        );
+        assert!(od.tpdo_configuration(0).unwrap().cob_id().is_valid());
+    }
+
+    #[test]
+    fn test_download() {
+        let mut bus = VirtualBus::new(test_od());
+        let mut client = SdoClient::new();
+        let node_id = NodeId::new(5).unwrap();
+
+        let mut dcf = ConciseDcf::new();
+        dcf.push(EntryId::new(0x1017, 0x0), &1000u16.to_le_bytes());
+        dcf.push(EntryId::new(0x2000, 0x1), &(-2i32).to_le_bytes());
+        assert_eq!(
+            download(&mut client, &mut bus, &StdClock::new(), node_id, &dcf),
+            Ok(())
+        );
+        assert_eq!(bus.od.read_unsigned(EntryId::new(0x1017, 0x0)), Some(1000));
+        assert_eq!(
+            bus.od.read_unsigned(EntryId::new(0x2000, 0x1)),
+            Some(0xFFFF_FFFE)
+        );
+
+        let mut dcf = ConciseDcf::new();
+        dcf.push(EntryId::new(0x2000, 0x1), &7i32.to_le_bytes());
+        let id = EntryId::new(0x1F22, 5);
+        assert_eq!(
+            client.block_download(&mut bus, &StdClock::new(), node_id, id, dcf.as_bytes()),
+            Ok(())
+        );
+        assert_eq!(bus.od.read_unsigned(EntryId::new(0x2000, 0x1)), Some(7));
+
+        let mut dcf = ConciseDcf::new();
+        while dcf.as_bytes().len() <= CONCISE_DCF_CAPACITY {
+            dcf.push(EntryId::new(0x1017, 0x0), &2000u16.to_le_bytes());
+        }
+        assert_eq!(
+            download(&mut client, &mut bus, &StdClock::new(), node_id, &dcf),
+            Err(SdoError::ServerAbort(SdoAbortCode::TooLong))
+        );
+        assert_eq!(bus.od.read_unsigned(EntryId::new(0x1017, 0x0)), Some(1000));
+    }
+}
@@ -0,0 +1,1192 @@
+//! Parser for the electronic data sheets (EDS) and device configuration files
+//! (DCF) of CiA 306.
+
+pub mod codegen;
+pub mod dcf;
+
+use std::{collections::BTreeMap, string::String, vec::Vec};
+
+use num_derive::{FromPrimitive, ToPrimitive};
+use num_traits::FromPrimitive;
+
+use crate::{
+    node::NodeId,
+    object_dictionary::{
+        AccessType, CobId, DomainHandler, EntryId, FixedBytes, NodeIdRelative, PdoMapability,
+        Variable, VariableType,
+    },
+    parameter_coder::*,
+    pdo::{
+        PdoConfiguration, PdoEntryMapping, PdoTransmissionType, RPDO_COMMUNICATION_INDEX,
+        RPDO_MAPPING_INDEX, TPDO_COMMUNICATION_INDEX, TPDO_MAPPING_INDEX,
+    },
+};
+
+/// Objects held by [`crate::object_dictionary::ObjectDictionary`] itself rather
+/// than by its variables.
+fn is_managed_by_od(index: u16) -> bool {
+    matches!(index, 0x1001 | 0x1003 | 0x1400..=0x1BFF)
+}
+
+#[derive(FromPrimitive, ToPrimitive, Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ObjectType {
+    Null = 0x0,
+    Domain = 0x2,
+    DefType = 0x5,
+    DefStruct = 0x6,
+    Var = 0x7,
+    Array = 0x8,
+    Record = 0x9,
+}
+
+/// The CiA 301 data types, by their index in the object dictionary.
+#[derive(FromPrimitive, ToPrimitive, Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u16)]
+pub enum DataType {
+    Boolean = 0x0001,
+    Integer8 = 0x0002,
+    Integer16 = 0x0003,
+    Integer32 = 0x0004,
+    Unsigned8 = 0x0005,
+    Unsigned16 = 0x0006,
+    Unsigned32 = 0x0007,
+    Real32 = 0x0008,
+    VisibleString = 0x0009,
+    OctetString = 0x000A,
+    UnicodeString = 0x000B,
+    TimeOfDay = 0x000C,
+    TimeDifference = 0x000D,
+    Domain = 0x000F,
+    Integer24 = 0x0010,
+    Real64 = 0x0011,
+    Integer40 = 0x0012,
+    Integer48 = 0x0013,
+    Integer56 = 0x0014,
+    Integer64 = 0x0015,
+    Unsigned24 = 0x0016,
+    Unsigned40 = 0x0018,
+    Unsigned48 = 0x0019,
+    Unsigned56 = 0x001A,
+    Unsigned64 = 0x001B,
+}
+
+impl DataType {
+    fn is_integer(&self) -> bool {
+        !matches!(
+            self,
+            DataType::Boolean
+                | DataType::Real32
+                | DataType::Real64
+                | DataType::VisibleString
+                | DataType::OctetString
+                | DataType::UnicodeString
+                | DataType::TimeOfDay
+                | DataType::TimeDifference
+                | DataType::Domain
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EdsErrorKind {
+    /// The line is neither a section header, a key-value pair nor a comment.
+    MalformedLine,
+    /// A key-value pair precedes the first section.
+    KeyOutsideSection,
+    DuplicateSection,
+    MissingKey(&'static str),
+    InvalidValue(String),
+    /// The data type is valid but has no [`VariableType`].
+    UnsupportedDataType(DataType),
+    /// Sub-objects described with `CompactSubObj` are not supported.
+    CompactSubObj,
+}
+
+/// An error in an EDS or DCF file, at a 1-based line number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdsError {
+    pub line: usize,
+    pub section: Option<String>,
+    pub kind: EdsErrorKind,
+}
+
+/// A value with the line it was read from.
+#[derive(Copy, Clone, Debug)]
+struct Value<'a> {
+    value: &'a str,
+    line: usize,
+}
+
+/// An object or sub-object described in an EDS or DCF file.
+#[derive(Clone, Debug)]
+pub struct EdsObject<'a> {
+    id: EntryId,
+    section: &'a str,
+    line: usize,
+    name: &'a str,
+    parent_name: Option<&'a str>,
+    object_type: ObjectType,
+    data_type: Option<DataType>,
+    access_type: AccessType,
+    pdo_mapability: PdoMapability,
+    sub_number: Option<u8>,
+    default_value: Option<Value<'a>>,
+    parameter_value: Option<Value<'a>>,
+    low_limit: Option<Value<'a>>,
+    high_limit: Option<Value<'a>>,
+}
+
+impl<'a> EdsObject<'a> {
+    pub fn id(&self) -> EntryId {
+        self.id
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Returns the name of the array or record holding a sub-object.
+    pub fn parent_name(&self) -> Option<&'a str> {
+        self.parent_name
+    }
+
+    pub fn object_type(&self) -> ObjectType {
+        self.object_type
+    }
+
+    /// `None` for arrays and records, which have no value of their own.
+    pub fn data_type(&self) -> Option<DataType> {
+        self.data_type
+    }
+
+    pub fn access_type(&self) -> AccessType {
+        self.access_type
+    }
+
+    pub fn pdo_mapability(&self) -> PdoMapability {
+        self.pdo_mapability
+    }
+
+    /// Number of sub-objects of an array or record.
+    pub fn sub_number(&self) -> Option<u8> {
+        self.sub_number
+    }
+
+    /// Returns the value of the object: its `ParameterValue` in a DCF, else
+    /// its `DefaultValue`, else 0 or an empty string. `$NODEID` is replaced
+    /// with `node_id`.
+    pub fn value(&self, node_id: NodeId) -> Result<VariableType, EdsError> {
+        let is_string = matches!(
+            self.data_type,
+            Some(DataType::VisibleString | DataType::OctetString | DataType::UnicodeString)
+        );
+        match self.parameter_value.or(self.default_value) {
+            Some(value) => self.parse_value(value, node_id),
+            None => self.parse_value(
+                Value {
+                    value: if is_string { "" } else { "0" },
+                    line: self.line,
+                },
+                node_id,
+            ),
+        }
+    }
+
+    /// Returns the `DefaultValue` of the object, with `$NODEID` replaced with
+    /// `node_id`.
+    pub fn default_value(&self, node_id: NodeId) -> Result<Option<VariableType>, EdsError> {
+        self.default_value
+            .map(|value| self.parse_value(value, node_id))
+            .transpose()
+    }
+
+    pub fn low_limit(&self, node_id: NodeId) -> Result<Option<VariableType>, EdsError> {
+        self.low_limit
+            .map(|value| self.parse_value(value, node_id))
+            .transpose()
+    }
+
+    pub fn high_limit(&self, node_id: NodeId) -> Result<Option<VariableType>, EdsError> {
+        self.high_limit
+            .map(|value| self.parse_value(value, node_id))
+            .transpose()
+    }
+
+    /// Returns which of the limits and default value are relative to the
+    /// node-ID. The default value is the value itself if there is no
+    /// `DefaultValue`.
+    fn node_id_relative(&self) -> Result<NodeIdRelative, EdsError> {
+        Ok(NodeIdRelative {
+            low_limit: self.is_node_id_relative(self.low_limit)?,
+            high_limit: self.is_node_id_relative(self.high_limit)?,
+            default_value: self.is_node_id_relative(self.default_value.or(self.parameter_value))?,
+        })
+    }
+
+    /// Whether an integer value is `$NODEID` plus a constant. Values using
+    /// `$NODEID` otherwise are refused.
+    fn is_node_id_relative(&self, value: Option<Value>) -> Result<bool, EdsError> {
+        let Some(value) = value else {
+            return Ok(false);
+        };
+        if !self
+            .data_type
+            .is_some_and(|data_type| data_type.is_integer())
+        {
+            return Ok(false);
+        }
+        let at = |node_id| {
+            evaluate(value.value, NodeId::new(node_id).unwrap())
+                .ok_or_else(|| self.invalid_value(value))
+        };
+        match at(2)? - at(1)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(self.invalid_value(value)),
+        }
+    }
+
+    /// Returns the value as an unsigned integer, for the objects configuring
+    /// the communication.
+    fn unsigned(&self, node_id: NodeId) -> Result<u64, EdsError> {
+        let value = self.parameter_value.or(self.default_value);
+        let Some(value) = value else {
+            return Ok(0);
+        };
+        evaluate(value.value, node_id)
+            .and_then(|value| u64::try_from(value).ok())
+            .ok_or_else(|| self.invalid_value(value))
+    }
+
+    fn parse_value(&self, value: Value, node_id: NodeId) -> Result<VariableType, EdsError> {
+        let data_type = self
+            .data_type
+            .ok_or_else(|| self.error(self.line, EdsErrorKind::MissingKey("DataType")))?;
+        let invalid = || self.invalid_value(value);
+        let integer = || evaluate(value.value, node_id).ok_or_else(invalid);
+        // Integers of the widths without a matching Rust type.
+        let signed = |bits: u32| {
+            let value = integer()?;
+            let bound = 1i128 << (bits - 1);
+            (-bound..bound)
+                .contains(&value)
+                .then_some(value as i64)
+                .ok_or_else(invalid)
+        };
+        let unsigned = |bits: u32| {
+            let value = integer()?;
+            (0..1i128 << bits)
+                .contains(&value)
+                .then_some(value as u64)
+                .ok_or_else(invalid)
+        };
+        let float = || value.value.trim().parse::<f64>().map_err(|_| invalid());
+        Ok(match (self.object_type, data_type) {
+            (ObjectType::Array, DataType::Unsigned8) => {
+                VariableType::Array(integer()?.try_into().map_err(|_| invalid())?)
+            }
+            (ObjectType::Record, DataType::Unsigned8) => {
+                VariableType::Record(integer()?.try_into().map_err(|_| invalid())?)
+            }
+            (_, data_type) => match data_type {
+                DataType::Boolean => match integer()? {
+                    0 => VariableType::Boolean(false, &DefaultBooleanCoder),
+                    1 => VariableType::Boolean(true, &DefaultBooleanCoder),
+                    _ => return Err(invalid()),
+                },
+                DataType::Integer8 => VariableType::Int8(
+                    integer()?.try_into().map_err(|_| invalid())?,
+                    &DefaultI8Coder,
+                ),
+                DataType::Integer16 => VariableType::Int16(
+                    integer()?.try_into().map_err(|_| invalid())?,
+                    &DefaultI16Coder,
+                ),
+                DataType::Integer24 => VariableType::Int24(signed(24)? as i32, &DefaultI24Coder),
+                DataType::Integer32 => VariableType::Int32(
+                    integer()?.try_into().map_err(|_| invalid())?,
+                    &DefaultI32Coder,
+                ),
+                DataType::Integer40 => VariableType::Int40(signed(40)?, &DefaultI40Coder),
+                DataType::Integer48 => VariableType::Int48(signed(48)?, &DefaultI48Coder),
+                DataType::Integer56 => VariableType::Int56(signed(56)?, &DefaultI56Coder),
+                DataType::Integer64 => VariableType::Int64(
+                    integer()?.try_into().map_err(|_| invalid())?,
+                    &DefaultI64Coder,
+                ),
+                DataType::Unsigned8 => VariableType::UInt8(
+                    integer()?.try_into().map_err(|_| invalid())?,
+                    &DefaultU8Coder,
+                ),
+                DataType::Unsigned16 => VariableType::UInt16(
+                    integer()?.try_into().map_err(|_| invalid())?,
+                    &DefaultU16Coder,
+                ),
+                DataType::Unsigned24 => {
+                    VariableType::UInt24(unsigned(24)? as u32, &DefaultU24Coder)
+                }
+                DataType::Unsigned32 => VariableType::UInt32(
+                    integer()?.try_into().map_err(|_| invalid())?,
+                    &DefaultU32Coder,
+                ),
+                DataType::Unsigned40 => VariableType::UInt40(unsigned(40)?, &DefaultU40Coder),
+                DataType::Unsigned48 => VariableType::UInt48(unsigned(48)?, &DefaultU48Coder),
+                DataType::Unsigned56 => VariableType::UInt56(unsigned(56)?, &DefaultU56Coder),
+                DataType::Unsigned64 => VariableType::UInt64(
+                    integer()?.try_into().map_err(|_| invalid())?,
+                    &DefaultU64Coder,
+                ),
+                DataType::Real32 => VariableType::Float32(float()? as f32, &DefaultF32Coder),
+                DataType::Real64 => VariableType::Float64(float()?, &DefaultF64Coder),
+                DataType::VisibleString => VariableType::VisibleString(
+                    FixedBytes::from_slice(value.value.as_bytes()).ok_or_else(invalid)?,
+                ),
+                // Octet and unicode strings are written as hexadecimal bytes.
+                DataType::OctetString => {
+                    VariableType::OctetString(parse_hex_bytes(value.value).ok_or_else(invalid)?)
+                }
+                DataType::UnicodeString => VariableType::UnicodeString(
+                    parse_hex_bytes(value.value)
+                        .filter(|bytes| bytes.len() % 2 == 0)
+                        .ok_or_else(invalid)?,
+                ),
+                data_type => {
+                    return Err(self.error(self.line, EdsErrorKind::UnsupportedDataType(data_type)))
+                }
+            },
+        })
+    }
+
+    fn invalid_value(&self, value: Value) -> EdsError {
+        self.error(value.line, EdsErrorKind::InvalidValue(value.value.into()))
+    }
+
+    fn error(&self, line: usize, kind: EdsErrorKind) -> EdsError {
+        EdsError {
+            line,
+            section: Some(self.section.into()),
+            kind,
+        }
+    }
+}
+
+/// The objects described by an EDS or DCF file.
+#[derive(Clone, Debug)]
+pub struct Eds<'a> {
+    objects: BTreeMap<EntryId, EdsObject<'a>>,
+    node_id: Option<NodeId>,
+}
+
+impl<'a> Eds<'a> {
+    pub fn parse(input: &'a str) -> Result<Self, EdsError> {
+        let sections = parse_sections(input)?;
+        let mut objects = BTreeMap::new();
+        let mut node_id = None;
+        for section in &sections {
+            if section.name.eq_ignore_ascii_case("DeviceComissioning") {
+                if let Some(value) = section.get("NodeID") {
+                    node_id = Some(
+                        evaluate(value.value, NodeId::default())
+                            .and_then(|id| u8::try_from(id).ok())
+                            .and_then(NodeId::new)
+                            .ok_or_else(|| section.invalid_value(value))?,
+                    );
+                }
+                continue;
+            }
+            let Some((index, sub_index)) = parse_object_name(section.name) else {
+                continue;
+            };
+            let parent = sub_index.and_then(|_| {
+                sections
+                    .iter()
+                    .find(|parent| parse_object_name(parent.name) == Some((index, None)))
+            });
+            let object = section.to_object(EntryId::new(index, sub_index.unwrap_or(0)), parent)?;
+            if let Some(object) = object {
+                objects.insert(object.id, object);
+            }
+        }
+        Ok(Self { objects, node_id })
+    }
+
+    /// Returns the node-ID of the `[DeviceComissioning]` section of a DCF.
+    pub fn node_id(&self) -> Option<NodeId> {
+        self.node_id
+    }
+
+    /// Returns the objects holding a value, by increasing index and
+    /// sub-index. Arrays and records are represented by their sub-objects.
+    pub fn objects(&self) -> impl Iterator<Item = &EdsObject<'a>> {
+        self.objects.values()
+    }
+
+    pub fn object(&self, id: EntryId) -> Option<&EdsObject<'a>> {
+        self.objects.get(&id)
+    }
+
+    /// Builds the configuration of the first `N` RPDOs from 0x1400 and 0x1600.
+    /// PDOs missing from the file are disabled.
+    pub fn rpdo_configurations<const N: usize>(
+        &self,
+        node_id: NodeId,
+    ) -> Result<[PdoConfiguration; N], EdsError> {
+        self.pdo_configurations(RPDO_COMMUNICATION_INDEX, RPDO_MAPPING_INDEX, node_id)
+    }
+
+    /// Builds the configuration of the first `N` TPDOs from 0x1800 and 0x1A00.
+    /// PDOs missing from the file are disabled.
+    pub fn tpdo_configurations<const N: usize>(
+        &self,
+        node_id: NodeId,
+    ) -> Result<[PdoConfiguration; N], EdsError> {
+        self.pdo_configurations(TPDO_COMMUNICATION_INDEX, TPDO_MAPPING_INDEX, node_id)
+    }
+
+    fn pdo_configurations<const N: usize>(
+        &self,
+        communication_index: u16,
+        mapping_index: u16,
+        node_id: NodeId,
+    ) -> Result<[PdoConfiguration; N], EdsError> {
+        let mut configurations = [PdoConfiguration::default(); N];
+        for (pdo, configuration) in configurations.iter_mut().enumerate() {
+            if let Some(found) = self.pdo_configuration(
+                communication_index + pdo as u16,
+                mapping_index + pdo as u16,
+                node_id,
+            )? {
+                *configuration = found;
+            }
+        }
+        Ok(configurations)
+    }
+
+    /// Returns `None` if the file has no COB-ID for the PDO.
+    fn pdo_configuration(
+        &self,
+        communication: u16,
+        mapping: u16,
+        node_id: NodeId,
+    ) -> Result<Option<PdoConfiguration>, EdsError> {
+        let cob_id_id = EntryId::new(communication, 1);
+        let Some(cob_id) = self.unsigned(cob_id_id, node_id)? else {
+            return Ok(None);
+        };
+        let cob_id_object = &self.objects[&cob_id_id];
+        let cob_id_node_id_relative = cob_id_object.is_node_id_relative(
+            cob_id_object
+                .parameter_value
+                .or(cob_id_object.default_value),
+        )?;
+        let transmission_type = match self.unsigned(EntryId::new(communication, 2), node_id)? {
+            Some(n @ 0..=240) => PdoTransmissionType::Synchronous(n as u8),
+            _ => PdoTransmissionType::EventDriven,
+        };
+        let inhibit_time = self.unsigned(EntryId::new(communication, 3), node_id)?;
+        let event_timer = self.unsigned(EntryId::new(communication, 5), node_id)?;
+
+        let count = self
+            .unsigned(EntryId::new(mapping, 0), node_id)?
+            .unwrap_or(0);
+        let mut mappings = [PdoEntryMapping::default(); 8];
+        for (sub_index, entry) in (1..=count.min(8) as u8).zip(mappings.iter_mut()) {
+            let raw = self
+                .unsigned(EntryId::new(mapping, sub_index), node_id)?
+                .unwrap_or(0);
+            *entry = PdoEntryMapping::new((raw >> 16) as u16, (raw >> 8) as u8, raw as u8);
+        }
+
+        let configuration = PdoConfiguration::new(
+            CobId::from_raw(cob_id as u32),
+            transmission_type,
+            count as u8,
+            mappings,
+            event_timer.unwrap_or(0) as u16,
+        )
+        .with_inhibit_time(inhibit_time.unwrap_or(0) as u16);
+        if cob_id_node_id_relative {
+            return Ok(Some(configuration.with_node_id_relative_cob_id()));
+        }
+        Ok(Some(configuration))
+    }
+
+    fn unsigned(&self, id: EntryId, node_id: NodeId) -> Result<Option<u64>, EdsError> {
+        self.objects
+            .get(&id)
+            .map(|object| object.unsigned(node_id))
+            .transpose()
+    }
+}
+
+impl Eds<'static> {
+    /// Builds the variables of an object dictionary for the node `node_id`,
+    /// leaving out the objects managed by the object dictionary itself: the
+    /// error register, the pre-defined error field and the PDO parameters.
+    ///
+    /// DOMAIN objects are left out as well, see
+    /// [`Self::variables_with_domains`].
+    pub fn variables(&self, node_id: NodeId) -> Result<Vec<Variable>, EdsError> {
+        self.variables_with_domains(node_id, |_| None)
+    }
+
+    /// Builds the variables like [`Self::variables`], streaming each DOMAIN
+    /// object through the handler returned by `domains`. DOMAIN objects
+    /// without a handler are left out.
+    pub fn variables_with_domains(
+        &self,
+        node_id: NodeId,
+        domains: impl Fn(EntryId) -> Option<&'static dyn DomainHandler>,
+    ) -> Result<Vec<Variable>, EdsError> {
+        self.objects()
+            .filter(|object| !is_managed_by_od(object.id.index()))
+            .filter_map(|object| {
+                let value = match object.data_type() {
+                    Some(DataType::Domain) => VariableType::Domain(domains(object.id)?),
+                    _ => match object.value(node_id) {
+                        Ok(value) => value,
+                        Err(error) => return Some(Err(error)),
+                    },
+                };
+                Some(object.variable(node_id, value))
+            })
+            .collect()
+    }
+}
+
+impl EdsObject<'static> {
+    /// Builds the variable of the object, holding `value`.
+    fn variable(&self, node_id: NodeId, value: VariableType) -> Result<Variable, EdsError> {
+        let mut builder = Variable::builder(self.id)
+            .name(self.name)
+            .access(self.access_type())
+            .pdo(self.pdo_mapability);
+        if let VariableType::Domain(_) = value {
+            // The value of a domain is only known to its handler.
+            return Ok(builder
+                .value(value)
+                .expect("domains hold no value to check"));
+        }
+        if let Some(low_limit) = self.low_limit(node_id)? {
+            builder = builder.low_limit(low_limit);
+        }
+        if let Some(high_limit) = self.high_limit(node_id)? {
+            builder = builder.high_limit(high_limit);
+        }
+        if let Some(default_value) = self.default_value(node_id)? {
+            builder = builder.default_value(default_value);
+        }
+        builder = builder.node_id_relative(self.node_id_relative()?);
+        Ok(builder
+            .value(value)
+            .expect("values are checked when parsed"))
+    }
+}
+
+struct Section<'a> {
+    name: &'a str,
+    line: usize,
+    entries: Vec<(&'a str, Value<'a>)>,
+}
+
+impl<'a> Section<'a> {
+    fn get(&self, key: &str) -> Option<Value<'a>> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, value)| *value)
+    }
+
+    fn require(&self, key: &'static str) -> Result<Value<'a>, EdsError> {
+        self.get(key)
+            .ok_or_else(|| self.error(self.line, EdsErrorKind::MissingKey(key)))
+    }
+
+    /// Parses an integer value, which is not relative to the node-ID.
+    fn integer<T: TryFrom<i128>>(&self, key: &'static str) -> Result<Option<T>, EdsError> {
+        self.get(key)
+            .map(|value| {
+                parse_integer(value.value)
+                    .and_then(|integer| T::try_from(integer).ok())
+                    .ok_or_else(|| self.invalid_value(value))
+            })
+            .transpose()
+    }
+
+    fn to_object(
+        &self,
+        id: EntryId,
+        parent: Option<&Section<'a>>,
+    ) -> Result<Option<EdsObject<'a>>, EdsError> {
+        if self
+            .get("CompactSubObj")
+            .is_some_and(|value| value.value.trim() != "0")
+        {
+            return Err(self.error(self.line, EdsErrorKind::CompactSubObj));
+        }
+        let name = self.require("ParameterName")?.value;
+        let object_type = match self.integer::<u8>("ObjectType")? {
+            None => ObjectType::Var,
+            Some(raw) => ObjectType::from_u8(raw)
+                .ok_or_else(|| self.invalid_value(self.get("ObjectType").unwrap()))?,
+        };
+        let sub_number = self.integer::<u8>("SubNumber")?;
+        if matches!(object_type, ObjectType::Array | ObjectType::Record) {
+            // Described by its sub-objects.
+            if sub_number.is_none() {
+                return Err(self.error(self.line, EdsErrorKind::MissingKey("SubNumber")));
+            }
+            return Ok(None);
+        }
+        if matches!(
+            object_type,
+            ObjectType::Null | ObjectType::DefType | ObjectType::DefStruct
+        ) {
+            return Ok(None);
+        }
+
+        let data_type_value = self.require("DataType")?;
+        let data_type = self
+            .integer::<u16>("DataType")?
+            .and_then(DataType::from_u16)
+            .ok_or_else(|| self.invalid_value(data_type_value))?;
+        let access_value = self.require("AccessType")?;
+        let access_type = match access_value.value.trim().to_ascii_lowercase().as_str() {
+            "ro" => AccessType::ReadOnly,
+            "wo" => AccessType::WriteOnly,
+            "rw" => AccessType::ReadWrite,
+            "rwr" => AccessType::ReadWriteTpdo,
+            "rww" => AccessType::ReadWriteRpdo,
+            "const" => AccessType::Const,
+            _ => return Err(self.invalid_value(access_value)),
+        };
+        let pdo_mapability = match self.integer::<u8>("PDOMapping")? {
+            None | Some(0) => PdoMapability::None,
+            Some(1) => access_type.pdo_mapability(),
+            _ => return Err(self.invalid_value(self.get("PDOMapping").unwrap())),
+        };
+
+        // The number of sub-objects of an array or record is held by its
+        // sub-index 0.
+        let parent_type = match parent {
+            Some(parent) if id.sub_index() == 0 => parent.integer::<u8>("ObjectType")?,
+            _ => None,
+        };
+        let object = EdsObject {
+            id,
+            section: self.name,
+            line: self.line,
+            name,
+            parent_name: match parent {
+                Some(parent) => Some(parent.require("ParameterName")?.value),
+                None => None,
+            },
+            object_type: match parent_type.and_then(ObjectType::from_u8) {
+                Some(parent_type @ (ObjectType::Array | ObjectType::Record)) => parent_type,
+                _ => object_type,
+            },
+            data_type: Some(data_type),
+            access_type,
+            pdo_mapability,
+            sub_number,
+            default_value: self.get("DefaultValue"),
+            parameter_value: self.get("ParameterValue"),
+            low_limit: self.get("LowLimit"),
+            high_limit: self.get("HighLimit"),
+        };
+
+        // Check the values now to report errors where they are. Data types
+        // without a variable type are reported when building the variables.
+        let check = |result: Result<(), EdsError>| match result {
+            Err(EdsError {
+                kind: EdsErrorKind::UnsupportedDataType(_),
+                ..
+            }) => Ok(()),
+            result => result,
+        };
+        let node_id = NodeId::default();
+        check(object.value(node_id).map(|_| ()))?;
+        check(object.low_limit(node_id).map(|_| ()))?;
+        check(object.high_limit(node_id).map(|_| ()))?;
+        Ok(Some(object))
+    }
+
+    fn invalid_value(&self, value: Value) -> EdsError {
+        self.error(value.line, EdsErrorKind::InvalidValue(value.value.into()))
+    }
+
+    fn error(&self, line: usize, kind: EdsErrorKind) -> EdsError {
+        EdsError {
+            line,
+            section: Some(self.name.into()),
+            kind,
+        }
+    }
+}
+
+fn parse_sections(input: &str) -> Result<Vec<Section<'_>>, EdsError> {
+    let mut sections: Vec<Section> = Vec::new();
+    for (line, text) in input.lines().enumerate() {
+        let line = line + 1;
+        let text = text.trim();
+        let error = |sections: &Vec<Section>, kind| EdsError {
+            line,
+            section: sections.last().map(|section| section.name.into()),
+            kind,
+        };
+        if text.is_empty() || text.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = text.strip_prefix('[') {
+            let Some(name) = name.strip_suffix(']') else {
+                return Err(error(&sections, EdsErrorKind::MalformedLine));
+            };
+            let name = name.trim();
+            if sections
+                .iter()
+                .any(|section| section.name.eq_ignore_ascii_case(name))
+            {
+                return Err(EdsError {
+                    line,
+                    section: Some(name.into()),
+                    kind: EdsErrorKind::DuplicateSection,
+                });
+            }
+            sections.push(Section {
+                name,
+                line,
+                entries: Vec::new(),
+            });
+            continue;
+        }
+        let Some((key, value)) = text.split_once('=') else {
+            return Err(error(&sections, EdsErrorKind::MalformedLine));
+        };
+        let Some(section) = sections.last_mut() else {
+            return Err(error(&sections, EdsErrorKind::KeyOutsideSection));
+        };
+        section.entries.push((
+            key.trim(),
+            Value {
+                value: value.trim(),
+                line,
+            },
+        ));
+    }
+    Ok(sections)
+}
+
+/// Parses an object section name, `1000` or `1000sub0`, in hexadecimal.
+fn parse_object_name(name: &str) -> Option<(u16, Option<u8>)> {
+    fn hex(digits: &str, max_len: usize) -> Option<&str> {
+        (!digits.is_empty()
+            && digits.len() <= max_len
+            && digits.bytes().all(|b| b.is_ascii_hexdigit()))
+        .then_some(digits)
+    }
+    let index = u16::from_str_radix(hex(name.get(..4)?, 4)?, 16).ok()?;
+    match name.get(4..)? {
+        "" => Some((index, None)),
+        rest if rest.len() > 3 && rest[..3].eq_ignore_ascii_case("sub") => {
+            let sub_index = u8::from_str_radix(hex(&rest[3..], 2)?, 16).ok()?;
+            Some((index, Some(sub_index)))
+        }
+        _ => None,
+    }
+}
+
+/// Parses an integer in decimal, hexadecimal with a `0x` prefix or octal with
+/// a `0` prefix, optionally negative.
+fn parse_integer(text: &str) -> Option<i128> {
+    let text = text.trim();
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let magnitude = if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        i128::from_str_radix(hex, 16).ok()?
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        i128::from_str_radix(&digits[1..], 8).ok()?
+    } else {
+        digits.parse::<i128>().ok()?
+    };
+    Some(if negative { -magnitude } else { magnitude })
+}
+
+/// Parses bytes written as pairs of hexadecimal digits, such as `0A 1B2C`.
+fn parse_hex_bytes(text: &str) -> Option<FixedBytes> {
+    let digits: Vec<u8> = text.bytes().filter(|c| !c.is_ascii_whitespace()).collect();
+    if !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    let bytes = digits
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    FixedBytes::from_slice(&bytes)
+}
+
+/// Evaluates a sum of integers and `$NODEID`, such as `$NODEID+0x180`.
+fn evaluate(expression: &str, node_id: NodeId) -> Option<i128> {
+    let expression = expression.trim();
+    let (first, mut rest) = match expression.strip_prefix('-') {
+        // A leading minus sign belongs to the first term.
+        Some(rest) => {
+            let end = rest.find(['+', '-']).unwrap_or(rest.len());
+            (-term(&rest[..end], node_id)?, &rest[end..])
+        }
+        None => {
+            let end = expression.find(['+', '-']).unwrap_or(expression.len());
+            (term(&expression[..end], node_id)?, &expression[end..])
+        }
+    };
+    let mut total = first;
+    while let Some(operator) = rest.chars().next() {
+        rest = &rest[1..];
+        let end = rest.find(['+', '-']).unwrap_or(rest.len());
+        let value = term(&rest[..end], node_id)?;
+        total = if operator == '+' {
+            total.checked_add(value)?
+        } else {
+            total.checked_sub(value)?
+        };
+        rest = &rest[end..];
+    }
+    Some(total)
+}
+
+fn term(text: &str, node_id: NodeId) -> Option<i128> {
+    let text = text.trim();
+    if text.eq_ignore_ascii_case("$NODEID") {
+        Some(node_id.raw() as i128)
+    } else if text.starts_with('-') {
+        None
+    } else {
+        parse_integer(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use heapless::Vec;
+
+    use crate::node::NodeId;
+    use crate::object_dictionary::{
+        AccessType, DomainHandler, EntryId, ObjectDictionary, PdoMapability, VariableType,
+    };
+    use crate::pdo::PdoTransmissionType;
+    use crate::sdo::SdoAbortCode;
+
+    use super::{evaluate, DataType, Eds, EdsError, EdsErrorKind, ObjectType};
+
+    const EDS: &str = "\
+[FileInfo]
+FileName=test.eds
+
+[DeviceInfo]
+VendorName=Test
+
+[1001]
+ParameterName=Error register
+DataType=0x0005
+AccessType=ro
+PDOMapping=1
+
+[1016]
+ParameterName=Consumer heartbeat time
+ObjectType=0x8
+SubNumber=2
+
+[1016sub0]
+ParameterName=Highest sub-index supported
+DataType=0x0005
+AccessType=const
+DefaultValue=1
+
+[1016sub1]
+ParameterName=Consumer heartbeat time 1
+DataType=0x0007
+AccessType=rw
+DefaultValue=0x00090064
+
+[1800]
+ParameterName=TPDO communication parameter 1
+ObjectType=0x9
+SubNumber=3
+
+[1800sub0]
+ParameterName=Highest sub-index supported
+DataType=0x0005
+AccessType=const
+DefaultValue=5
+
+[1800sub1]
+ParameterName=COB-ID
+DataType=0x0007
+AccessType=rw
+DefaultValue=$NODEID+0x180
+
+[1800sub2]
+ParameterName=Transmission type
+DataType=0x0005
+AccessType=rw
+DefaultValue=254
+
+[1A00]
+ParameterName=TPDO mapping parameter 1
+ObjectType=0x9
+SubNumber=2
+
+[1A00sub0]
+ParameterName=Number of mapped objects
+DataType=0x0005
+AccessType=rw
+DefaultValue=1
+
+[1A00sub1]
+ParameterName=Mapping 1
+DataType=0x0007
+AccessType=rw
+DefaultValue=0x20000010
+
+; A comment
+[2000]
+ParameterName=Setpoint
+ObjectType=0x7
+DataType=0x0003
+AccessType=rww
+PDOMapping=1
+DefaultValue=-010
+LowLimit=-100
+HighLimit=0x64
+
+[2001]
+ParameterName=Gain
+DataType=0x0008
+AccessType=rw
+DefaultValue=1.5
+ParameterValue=2.25
+";
+
+    fn error(input: &str) -> EdsError {
+        Eds::parse(input).err().unwrap()
+    }
+
+    #[test]
+    fn test_expressions() {
+        let node_id = NodeId::new(5).unwrap();
+        assert_eq!(evaluate("$NODEID+0x180", node_id), Some(0x185));
+        assert_eq!(evaluate("0x600 + $nodeid", node_id), Some(0x605));
+        assert_eq!(evaluate("-0x10", node_id), Some(-16));
+        assert_eq!(evaluate("010", node_id), Some(8));
+        assert_eq!(evaluate("$NODEID-1", node_id), Some(4));
+        assert_eq!(evaluate("1+", node_id), None);
+        assert_eq!(evaluate("0x", node_id), None);
+    }
+
+    #[test]
+    fn test_objects() {
+        let eds = Eds::parse(EDS).unwrap();
+        let ids: std::vec::Vec<_> = eds.objects().map(|object| object.id()).collect();
+        assert_eq!(ids.len(), 10);
+
+        let sub0 = eds.object(EntryId::new(0x1016, 0)).unwrap();
+        assert_eq!(sub0.object_type(), ObjectType::Array);
+        assert_eq!(sub0.access_type(), AccessType::Const);
+
+        let setpoint = eds.object(EntryId::new(0x2000, 0)).unwrap();
+        assert_eq!(setpoint.name(), "Setpoint");
+        assert_eq!(setpoint.data_type(), Some(DataType::Integer16));
+        assert_eq!(setpoint.access_type(), AccessType::ReadWriteRpdo);
+        assert_eq!(setpoint.pdo_mapability(), PdoMapability::Rpdo);
+        assert_eq!(
+            eds.object(EntryId::new(0x1001, 0))
+                .unwrap()
+                .pdo_mapability(),
+            PdoMapability::Tpdo
+        );
+    }
+
+    #[test]
+    fn test_object_dictionary() {
+        let eds = Eds::parse(EDS).unwrap();
+        let node_id = NodeId::new(5).unwrap();
+        let variables = eds.variables(node_id).unwrap();
+        assert_eq!(variables.len(), 4);
+
+        let mut od: ObjectDictionary<4, 0, 1> = ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            eds.tpdo_configurations(node_id).unwrap(),
+            [],
+            Vec::from_slice(&variables).ok().unwrap(),
+            node_id,
+        );
+        assert_eq!(od.read_unsigned(EntryId::new(0x1016, 0)), Some(1));
+        assert_eq!(od.read_unsigned(EntryId::new(0x1016, 1)), Some(0x0009_0064));
+        assert_eq!(od.read_unsigned(EntryId::new(0x2000, 0)), Some(0xFFF8));
+        assert_eq!(
+            od.read_unsigned(EntryId::new(0x2001, 0)),
+            Some(2.25f32.to_bits() as u64)
+        );
+
+        let tpdo = od.tpdo_configuration(0).unwrap();
+        assert_eq!(tpdo.cob_id().raw(), 0x185);
+        assert_eq!(tpdo.transmission_type(), PdoTransmissionType::EventDriven);
+        assert_eq!(tpdo.mappings().len(), 1);
+        assert_eq!(tpdo.mappings()[0].entry_id(), EntryId::new(0x2000, 0));
+        assert_eq!(tpdo.mappings()[0].length(), 16);
+
+        let setpoint = EntryId::new(0x2000, 0);
+        assert_eq!(
+            od.write_raw(setpoint, &101i16.to_le_bytes()),
+            Err(SdoAbortCode::ValueTooHigh)
+        );
+        assert_eq!(
+            od.write_raw(setpoint, &(-101i16).to_le_bytes()),
+            Err(SdoAbortCode::ValueTooLow)
+        );
+        assert_eq!(od.write_raw(setpoint, &100i16.to_le_bytes()), Ok(()));
+        od.restore_default(setpoint).unwrap();
+        assert_eq!(od.read_unsigned(setpoint), Some(0xFFF8));
+    }
+
+    #[test]
+    fn test_strings() {
+        let eds = Eds::parse(
+            "[1008]\nParameterName=Device name\nDataType=0x0009\nAccessType=const\nDefaultValue=Pump\n\
+             [2002]\nParameterName=Key\nDataType=0x000A\nAccessType=rw\nDefaultValue=01 ab FF\n",
+        )
+        .unwrap();
+        let variables = eds.variables(NodeId::new(1).unwrap()).unwrap();
+        assert_eq!(variables[0].read_raw().unwrap().as_slice(), b"Pump");
+        assert_eq!(
+            variables[1].read_raw().unwrap().as_slice(),
+            &[0x01, 0xAB, 0xFF]
+        );
+
+        assert_eq!(
+            error("[2002]\nParameterName=Key\nDataType=0x000A\nAccessType=rw\nDefaultValue=0x1\n")
+                .kind,
+            EdsErrorKind::InvalidValue("0x1".into())
+        );
+    }
+
+    #[test]
+    fn test_odd_width_integers() {
+        let variable = |data_type, value| {
+            // Variables borrow their names from the file.
+            Eds::parse(format!(
+                "[2000]\nParameterName=Position\nDataType={data_type}\nAccessType=rw\nDefaultValue={value}\n"
+            ).leak())
+            .map(|eds| eds.variables(NodeId::new(1).unwrap()).unwrap()[0])
+        };
+        let raw = |data_type, value| variable(data_type, value).unwrap().read_raw().unwrap();
+        assert_eq!(raw("0x0010", "-2"), [0xFE, 0xFF, 0xFF]);
+        assert_eq!(raw("0x0016", "0xFFFFFF"), [0xFF, 0xFF, 0xFF]);
+        assert_eq!(raw("0x0013", "-0x800000000000"), [0, 0, 0, 0, 0, 0x80]);
+        assert_eq!(raw("0x001A", "0x01020304050607"), [7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(
+            variable("0x0010", "0x800000").err().unwrap().kind,
+            EdsErrorKind::InvalidValue("0x800000".into())
+        );
+        assert_eq!(
+            variable("0x0018", "-1").err().unwrap().kind,
+            EdsErrorKind::InvalidValue("-1".into())
+        );
+    }
+
+    #[test]
+    fn test_dcf_node_id() {
+        let eds = Eds::parse("[DeviceComissioning]\nNodeID=0x20\n").unwrap();
+        assert_eq!(eds.node_id(), NodeId::new(0x20));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            error(
+                "[2000]\nParameterName=Value\nDataType=0x0005\nAccessType=rw\nDefaultValue=256\n"
+            ),
+            EdsError {
+                line: 5,
+                section: Some("2000".into()),
+                kind: EdsErrorKind::InvalidValue("256".into()),
+            }
+        );
+        assert_eq!(
+            error("[2000]\nParameterName=Value\nAccessType=rw\n"),
+            EdsError {
+                line: 1,
+                section: Some("2000".into()),
+                kind: EdsErrorKind::MissingKey("DataType"),
+            }
+        );
+        assert_eq!(
+            error("[FileInfo]\nFileName\n"),
+            EdsError {
+                line: 2,
+                section: Some("FileInfo".into()),
+                kind: EdsErrorKind::MalformedLine,
+            }
+        );
+        assert_eq!(
+            error("[1000]\n[1000]\n").kind,
+            EdsErrorKind::DuplicateSection
+        );
+        assert_eq!(error("Key=1\n").kind, EdsErrorKind::KeyOutsideSection);
+    }
+
+    /// A domain holding nothing.
+    struct EmptyDomain;
+
+    impl DomainHandler for EmptyDomain {
+        fn size(&self) -> usize {
+            0
+        }
+
+        fn capacity(&self) -> usize {
+            0
+        }
+
+        fn read(&self, _offset: usize, _buf: &mut [u8]) -> Result<(), SdoAbortCode> {
+            Ok(())
+        }
+
+        fn write(&self, _offset: usize, _data: &[u8]) -> Result<(), SdoAbortCode> {
+            Err(SdoAbortCode::TooLong)
+        }
+
+        fn finish_write(&self, _size: usize) -> Result<(), SdoAbortCode> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_domains() {
+        static FIRMWARE: EmptyDomain = EmptyDomain;
+        let eds = Eds::parse(
+            "[1F50]\nParameterName=Program data\nDataType=0x000F\nAccessType=rw\nDefaultValue=\n\n\
+             [2000]\nParameterName=Value\nDataType=0x0005\nAccessType=rw\n",
+        )
+        .unwrap();
+        let node_id = NodeId::new(1).unwrap();
+        let variables = eds.variables(node_id).unwrap();
+        assert_eq!(variables.len(), 1);
+        assert_eq!(variables[0].id(), EntryId::new(0x2000, 0));
+
+        let variables = eds
+            .variables_with_domains(node_id, |id| {
+                (id == EntryId::new(0x1F50, 0)).then_some(&FIRMWARE as &'static dyn DomainHandler)
+            })
+            .unwrap();
+        assert_eq!(variables.len(), 2);
+        assert!(matches!(variables[0].value(), VariableType::Domain(_)));
+        assert_eq!(variables[0].name(), "Program data");
+    }
+}
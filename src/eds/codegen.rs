@@ -0,0 +1,744 @@
+//! Generates the object dictionary described by an EDS or DCF file as Rust
+//! code, for devices that cannot parse it at runtime.
+//!
+//! The generator is meant to be called from a build script:
+//!
+//! ```no_run
+//! // build.rs
+//! let out_dir = std::env::var("OUT_DIR").unwrap();
+//! canopen::eds::codegen::generate_file("device.eds", format!("{out_dir}/od.rs")).unwrap();
+//! ```
+//!
+//! ```ignore
+//! // main.rs
+//! mod od {
+//!     include!(concat!(env!("OUT_DIR"), "/od.rs"));
+//! }
+//! use od::Accessors;
+//!
+//! let mut od = od::object_dictionary(NodeId::new(5).unwrap());
+//! od.set_motor_current(120)?;
+//! ```
+//!
+//! The generated module holds:
+//! - `ENTRY_COUNT`, `RPDO_COUNT` and `TPDO_COUNT`, the sizes of the object
+//!   dictionary, and an `ObjectDictionary` type alias using them.
+//! - `OBJECT_DICTIONARY`, a `static` holding the object dictionary built for
+//!   the `NodeId` of the file (or the default one if it has none).
+//! - `object_dictionary(node_id)`, building the object dictionary with its
+//!   default values, `$NODEID` being replaced with `node_id`.
+//! - The `Accessors` trait, with a typed getter and setter for each entry,
+//!   named after its `ParameterName`.
+//!
+//! The `static` is a `thread_local!`, as the parameter coders of the entries
+//! are not `Sync`. [`Node::new`](crate::node::Node::new) takes the object
+//! dictionary by value, so an application running a node calls
+//! `object_dictionary` instead. Either way the sizes are fixed at compile
+//! time, no EDS is parsed and nothing is allocated on the device.
+//!
+//! DOMAIN objects are left out, as their handlers belong to the application.
+//! It can build the object dictionary at runtime with
+//! [`Eds::variables_with_domains`] instead.
+
+use std::{collections::BTreeSet, fmt::Write, io, path::Path, string::String, vec::Vec};
+
+use crate::{
+    node::NodeId,
+    object_dictionary::{AccessType, NodeIdRelative, PdoMapability, VariableType},
+    pdo::{PdoConfiguration, PdoTransmissionType},
+};
+
+use super::{
+    evaluate, is_managed_by_od, DataType, Eds, EdsError, EdsObject, Value,
+    RPDO_COMMUNICATION_INDEX, RPDO_MAPPING_INDEX, TPDO_COMMUNICATION_INDEX, TPDO_MAPPING_INDEX,
+};
+
+/// Highest number of PDOs of each direction.
+const MAX_PDO_COUNT: u16 = 512;
+
+/// Expression of the node-ID in the generated code.
+const NODE_ID: &str = "node_id.raw() as i128";
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "try",
+    "type", "unsafe", "use", "where", "while", "yield",
+];
+
+#[derive(Debug)]
+pub enum CodegenError {
+    Io(io::Error),
+    Eds(EdsError),
+}
+
+impl From<io::Error> for CodegenError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<EdsError> for CodegenError {
+    fn from(error: EdsError) -> Self {
+        Self::Eds(error)
+    }
+}
+
+/// Generates the code for the file at `eds_path` into `output`, and asks
+/// Cargo to run the build script again when the file changes.
+pub fn generate_file(
+    eds_path: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+) -> Result<(), CodegenError> {
+    let eds_path = eds_path.as_ref();
+    println!("cargo:rerun-if-changed={}", eds_path.display());
+    let input = std::fs::read_to_string(eds_path)?;
+    let code = generate(&Eds::parse(&input)?)?;
+    std::fs::write(output, code)?;
+    Ok(())
+}
+
+/// Generates the code of the object dictionary described by `eds`.
+pub fn generate(eds: &Eds) -> Result<String, EdsError> {
+    let objects: Vec<&EdsObject> = eds
+        .objects()
+        .filter(|object| !is_managed_by_od(object.id.index()))
+        .filter(|object| object.data_type() != Some(DataType::Domain))
+        .collect();
+    let mut variables = Vec::new();
+    for object in &objects {
+        variables.push(variable_code(object)?);
+    }
+    let rpdos = pdo_codes(eds, RPDO_COMMUNICATION_INDEX, RPDO_MAPPING_INDEX)?;
+    let tpdos = pdo_codes(eds, TPDO_COMMUNICATION_INDEX, TPDO_MAPPING_INDEX)?;
+
+    let mut body = String::from("    let variables = [\n");
+    for variable in &variables {
+        writeln!(body, "        {variable},").unwrap();
+    }
+    body.push_str("    ];\n");
+    for (name, pdos) in [("rpdos", &rpdos), ("tpdos", &tpdos)] {
+        writeln!(body, "    let {name} = [").unwrap();
+        for pdo in pdos {
+            writeln!(body, "        {pdo},").unwrap();
+        }
+        body.push_str("    ];\n");
+    }
+
+    let mut code = String::new();
+    code.push_str("// Generated by canopen::eds::codegen. Do not edit.\n\n");
+    writeln!(code, "pub const ENTRY_COUNT: usize = {};", objects.len()).unwrap();
+    writeln!(code, "pub const RPDO_COUNT: usize = {};", rpdos.len()).unwrap();
+    writeln!(code, "pub const TPDO_COUNT: usize = {};", tpdos.len()).unwrap();
+    let node_id = match eds.node_id() {
+        Some(node_id) => format!("::canopen::node::NodeId::new({}).unwrap()", node_id.raw()),
+        None => "::canopen::node::NodeId::default()".into(),
+    };
+    write!(
+        code,
+        "
+pub type ObjectDictionary = ::canopen::object_dictionary::ObjectDictionary<
+    ENTRY_COUNT,
+    RPDO_COUNT,
+    TPDO_COUNT,
+>;
+
+::std::thread_local! {{
+    /// The object dictionary, built on first use for the node-ID of the file.
+    pub static OBJECT_DICTIONARY: ::core::cell::RefCell<ObjectDictionary> =
+        ::core::cell::RefCell::new(object_dictionary({node_id}));
+}}
+
+/// Builds the object dictionary of the node `node_id`.
+#[allow(unused_variables)]
+pub fn object_dictionary(node_id: ::canopen::node::NodeId) -> ObjectDictionary {{
+{imports}
+{body}",
+        imports = imports(&body),
+    )
+    .unwrap();
+    code.push_str(
+        "    ObjectDictionary::new(
+        0,
+        0,
+        [0; 8],
+        tpdos,
+        rpdos,
+        variables.into_iter().collect(),
+        node_id,
+    )
+}
+
+/// Typed access to the entries of the object dictionary.
+#[allow(dead_code)]
+pub trait Accessors {
+",
+    );
+    let accessors = accessors(&objects)?;
+    for accessor in &accessors {
+        writeln!(
+            code,
+            "    fn {}(&self) -> Option<{}>;",
+            accessor.name, accessor.value_type
+        )
+        .unwrap();
+        writeln!(
+            code,
+            "    fn set_{}(&mut self, value: {}) -> Result<(), ::canopen::sdo::SdoAbortCode>;",
+            accessor.name, accessor.value_type
+        )
+        .unwrap();
+    }
+    code.push_str(
+        "}
+
+impl Accessors for ObjectDictionary {
+",
+    );
+    for accessor in &accessors {
+        write!(
+            code,
+            "    fn {name}(&self) -> Option<{value_type}> {{
+        let id = ::canopen::object_dictionary::EntryId::new({index:#06X}, {sub_index:#04X});
+        match self.get_variable(id)?.value() {{
+            ::canopen::object_dictionary::VariableType::{variant}(value, _) => Some(value),
+            _ => None,
+        }}
+    }}
+
+    fn set_{name}(&mut self, value: {value_type}) -> Result<(), ::canopen::sdo::SdoAbortCode> {{
+        let id = ::canopen::object_dictionary::EntryId::new({index:#06X}, {sub_index:#04X});
+        self.get_mut_variable(id)
+            .ok_or(::canopen::sdo::SdoAbortCode::ObjectDoesNotExist)?
+            .set_value(::canopen::object_dictionary::VariableType::{variant}(
+                value,
+                &::canopen::parameter_coder::{coder},
+            ))
+    }}
+",
+            name = accessor.name,
+            value_type = accessor.value_type,
+            index = accessor.object.id.index(),
+            sub_index = accessor.object.id.sub_index(),
+            variant = accessor.variant,
+            coder = accessor.coder,
+        )
+        .unwrap();
+    }
+    code.push_str("}\n");
+    Ok(code)
+}
+
+/// Returns the `use` declarations of the items used by `body`.
+fn imports(body: &str) -> String {
+    let mut imports = String::new();
+    for (module, names) in [
+        (
+            "object_dictionary",
+            &[
+                "AccessType",
+                "CobId",
+                "EntryId",
+                "FixedBytes",
+                "NodeIdRelative",
+                "PdoMapability",
+                "Variable",
+                "VariableType",
+            ][..],
+        ),
+        (
+            "pdo",
+            &["PdoConfiguration", "PdoEntryMapping", "PdoTransmissionType"],
+        ),
+    ] {
+        // Items are only used through their paths, or as struct literals.
+        let used: Vec<&str> = names
+            .iter()
+            .copied()
+            .filter(|name| {
+                body.contains(&format!("{name}::")) || body.contains(&format!("{name} {{"))
+            })
+            .collect();
+        match used[..] {
+            [] => {}
+            [name] => writeln!(imports, "    use ::canopen::{module}::{name};").unwrap(),
+            _ => writeln!(
+                imports,
+                "    use ::canopen::{module}::{{{}}};",
+                used.join(", ")
+            )
+            .unwrap(),
+        }
+    }
+    if body.contains("Coder)") {
+        imports.push_str("    use ::canopen::parameter_coder::*;\n");
+    }
+    imports
+}
+
+/// Node-IDs the values are checked with. Values are linear in the node-ID,
+/// so checking the bounds is enough.
+fn node_ids() -> [NodeId; 2] {
+    [NodeId::new(1).unwrap(), NodeId::new(127).unwrap()]
+}
+
+/// Returns the code of an integer `base + coefficient * node_id`, of type `ty`.
+fn linear_code(base: i128, coefficient: i128, ty: &str) -> String {
+    let term = match coefficient {
+        0 => return base.to_string(),
+        1 => NODE_ID.into(),
+        -1 => format!("-({NODE_ID})"),
+        _ => format!("{coefficient} * ({NODE_ID})"),
+    };
+    match base {
+        0 => format!("({term}) as {ty}"),
+        ..0 => format!("({term} - {}) as {ty}", -base),
+        _ => format!("({term} + {base}) as {ty}"),
+    }
+}
+
+/// Splits a value of an object into the `base` and `coefficient` of
+/// `base + coefficient * node_id`.
+fn linear_value(object: &EdsObject, value: Option<Value>) -> Result<(i128, i128), EdsError> {
+    let Some(value) = value else {
+        return Ok((0, 0));
+    };
+    let at = |node_id| {
+        evaluate(value.value, NodeId::new(node_id).unwrap())
+            .ok_or_else(|| object.invalid_value(value))
+    };
+    let base = at(0)?;
+    Ok((base, at(1)? - base))
+}
+
+fn variable_code(object: &EdsObject) -> Result<String, EdsError> {
+    let value = value_code(object, object.parameter_value.or(object.default_value))?;
+    let access_type = match object.access_type() {
+        AccessType::ReadOnly => "ReadOnly",
+        AccessType::WriteOnly => "WriteOnly",
+        AccessType::ReadWrite => "ReadWrite",
+        AccessType::ReadWriteTpdo => "ReadWriteTpdo",
+        AccessType::ReadWriteRpdo => "ReadWriteRpdo",
+        AccessType::Const => "Const",
+    };
+    let pdo_mapability = match object.pdo_mapability() {
+        PdoMapability::All => "All",
+        PdoMapability::Tpdo => "Tpdo",
+        PdoMapability::Rpdo => "Rpdo",
+        PdoMapability::None => "None",
+    };
+    let mut code = format!(
+        "Variable::builder(EntryId::new({:#06X}, {:#04X})).name({:?})\
+         .access(AccessType::{access_type}).pdo(PdoMapability::{pdo_mapability})",
+        object.id.index(),
+        object.id.sub_index(),
+        object.name,
+    );
+    if let Some(low_limit) = object.low_limit {
+        let low_limit = value_code(object, Some(low_limit))?;
+        write!(code, ".low_limit({low_limit})").unwrap();
+    }
+    if let Some(high_limit) = object.high_limit {
+        let high_limit = value_code(object, Some(high_limit))?;
+        write!(code, ".high_limit({high_limit})").unwrap();
+    }
+    // The value is the default one unless the file is a DCF.
+    if let (Some(_), Some(default_value)) = (object.parameter_value, object.default_value) {
+        let default_value = value_code(object, Some(default_value))?;
+        write!(code, ".default_value({default_value})").unwrap();
+    }
+    let node_id_relative = object.node_id_relative()?;
+    if node_id_relative != NodeIdRelative::default() {
+        write!(code, ".node_id_relative({node_id_relative:?})").unwrap();
+    }
+    write!(code, ".value({value}).unwrap()").unwrap();
+    Ok(code)
+}
+
+/// Returns the code of a value of an object, 0 if `None`.
+fn value_code(object: &EdsObject, value: Option<Value>) -> Result<String, EdsError> {
+    let at = |node_id| match value {
+        Some(value) => object.parse_value(value, node_id),
+        None => object.value(node_id),
+    };
+    for node_id in node_ids() {
+        at(node_id)?;
+    }
+    let (base, coefficient) = match at(node_ids()[0])? {
+        VariableType::Float32(..)
+        | VariableType::Float64(..)
+        | VariableType::VisibleString(_)
+        | VariableType::OctetString(_)
+        | VariableType::UnicodeString(_) => (0, 0),
+        _ => linear_value(object, value)?,
+    };
+    let not_linear = || Err(object.invalid_value(value.unwrap()));
+    let integer = |variant: &str, ty: &str, coder: &str| {
+        format!(
+            "VariableType::{variant}({}, &{coder})",
+            linear_code(base, coefficient, ty)
+        )
+    };
+    Ok(match at(node_ids()[0])? {
+        VariableType::Array(_) | VariableType::Record(_) | VariableType::Boolean(..)
+            if coefficient != 0 =>
+        {
+            return not_linear()
+        }
+        VariableType::Array(count) => format!("VariableType::Array({count})"),
+        VariableType::Record(count) => format!("VariableType::Record({count})"),
+        VariableType::Boolean(value, _) => {
+            format!("VariableType::Boolean({value}, &DefaultBooleanCoder)")
+        }
+        VariableType::Int8(..) => integer("Int8", "i8", "DefaultI8Coder"),
+        VariableType::UInt8(..) => integer("UInt8", "u8", "DefaultU8Coder"),
+        VariableType::Int16(..) => integer("Int16", "i16", "DefaultI16Coder"),
+        VariableType::UInt16(..) => integer("UInt16", "u16", "DefaultU16Coder"),
+        VariableType::Int24(..) => integer("Int24", "i32", "DefaultI24Coder"),
+        VariableType::UInt24(..) => integer("UInt24", "u32", "DefaultU24Coder"),
+        VariableType::Int32(..) => integer("Int32", "i32", "DefaultI32Coder"),
+        VariableType::UInt32(..) => integer("UInt32", "u32", "DefaultU32Coder"),
+        VariableType::Int40(..) => integer("Int40", "i64", "DefaultI40Coder"),
+        VariableType::UInt40(..) => integer("UInt40", "u64", "DefaultU40Coder"),
+        VariableType::Int48(..) => integer("Int48", "i64", "DefaultI48Coder"),
+        VariableType::UInt48(..) => integer("UInt48", "u64", "DefaultU48Coder"),
+        VariableType::Int56(..) => integer("Int56", "i64", "DefaultI56Coder"),
+        VariableType::UInt56(..) => integer("UInt56", "u64", "DefaultU56Coder"),
+        VariableType::Int64(..) => integer("Int64", "i64", "DefaultI64Coder"),
+        VariableType::UInt64(..) => integer("UInt64", "u64", "DefaultU64Coder"),
+        VariableType::Float32(value, _) => format!(
+            "VariableType::Float32(f32::from_bits({:#010X}), &DefaultF32Coder)",
+            value.to_bits()
+        ),
+        VariableType::Float64(value, _) => format!(
+            "VariableType::Float64(f64::from_bits({:#018X}), &DefaultF64Coder)",
+            value.to_bits()
+        ),
+        VariableType::VisibleString(value) => format!(
+            "VariableType::VisibleString(FixedBytes::from_slice({:?}.as_bytes()).unwrap())",
+            std::str::from_utf8(value.as_slice()).unwrap()
+        ),
+        VariableType::OctetString(value) => format!(
+            "VariableType::OctetString(FixedBytes::from_slice(&{:?}).unwrap())",
+            value.as_slice()
+        ),
+        VariableType::UnicodeString(value) => format!(
+            "VariableType::UnicodeString(FixedBytes::from_slice(&{:?}).unwrap())",
+            value.as_slice()
+        ),
+        VariableType::RawBytes(_) | VariableType::Domain(_) => {
+            unreachable!("not parsed from an EDS")
+        }
+    })
+}
+
+/// Returns the code of the configuration of each PDO up to the last one
+/// described by the file.
+fn pdo_codes(
+    eds: &Eds,
+    communication_index: u16,
+    mapping_index: u16,
+) -> Result<Vec<String>, EdsError> {
+    let mut codes = Vec::new();
+    let mut count = 0;
+    for pdo in 0..MAX_PDO_COUNT {
+        let [first, last] = node_ids().map(|node_id| {
+            eds.pdo_configuration(communication_index + pdo, mapping_index + pdo, node_id)
+        });
+        let (Some(first), Some(last)) = (first?, last?) else {
+            codes.push("PdoConfiguration::default()".into());
+            continue;
+        };
+        codes.push(pdo_code(&first, &last, node_ids()));
+        count = codes.len();
+    }
+    codes.truncate(count);
+    Ok(codes)
+}
+
+fn pdo_code(first: &PdoConfiguration, last: &PdoConfiguration, node_ids: [NodeId; 2]) -> String {
+    // The COB-ID is the only parameter expected to depend on the node-ID.
+    let (first_id, last_id) = (first.cob_id().raw() as i128, last.cob_id().raw() as i128);
+    let coefficient = (last_id - first_id) / (node_ids[1].raw() - node_ids[0].raw()) as i128;
+    let base = first_id - coefficient * node_ids[0].raw() as i128;
+    let transmission_type = match first.transmission_type() {
+        PdoTransmissionType::Synchronous(n) => format!("PdoTransmissionType::Synchronous({n})"),
+        PdoTransmissionType::EventDriven => "PdoTransmissionType::EventDriven".into(),
+    };
+    let mut mappings: Vec<String> = first
+        .mappings()
+        .iter()
+        .map(|mapping| {
+            format!(
+                "PdoEntryMapping::new({:#06X}, {:#04X}, {})",
+                mapping.entry_id().index(),
+                mapping.entry_id().sub_index(),
+                mapping.length()
+            )
+        })
+        .collect();
+    mappings.resize(8, "PdoEntryMapping::default()".into());
+    let mut code = format!(
+        "PdoConfiguration::new(CobId::from_raw({}), {transmission_type}, {}, [{}], {}).with_inhibit_time({})",
+        linear_code(base, coefficient, "u32"),
+        first.mappings().len(),
+        mappings.join(", "),
+        first.event_timer_ms(),
+        first.inhibit_time_100us(),
+    );
+    if first.is_cob_id_node_id_relative() {
+        code.push_str(".with_node_id_relative_cob_id()");
+    }
+    code
+}
+
+struct Accessor<'a, 'b> {
+    name: String,
+    object: &'b EdsObject<'a>,
+    variant: &'static str,
+    value_type: &'static str,
+    coder: &'static str,
+}
+
+/// Returns the accessors of the objects holding a value. Names used by
+/// several objects are suffixed with the index and sub-index.
+fn accessors<'a, 'b>(objects: &[&'b EdsObject<'a>]) -> Result<Vec<Accessor<'a, 'b>>, EdsError> {
+    let mut accessors = Vec::new();
+    let mut names = BTreeSet::new();
+    for object in objects {
+        let (variant, value_type, coder) = match object.value(node_ids()[0])? {
+            VariableType::Array(_)
+            | VariableType::Record(_)
+            | VariableType::RawBytes(_)
+            | VariableType::VisibleString(_)
+            | VariableType::OctetString(_)
+            | VariableType::UnicodeString(_)
+            | VariableType::Domain(_) => continue,
+            VariableType::Boolean(..) => ("Boolean", "bool", "DefaultBooleanCoder"),
+            VariableType::Int8(..) => ("Int8", "i8", "DefaultI8Coder"),
+            VariableType::UInt8(..) => ("UInt8", "u8", "DefaultU8Coder"),
+            VariableType::Int16(..) => ("Int16", "i16", "DefaultI16Coder"),
+            VariableType::UInt16(..) => ("UInt16", "u16", "DefaultU16Coder"),
+            VariableType::Int24(..) => ("Int24", "i32", "DefaultI24Coder"),
+            VariableType::UInt24(..) => ("UInt24", "u32", "DefaultU24Coder"),
+            VariableType::Int32(..) => ("Int32", "i32", "DefaultI32Coder"),
+            VariableType::UInt32(..) => ("UInt32", "u32", "DefaultU32Coder"),
+            VariableType::Int40(..) => ("Int40", "i64", "DefaultI40Coder"),
+            VariableType::UInt40(..) => ("UInt40", "u64", "DefaultU40Coder"),
+            VariableType::Int48(..) => ("Int48", "i64", "DefaultI48Coder"),
+            VariableType::UInt48(..) => ("UInt48", "u64", "DefaultU48Coder"),
+            VariableType::Int56(..) => ("Int56", "i64", "DefaultI56Coder"),
+            VariableType::UInt56(..) => ("UInt56", "u64", "DefaultU56Coder"),
+            VariableType::Int64(..) => ("Int64", "i64", "DefaultI64Coder"),
+            VariableType::UInt64(..) => ("UInt64", "u64", "DefaultU64Coder"),
+            VariableType::Float32(..) => ("Float32", "f32", "DefaultF32Coder"),
+            VariableType::Float64(..) => ("Float64", "f64", "DefaultF64Coder"),
+        };
+        let mut name = match object.parent_name {
+            Some(parent) => identifier(&format!("{parent} {}", object.name)),
+            None => identifier(object.name),
+        };
+        if !names.insert(name.clone()) {
+            name = format!(
+                "{name}_{:04x}_{:02x}",
+                object.id.index(),
+                object.id.sub_index()
+            );
+            names.insert(name.clone());
+        }
+        accessors.push(Accessor {
+            name,
+            object,
+            variant,
+            value_type,
+            coder,
+        });
+    }
+    Ok(accessors)
+}
+
+/// Converts a parameter name to a snake case identifier.
+fn identifier(name: &str) -> String {
+    let mut identifier = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            identifier.push(c.to_ascii_lowercase());
+        } else if !identifier.is_empty() && !identifier.ends_with('_') {
+            identifier.push('_');
+        }
+    }
+    while identifier.ends_with('_') {
+        identifier.pop();
+    }
+    if identifier.is_empty() || identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier.insert_str(0, "entry_");
+    }
+    if KEYWORDS.contains(&identifier.as_str()) {
+        identifier.push('_');
+    }
+    identifier
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::eds::Eds;
+    use crate::node::NodeId;
+
+    use super::{generate, identifier, linear_code};
+
+    /// The code generated from [`EDS`], compiled to check that it builds.
+    mod generated {
+        include!("codegen/test_od.rs");
+    }
+
+    const EDS: &str = "\
+[1018]
+ParameterName=Identity object
+ObjectType=0x9
+SubNumber=2
+
+[1018sub0]
+ParameterName=Highest sub-index supported
+DataType=0x0005
+AccessType=const
+DefaultValue=1
+
+[1018sub1]
+ParameterName=Vendor-ID
+DataType=0x0007
+AccessType=ro
+DefaultValue=0x1234
+
+[1008]
+ParameterName=Manufacturer device name
+DataType=0x0009
+AccessType=const
+DefaultValue=Pump
+
+[1801sub1]
+ParameterName=COB-ID
+DataType=0x0007
+AccessType=rw
+DefaultValue=$NODEID+0x280
+
+[2000]
+ParameterName=Motor current
+DataType=0x0003
+AccessType=rww
+PDOMapping=1
+DefaultValue=-10
+LowLimit=-100
+HighLimit=$NODEID+100
+
+[2001]
+ParameterName=Motor current
+DataType=0x0008
+AccessType=rw
+DefaultValue=1.5
+
+[2002]
+ParameterName=Firmware image
+DataType=0x000F
+AccessType=rw
+";
+
+    #[test]
+    fn test_identifiers() {
+        assert_eq!(identifier("Motor current (mA)"), "motor_current_ma");
+        assert_eq!(identifier("Vendor-ID"), "vendor_id");
+        assert_eq!(identifier("2nd value"), "entry_2nd_value");
+        assert_eq!(identifier("Type"), "type_");
+        assert_eq!(identifier("--"), "entry_");
+    }
+
+    #[test]
+    fn test_linear_code() {
+        assert_eq!(linear_code(-10, 0, "i16"), "-10");
+        assert_eq!(
+            linear_code(0x180, 1, "u32"),
+            "(node_id.raw() as i128 + 384) as u32"
+        );
+        assert_eq!(
+            linear_code(-1, 1, "u8"),
+            "(node_id.raw() as i128 - 1) as u8"
+        );
+        assert_eq!(
+            linear_code(0, 2, "u8"),
+            "(2 * (node_id.raw() as i128)) as u8"
+        );
+    }
+
+    #[test]
+    fn test_generate() {
+        let code = generate(&Eds::parse(EDS).unwrap()).unwrap();
+        assert!(code.contains("pub const ENTRY_COUNT: usize = 5;"));
+        assert!(code.contains("pub const RPDO_COUNT: usize = 0;"));
+        assert!(code.contains("pub const TPDO_COUNT: usize = 2;"));
+        assert!(code.contains(
+            "Variable::builder(EntryId::new(0x2000, 0x00)).name(\"Motor current\")\
+             .access(AccessType::ReadWriteRpdo).pdo(PdoMapability::Rpdo)\
+             .low_limit(VariableType::Int16(-100, &DefaultI16Coder))\
+             .high_limit(VariableType::Int16((node_id.raw() as i128 + 100) as i16, \
+             &DefaultI16Coder))\
+             .node_id_relative(NodeIdRelative { low_limit: false, high_limit: true, \
+             default_value: false })\
+             .value(VariableType::Int16(-10, &DefaultI16Coder)).unwrap()"
+        ));
+        assert!(
+            code.contains("VariableType::Float32(f32::from_bits(0x3FC00000), &DefaultF32Coder)")
+        );
+        assert!(code.contains(
+            "VariableType::VisibleString(FixedBytes::from_slice(\"Pump\".as_bytes()).unwrap())"
+        ));
+        assert!(code.contains("CobId::from_raw((node_id.raw() as i128 + 640) as u32)"));
+        assert!(code.contains(".with_node_id_relative_cob_id()"));
+        assert!(code.contains("fn identity_object_vendor_id(&self) -> Option<u32>;"));
+        assert!(code.contains("fn motor_current(&self) -> Option<i16>;"));
+        assert!(code.contains("fn motor_current_2001_00(&self) -> Option<f32>;"));
+        assert!(!code.contains("fn identity_object_highest_sub_index_supported"));
+        assert!(!code.contains("0x2002"));
+    }
+    #[test]
+    fn test_generated_code() {
+        use generated::Accessors;
+
+        let code = generate(&Eds::parse(EDS).unwrap()).unwrap();
+        assert_eq!(code, include_str!("codegen/test_od.rs"));
+
+        let mut od = generated::object_dictionary(NodeId::new(5).unwrap());
+        assert_eq!(od.identity_object_vendor_id(), Some(0x1234));
+        assert_eq!(od.motor_current(), Some(-10));
+        assert!(od.set_motor_current(105).is_ok());
+        assert!(od.set_motor_current(106).is_err());
+        assert_eq!(od.tpdo_configuration(1).unwrap().cob_id().raw(), 0x285);
+
+        generated::OBJECT_DICTIONARY.with_borrow(|od| {
+            assert_eq!(od.node_id(), NodeId::default());
+            assert_eq!(od.motor_current_2001_00(), Some(1.5));
+        });
+    }
+
+    #[test]
+    fn test_imports() {
+        let code = generate(
+            &Eds::parse(
+                "\
+[1017]
+ParameterName=Producer heartbeat time
+ObjectType=0x7
+DataType=0x0006
+AccessType=rw
+DefaultValue=0
+",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(code.contains(
+            "use ::canopen::object_dictionary::{AccessType, EntryId, PdoMapability, Variable, \
+             VariableType};"
+        ));
+        assert!(!code.contains("FixedBytes"));
+        assert!(!code.contains("::canopen::pdo::"));
+    }
+}
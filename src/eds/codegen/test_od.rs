@@ -0,0 +1,113 @@
+// Generated by canopen::eds::codegen. Do not edit.
+
+pub const ENTRY_COUNT: usize = 5;
+pub const RPDO_COUNT: usize = 0;
+pub const TPDO_COUNT: usize = 2;
+
+pub type ObjectDictionary = ::canopen::object_dictionary::ObjectDictionary<
+    ENTRY_COUNT,
+    RPDO_COUNT,
+    TPDO_COUNT,
+>;
+
+::std::thread_local! {
+    /// The object dictionary, built on first use for the node-ID of the file.
+    pub static OBJECT_DICTIONARY: ::core::cell::RefCell<ObjectDictionary> =
+        ::core::cell::RefCell::new(object_dictionary(::canopen::node::NodeId::default()));
+}
+
+/// Builds the object dictionary of the node `node_id`.
+#[allow(unused_variables)]
+pub fn object_dictionary(node_id: ::canopen::node::NodeId) -> ObjectDictionary {
+    use ::canopen::object_dictionary::{AccessType, CobId, EntryId, FixedBytes, NodeIdRelative, PdoMapability, Variable, VariableType};
+    use ::canopen::pdo::{PdoConfiguration, PdoEntryMapping, PdoTransmissionType};
+    use ::canopen::parameter_coder::*;
+
+    let variables = [
+        Variable::builder(EntryId::new(0x1008, 0x00)).name("Manufacturer device name").access(AccessType::Const).pdo(PdoMapability::None).value(VariableType::VisibleString(FixedBytes::from_slice("Pump".as_bytes()).unwrap())).unwrap(),
+        Variable::builder(EntryId::new(0x1018, 0x00)).name("Highest sub-index supported").access(AccessType::Const).pdo(PdoMapability::None).value(VariableType::Record(1)).unwrap(),
+        Variable::builder(EntryId::new(0x1018, 0x01)).name("Vendor-ID").access(AccessType::ReadOnly).pdo(PdoMapability::None).value(VariableType::UInt32(4660, &DefaultU32Coder)).unwrap(),
+        Variable::builder(EntryId::new(0x2000, 0x00)).name("Motor current").access(AccessType::ReadWriteRpdo).pdo(PdoMapability::Rpdo).low_limit(VariableType::Int16(-100, &DefaultI16Coder)).high_limit(VariableType::Int16((node_id.raw() as i128 + 100) as i16, &DefaultI16Coder)).node_id_relative(NodeIdRelative { low_limit: false, high_limit: true, default_value: false }).value(VariableType::Int16(-10, &DefaultI16Coder)).unwrap(),
+        Variable::builder(EntryId::new(0x2001, 0x00)).name("Motor current").access(AccessType::ReadWrite).pdo(PdoMapability::None).value(VariableType::Float32(f32::from_bits(0x3FC00000), &DefaultF32Coder)).unwrap(),
+    ];
+    let rpdos = [
+    ];
+    let tpdos = [
+        PdoConfiguration::default(),
+        PdoConfiguration::new(CobId::from_raw((node_id.raw() as i128 + 640) as u32), PdoTransmissionType::EventDriven, 0, [PdoEntryMapping::default(), PdoEntryMapping::default(), PdoEntryMapping::default(), PdoEntryMapping::default(), PdoEntryMapping::default(), PdoEntryMapping::default(), PdoEntryMapping::default(), PdoEntryMapping::default()], 0).with_inhibit_time(0).with_node_id_relative_cob_id(),
+    ];
+    ObjectDictionary::new(
+        0,
+        0,
+        [0; 8],
+        tpdos,
+        rpdos,
+        variables.into_iter().collect(),
+        node_id,
+    )
+}
+
+/// Typed access to the entries of the object dictionary.
+#[allow(dead_code)]
+pub trait Accessors {
+    fn identity_object_vendor_id(&self) -> Option<u32>;
+    fn set_identity_object_vendor_id(&mut self, value: u32) -> Result<(), ::canopen::sdo::SdoAbortCode>;
+    fn motor_current(&self) -> Option<i16>;
+    fn set_motor_current(&mut self, value: i16) -> Result<(), ::canopen::sdo::SdoAbortCode>;
+    fn motor_current_2001_00(&self) -> Option<f32>;
+    fn set_motor_current_2001_00(&mut self, value: f32) -> Result<(), ::canopen::sdo::SdoAbortCode>;
+}
+
+impl Accessors for ObjectDictionary {
+    fn identity_object_vendor_id(&self) -> Option<u32> {
+        let id = ::canopen::object_dictionary::EntryId::new(0x1018, 0x01);
+        match self.get_variable(id)?.value() {
+            ::canopen::object_dictionary::VariableType::UInt32(value, _) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn set_identity_object_vendor_id(&mut self, value: u32) -> Result<(), ::canopen::sdo::SdoAbortCode> {
+        let id = ::canopen::object_dictionary::EntryId::new(0x1018, 0x01);
+        self.get_mut_variable(id)
+            .ok_or(::canopen::sdo::SdoAbortCode::ObjectDoesNotExist)?
+            .set_value(::canopen::object_dictionary::VariableType::UInt32(
+                value,
+                &::canopen::parameter_coder::DefaultU32Coder,
+            ))
+    }
+    fn motor_current(&self) -> Option<i16> {
+        let id = ::canopen::object_dictionary::EntryId::new(0x2000, 0x00);
+        match self.get_variable(id)?.value() {
+            ::canopen::object_dictionary::VariableType::Int16(value, _) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn set_motor_current(&mut self, value: i16) -> Result<(), ::canopen::sdo::SdoAbortCode> {
+        let id = ::canopen::object_dictionary::EntryId::new(0x2000, 0x00);
+        self.get_mut_variable(id)
+            .ok_or(::canopen::sdo::SdoAbortCode::ObjectDoesNotExist)?
+            .set_value(::canopen::object_dictionary::VariableType::Int16(
+                value,
+                &::canopen::parameter_coder::DefaultI16Coder,
+            ))
+    }
+    fn motor_current_2001_00(&self) -> Option<f32> {
+        let id = ::canopen::object_dictionary::EntryId::new(0x2001, 0x00);
+        match self.get_variable(id)?.value() {
+            ::canopen::object_dictionary::VariableType::Float32(value, _) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn set_motor_current_2001_00(&mut self, value: f32) -> Result<(), ::canopen::sdo::SdoAbortCode> {
+        let id = ::canopen::object_dictionary::EntryId::new(0x2001, 0x00);
+        self.get_mut_variable(id)
+            .ok_or(::canopen::sdo::SdoAbortCode::ObjectDoesNotExist)?
+            .set_value(::canopen::object_dictionary::VariableType::Float32(
+                value,
+                &::canopen::parameter_coder::DefaultF32Coder,
+            ))
+    }
+}
@@ -0,0 +1,646 @@
+//! Writes device configuration files (DCF) holding the current values of a
+//! node, from its object dictionary or through SDO.
+
+use std::{collections::BTreeMap, fmt::Write, format, string::String, vec::Vec};
+
+use embedded_can::nb::Can;
+
+use crate::{
+    node::NodeId,
+    object_dictionary::{
+        AccessType, EntryId, ObjectDictionary, PdoMapability, Variable, VariableType,
+    },
+    pdo::{PdoConfiguration, PdoTransmissionType, EVENT_DRIVEN},
+    sdo::{SdoAbortCode, SdoClient, SdoError},
+    time::Clock,
+};
+
+use super::{
+    DataType, Eds, EdsObject, ObjectType, RPDO_COMMUNICATION_INDEX, RPDO_MAPPING_INDEX,
+    TPDO_COMMUNICATION_INDEX, TPDO_MAPPING_INDEX,
+};
+
+const ERROR_REGISTER_INDEX: u16 = 0x1001;
+
+/// Objects every device implements.
+const MANDATORY_OBJECTS: [u16; 3] = [0x1000, 0x1001, 0x1018];
+
+/// An entry of the DCF.
+struct Entry {
+    id: EntryId,
+    name: String,
+    data_type: DataType,
+    access_type: AccessType,
+    pdo_mapping: bool,
+    value: Option<String>,
+    default_value: Option<String>,
+    low_limit: Option<String>,
+    high_limit: Option<String>,
+}
+
+impl Entry {
+    fn from_variable(variable: &Variable) -> Option<Self> {
+        let value = variable.value();
+        Some(Self {
+            id: variable.id(),
+            name: variable.name().into(),
+            data_type: data_type(&value)?,
+            access_type: variable.access_type(),
+            pdo_mapping: variable.pdo_mapability() != PdoMapability::None,
+            value: format_value(&value),
+            default_value: variable.default_value().as_ref().and_then(format_value),
+            low_limit: variable.low_limit().as_ref().and_then(format_value),
+            high_limit: variable.high_limit().as_ref().and_then(format_value),
+        })
+    }
+
+    fn unsigned(id: EntryId, name: &str, data_type: DataType, value: u64) -> Self {
+        Self {
+            id,
+            name: name.into(),
+            data_type,
+            access_type: AccessType::ReadWrite,
+            pdo_mapping: false,
+            value: Some(format!("{value:#X}")),
+            default_value: None,
+            low_limit: None,
+            high_limit: None,
+        }
+    }
+}
+
+/// An object of the DCF, with its sub-objects.
+struct Object {
+    name: Option<String>,
+    object_type: ObjectType,
+    entries: Vec<Entry>,
+}
+
+/// The objects of a DCF, by index.
+#[derive(Default)]
+struct Objects(BTreeMap<u16, Object>);
+
+impl Objects {
+    fn push(&mut self, entry: Entry) {
+        let object = self.0.entry(entry.id.index()).or_insert_with(|| Object {
+            name: None,
+            object_type: ObjectType::Var,
+            entries: Vec::new(),
+        });
+        if entry.id.sub_index() != 0 && object.object_type == ObjectType::Var {
+            object.object_type = ObjectType::Record;
+        }
+        object.entries.push(entry);
+    }
+
+    /// Sets the name and type of an array or record.
+    fn set_parent(&mut self, index: u16, name: Option<&str>, object_type: ObjectType) {
+        if let Some(object) = self.0.get_mut(&index) {
+            object.name = name.map(String::from);
+            object.object_type = object_type;
+        }
+    }
+
+    fn push_pdo(&mut self, communication: u16, mapping: u16, pdo: &PdoConfiguration) {
+        let transmission_type = match pdo.transmission_type() {
+            PdoTransmissionType::Synchronous(n) => n,
+            PdoTransmissionType::EventDriven => EVENT_DRIVEN,
+        };
+        let cob_id = pdo.cob_id().raw() as u64;
+        let inhibit_time = pdo.inhibit_time_100us() as u64;
+        let event_timer = pdo.event_timer_ms() as u64;
+        for (sub_index, name, data_type, value) in [
+            (0, "Highest sub-index supported", DataType::Unsigned8, 5),
+            (1, "COB-ID", DataType::Unsigned32, cob_id),
+            (
+                2,
+                "Transmission type",
+                DataType::Unsigned8,
+                transmission_type as u64,
+            ),
+            (3, "Inhibit time", DataType::Unsigned16, inhibit_time),
+            (5, "Event timer", DataType::Unsigned16, event_timer),
+        ] {
+            let id = EntryId::new(communication, sub_index);
+            self.push(Entry::unsigned(id, name, data_type, value));
+        }
+
+        let mappings = pdo.mappings();
+        self.push(Entry::unsigned(
+            EntryId::new(mapping, 0),
+            "Number of mapped objects",
+            DataType::Unsigned8,
+            mappings.len() as u64,
+        ));
+        for (sub_index, entry) in (1..=8).zip(mappings) {
+            self.push(Entry::unsigned(
+                EntryId::new(mapping, sub_index),
+                &format!("Mapped object {sub_index}"),
+                DataType::Unsigned32,
+                entry.raw() as u64,
+            ));
+        }
+    }
+
+    fn push_pdos(&mut self, rpdos: &[PdoConfiguration], tpdos: &[PdoConfiguration]) {
+        for (communication, mapping, pdos, kind) in [
+            (RPDO_COMMUNICATION_INDEX, RPDO_MAPPING_INDEX, rpdos, "RPDO"),
+            (TPDO_COMMUNICATION_INDEX, TPDO_MAPPING_INDEX, tpdos, "TPDO"),
+        ] {
+            for (n, pdo) in pdos.iter().enumerate() {
+                let (communication, mapping) = (communication + n as u16, mapping + n as u16);
+                self.push_pdo(communication, mapping, pdo);
+                let name = format!("{kind} communication parameter {}", n + 1);
+                self.set_parent(communication, Some(&name), ObjectType::Record);
+                let name = format!("{kind} mapping parameter {}", n + 1);
+                self.set_parent(mapping, Some(&name), ObjectType::Record);
+            }
+        }
+    }
+
+    fn pdo_count(&self, communication_index: u16) -> usize {
+        self.0
+            .range(communication_index..communication_index + 0x200)
+            .count()
+    }
+
+    fn unsigned(&self, id: EntryId) -> Option<&str> {
+        let object = self.0.get(&id.index())?;
+        let entry = object.entries.iter().find(|entry| entry.id == id)?;
+        entry.value.as_deref()
+    }
+
+    fn write(&self, node_id: NodeId) -> String {
+        let mut dcf = String::new();
+        dcf.push_str("[FileInfo]\nFileVersion=1\nFileRevision=1\nEDSVersion=4.0\n\n");
+
+        dcf.push_str("[DeviceInfo]\n");
+        for (key, sub_index) in [
+            ("VendorNumber", 1),
+            ("ProductNumber", 2),
+            ("RevisionNumber", 3),
+        ] {
+            if let Some(value) = self.unsigned(EntryId::new(0x1018, sub_index)) {
+                writeln!(dcf, "{key}={value}").unwrap();
+            }
+        }
+        writeln!(
+            dcf,
+            "NrOfRXPDO={}",
+            self.pdo_count(RPDO_COMMUNICATION_INDEX)
+        )
+        .unwrap();
+        writeln!(
+            dcf,
+            "NrOfTXPDO={}",
+            self.pdo_count(TPDO_COMMUNICATION_INDEX)
+        )
+        .unwrap();
+        dcf.push_str("\n[DeviceComissioning]\n");
+        writeln!(dcf, "NodeID={:#X}\n", node_id.raw()).unwrap();
+
+        let (mandatory, rest): (Vec<u16>, Vec<u16>) = self
+            .0
+            .keys()
+            .partition(|index| MANDATORY_OBJECTS.contains(index));
+        let (manufacturer, optional): (Vec<u16>, Vec<u16>) = rest
+            .into_iter()
+            .partition(|index| (0x2000..0x6000).contains(index));
+        for (section, indices) in [
+            ("MandatoryObjects", mandatory),
+            ("OptionalObjects", optional),
+            ("ManufacturerObjects", manufacturer),
+        ] {
+            writeln!(dcf, "[{section}]\nSupportedObjects={}", indices.len()).unwrap();
+            for (n, index) in indices.iter().enumerate() {
+                writeln!(dcf, "{}={index:#06X}", n + 1).unwrap();
+            }
+            dcf.push('\n');
+        }
+
+        for (index, object) in &self.0 {
+            writeln!(dcf, "[{index:04X}]").unwrap();
+            if object.object_type == ObjectType::Var {
+                write_entry(&mut dcf, &object.entries[0]);
+                continue;
+            }
+            match &object.name {
+                Some(name) => writeln!(dcf, "ParameterName={name}").unwrap(),
+                None => writeln!(dcf, "ParameterName=Object {index:04X}").unwrap(),
+            }
+            writeln!(dcf, "ObjectType={:#X}", object.object_type as u8).unwrap();
+            writeln!(dcf, "SubNumber={}\n", object.entries.len()).unwrap();
+            for entry in &object.entries {
+                writeln!(dcf, "[{index:04X}sub{:X}]", entry.id.sub_index()).unwrap();
+                write_entry(&mut dcf, entry);
+            }
+        }
+        dcf
+    }
+}
+
+fn write_entry(dcf: &mut String, entry: &Entry) {
+    let access_type = match entry.access_type {
+        AccessType::ReadOnly => "ro",
+        AccessType::WriteOnly => "wo",
+        AccessType::ReadWrite => "rw",
+        AccessType::ReadWriteTpdo => "rwr",
+        AccessType::ReadWriteRpdo => "rww",
+        AccessType::Const => "const",
+    };
+    writeln!(dcf, "ParameterName={}", entry.name).unwrap();
+    writeln!(dcf, "ObjectType={:#X}", ObjectType::Var as u8).unwrap();
+    writeln!(dcf, "DataType={:#06X}", entry.data_type as u16).unwrap();
+    writeln!(dcf, "AccessType={access_type}").unwrap();
+    writeln!(dcf, "PDOMapping={}", entry.pdo_mapping as u8).unwrap();
+    for (key, value) in [
+        ("LowLimit", &entry.low_limit),
+        ("HighLimit", &entry.high_limit),
+        ("DefaultValue", &entry.default_value),
+        ("ParameterValue", &entry.value),
+    ] {
+        if let Some(value) = value {
+            writeln!(dcf, "{key}={value}").unwrap();
+        }
+    }
+    dcf.push('\n');
+}
+
+/// Returns the data type of the values of a variable, or `None` for raw
+/// bytes.
+fn data_type(value: &VariableType) -> Option<DataType> {
+    Some(match value {
+        VariableType::Array(_) | VariableType::Record(_) => DataType::Unsigned8,
+        VariableType::Boolean(..) => DataType::Boolean,
+        VariableType::Int8(..) => DataType::Integer8,
+        VariableType::UInt8(..) => DataType::Unsigned8,
+        VariableType::Int16(..) => DataType::Integer16,
+        VariableType::UInt16(..) => DataType::Unsigned16,
+        VariableType::Int24(..) => DataType::Integer24,
+        VariableType::UInt24(..) => DataType::Unsigned24,
+        VariableType::Int32(..) => DataType::Integer32,
+        VariableType::UInt32(..) => DataType::Unsigned32,
+        VariableType::Int40(..) => DataType::Integer40,
+        VariableType::UInt40(..) => DataType::Unsigned40,
+        VariableType::Int48(..) => DataType::Integer48,
+        VariableType::UInt48(..) => DataType::Unsigned48,
+        VariableType::Int56(..) => DataType::Integer56,
+        VariableType::UInt56(..) => DataType::Unsigned56,
+        VariableType::Int64(..) => DataType::Integer64,
+        VariableType::UInt64(..) => DataType::Unsigned64,
+        VariableType::Float32(..) => DataType::Real32,
+        VariableType::Float64(..) => DataType::Real64,
+        VariableType::VisibleString(_) => DataType::VisibleString,
+        VariableType::OctetString(_) => DataType::OctetString,
+        VariableType::UnicodeString(_) => DataType::UnicodeString,
+        VariableType::Domain(_) => DataType::Domain,
+        VariableType::RawBytes(_) => return None,
+    })
+}
+
+/// Formats a value as the `ParameterValue` of a DCF: unsigned integers in
+/// hexadecimal, other numbers in decimal, octet and unicode strings as
+/// hexadecimal bytes.
+fn format_value(value: &VariableType) -> Option<String> {
+    Some(match *value {
+        VariableType::Array(count) | VariableType::Record(count) => count.to_string(),
+        VariableType::Boolean(value, _) => (value as u8).to_string(),
+        VariableType::Int8(value, _) => value.to_string(),
+        VariableType::UInt8(value, _) => format!("{value:#X}"),
+        VariableType::Int16(value, _) => value.to_string(),
+        VariableType::UInt16(value, _) => format!("{value:#X}"),
+        VariableType::Int24(value, _) => value.to_string(),
+        VariableType::UInt24(value, _) => format!("{value:#X}"),
+        VariableType::Int32(value, _) => value.to_string(),
+        VariableType::UInt32(value, _) => format!("{value:#X}"),
+        VariableType::Int40(value, _)
+        | VariableType::Int48(value, _)
+        | VariableType::Int56(value, _) => value.to_string(),
+        VariableType::UInt40(value, _)
+        | VariableType::UInt48(value, _)
+        | VariableType::UInt56(value, _) => format!("{value:#X}"),
+        VariableType::Int64(value, _) => value.to_string(),
+        VariableType::UInt64(value, _) => format!("{value:#X}"),
+        VariableType::Float32(value, _) => value.to_string(),
+        VariableType::Float64(value, _) => value.to_string(),
+        VariableType::VisibleString(value) => String::from_utf8_lossy(value.as_slice()).into(),
+        VariableType::OctetString(value) | VariableType::UnicodeString(value) => value
+            .as_slice()
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect(),
+        VariableType::RawBytes(_) | VariableType::Domain(_) => return None,
+    })
+}
+
+/// Writes a DCF holding the current values of `od`, including its error
+/// register and PDO configurations.
+pub fn from_object_dictionary<
+    const ENTRY_COUNT: usize,
+    const RPDO_COUNT: usize,
+    const TPDO_COUNT: usize,
+>(
+    od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+) -> String {
+    let mut objects = Objects::default();
+    objects.push(Entry {
+        id: EntryId::new(ERROR_REGISTER_INDEX, 0),
+        name: "Error register".into(),
+        data_type: DataType::Unsigned8,
+        access_type: AccessType::ReadOnly,
+        pdo_mapping: true,
+        value: Some(format!("{:#X}", od.error_register())),
+        default_value: None,
+        low_limit: None,
+        high_limit: None,
+    });
+    for variable in od.variables() {
+        if let Some(entry) = Entry::from_variable(variable) {
+            objects.push(entry);
+        }
+        let index = variable.id().index();
+        match variable.value() {
+            VariableType::Array(_) => objects.set_parent(index, None, ObjectType::Array),
+            VariableType::Record(_) => objects.set_parent(index, None, ObjectType::Record),
+            _ => {}
+        }
+    }
+    let rpdos: Vec<_> = (0..RPDO_COUNT)
+        .filter_map(|pdo| od.rpdo_configuration(pdo).copied())
+        .collect();
+    let tpdos: Vec<_> = (0..TPDO_COUNT)
+        .filter_map(|pdo| od.tpdo_configuration(pdo).copied())
+        .collect();
+    objects.push_pdos(&rpdos, &tpdos);
+    objects.write(od.node_id())
+}
+
+/// A DCF uploaded from a remote node by [`upload`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadedDcf {
+    pub dcf: String,
+    /// The readable objects the node refused to upload, written without a
+    /// value.
+    pub aborted: Vec<(EntryId, SdoAbortCode)>,
+}
+
+/// Writes a DCF holding the values of the remote node `node_id`, uploading
+/// every readable object described by `eds`.
+///
+/// Objects the node refuses to upload are written without a value and
+/// listed in [`UploadedDcf::aborted`]; bus and protocol errors stop the
+/// upload.
+pub fn upload<B: Can>(
+    client: &mut SdoClient,
+    bus: &mut B,
+    clock: &impl Clock,
+    node_id: NodeId,
+    eds: &Eds,
+) -> Result<UploadedDcf, SdoError> {
+    let mut objects = Objects::default();
+    let mut aborted = Vec::new();
+    for object in eds.objects() {
+        let Some(data_type) = object.data_type() else {
+            continue;
+        };
+        let value = match object.access_type() {
+            AccessType::WriteOnly => None,
+            AccessType::ReadOnly
+            | AccessType::Const
+            | AccessType::ReadWrite
+            | AccessType::ReadWriteTpdo
+            | AccessType::ReadWriteRpdo => match client.upload(bus, clock, node_id, object.id()) {
+                Ok(raw) => decode(object, node_id, &raw),
+                Err(SdoError::ServerAbort(code)) => {
+                    aborted.push((object.id(), code));
+                    None
+                }
+                Err(error) => return Err(error),
+            },
+        };
+        objects.push(Entry {
+            id: object.id(),
+            name: object.name().into(),
+            data_type,
+            access_type: object.access_type(),
+            pdo_mapping: object.pdo_mapability() != PdoMapability::None,
+            value,
+            default_value: object.default_value.map(|value| value.value.into()),
+            low_limit: object.low_limit.map(|value| value.value.into()),
+            high_limit: object.high_limit.map(|value| value.value.into()),
+        });
+        if let Some(parent) = object.parent_name() {
+            let object_type = match object.object_type() {
+                ObjectType::Array => ObjectType::Array,
+                _ => ObjectType::Record,
+            };
+            objects.set_parent(object.id().index(), Some(parent), object_type);
+        }
+    }
+    Ok(UploadedDcf {
+        dcf: objects.write(node_id),
+        aborted,
+    })
+}
+
+/// Decodes an uploaded value as the data type of the object.
+fn decode(object: &EdsObject, node_id: NodeId, raw: &[u8]) -> Option<String> {
+    if object.data_type() == Some(DataType::VisibleString) {
+        return std::str::from_utf8(raw).ok().map(String::from);
+    }
+    let value = object.value(node_id).ok()?;
+    if let VariableType::Array(_) | VariableType::Record(_) = value {
+        return match raw {
+            [count] => Some(count.to_string()),
+            _ => None,
+        };
+    }
+    let mut variable = Variable::builder(object.id()).value(value).ok()?;
+    variable.write_raw(raw).ok()?;
+    format_value(&variable.value())
+}
+
+#[cfg(test)]
+mod tests {
+    use heapless::Vec;
+
+    use crate::eds::Eds;
+    use crate::node::NodeId;
+    use crate::object_dictionary::{
+        AccessType, CobId, EntryId, ObjectDictionary, PdoMapability, Variable, VariableType,
+    };
+    use crate::parameter_coder::*;
+    use crate::pdo::{PdoConfiguration, PdoEntryMapping, PdoTransmissionType};
+    use crate::sdo::{SdoAbortCode, SdoClient};
+    use crate::test_utils::VirtualBus;
+    use crate::time::StdClock;
+
+    use super::{from_object_dictionary, upload};
+
+    fn test_od() -> ObjectDictionary<5, 0, 1> {
+        let mut mappings = [PdoEntryMapping::default(); 8];
+        mappings[0] = PdoEntryMapping::new(0x2000, 0x0, 16);
+        ObjectDictionary::new(
+            0x01,
+            0,
+            [0; 8],
+            [PdoConfiguration::new(
+                CobId::from_raw(0x185),
+                PdoTransmissionType::Synchronous(1),
+                1,
+                mappings,
+                0,
+            )],
+            [],
+            Vec::from_slice(&[
+                Variable::new(
+                    EntryId::new(0x1018, 0x0),
+                    "Highest sub-index supported",
+                    VariableType::Record(1),
+                    AccessType::ReadOnly,
+                ),
+                Variable::new(
+                    EntryId::new(0x1018, 0x1),
+                    "Vendor-ID",
+                    VariableType::UInt32(0x1234, &DefaultU32Coder),
+                    AccessType::ReadOnly,
+                ),
+                Variable::builder(EntryId::new(0x2000, 0x0))
+                    .name("Setpoint")
+                    .pdo(PdoMapability::All)
+                    .low_limit(VariableType::Int16(-500, &DefaultI16Coder))
+                    .high_limit(VariableType::Int16(500, &DefaultI16Coder))
+                    .value(VariableType::Int16(-20, &DefaultI16Coder))
+                    .unwrap(),
+                Variable::new(
+                    EntryId::new(0x2001, 0x0),
+                    "Gain",
+                    VariableType::Float32(0.25, &DefaultF32Coder),
+                    AccessType::ReadWrite,
+                ),
+                Variable::new(
+                    EntryId::new(0x2002, 0x0),
+                    "Command",
+                    VariableType::UInt8(0, &DefaultU8Coder),
+                    AccessType::WriteOnly,
+                ),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::new(5).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_from_object_dictionary() {
+        // Variables hold static names.
+        let dcf = from_object_dictionary(&test_od()).leak();
+        assert!(dcf.contains("[DeviceComissioning]\nNodeID=0x5\n"));
+        assert!(dcf.contains("[MandatoryObjects]\nSupportedObjects=2\n1=0x1001\n2=0x1018\n"));
+        assert!(dcf.contains("[1018]\nParameterName=Object 1018\nObjectType=0x9\nSubNumber=2\n"));
+        assert!(dcf.contains("[1800]\nParameterName=TPDO communication parameter 1\n"));
+
+        let eds = Eds::parse(dcf).unwrap();
+        let node_id = NodeId::new(1).unwrap();
+        assert_eq!(eds.node_id(), NodeId::new(5));
+        let setpoint = eds.object(EntryId::new(0x2000, 0)).unwrap();
+        assert_eq!(setpoint.pdo_mapability(), PdoMapability::All);
+        assert!(matches!(
+            setpoint.high_limit(node_id),
+            Ok(Some(VariableType::Int16(500, _)))
+        ));
+
+        let od: ObjectDictionary<5, 0, 1> = ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            eds.tpdo_configurations(node_id).unwrap(),
+            [],
+            Vec::from_slice(&eds.variables(node_id).unwrap())
+                .ok()
+                .unwrap(),
+            node_id,
+        );
+        assert_eq!(od.read_unsigned(EntryId::new(0x1018, 1)), Some(0x1234));
+        assert_eq!(od.read_unsigned(EntryId::new(0x2000, 0)), Some(0xFFEC));
+        assert_eq!(
+            od.read_unsigned(EntryId::new(0x2001, 0)),
+            Some(0.25f32.to_bits() as u64)
+        );
+        let tpdo = od.tpdo_configuration(0).unwrap();
+        assert_eq!(tpdo.cob_id().raw(), 0x185);
+        assert_eq!(
+            tpdo.transmission_type(),
+            PdoTransmissionType::Synchronous(1)
+        );
+        assert_eq!(tpdo.mappings()[0].entry_id(), EntryId::new(0x2000, 0));
+    }
+
+    #[test]
+    fn test_upload() {
+        let eds_file = from_object_dictionary(&test_od());
+        let eds = Eds::parse(&eds_file).unwrap();
+        let mut od = test_od();
+        od.get_mut_variable(EntryId::new(0x2000, 0))
+            .unwrap()
+            .set_value(VariableType::Int16(300, &DefaultI16Coder))
+            .unwrap();
+        let mut bus = VirtualBus::new(od);
+
+        let node_id = NodeId::new(5).unwrap();
+        let uploaded = upload(
+            &mut SdoClient::new(),
+            &mut bus,
+            &StdClock::new(),
+            node_id,
+            &eds,
+        )
+        .unwrap();
+        assert_eq!(uploaded.aborted, []);
+        let dcf = uploaded.dcf;
+        assert!(dcf.contains("[2000]\nParameterName=Setpoint\nObjectType=0x7\nDataType=0x0003\nAccessType=rw\nPDOMapping=1\nLowLimit=-500\nHighLimit=500\nDefaultValue=-20\nParameterValue=300\n"));
+        assert!(dcf.contains("[1800sub1]\nParameterName=COB-ID\nObjectType=0x7\nDataType=0x0007\nAccessType=rw\nPDOMapping=0\nParameterValue=0x185\n"));
+        assert!(dcf.contains("[1A00sub1]\nParameterName=Mapped object 1\nObjectType=0x7\nDataType=0x0007\nAccessType=rw\nPDOMapping=0\nParameterValue=0x20000010\n"));
+        assert!(dcf.contains("[2002]\nParameterName=Command\nObjectType=0x7\nDataType=0x0005\nAccessType=wo\nPDOMapping=0\nDefaultValue=0x0\n\n"));
+
+        let eds = Eds::parse(&dcf).unwrap();
+        assert_eq!(eds.node_id(), Some(node_id));
+        let gain = eds.object(EntryId::new(0x2001, 0)).unwrap();
+        assert!(
+            matches!(gain.value(node_id), Ok(VariableType::Float32(value, _)) if value == 0.25)
+        );
+    }
+
+    #[test]
+    fn test_upload_aborts() {
+        let eds_file = from_object_dictionary(&test_od());
+        let eds = Eds::parse(&eds_file).unwrap();
+        let gain = EntryId::new(0x2001, 0);
+        let variables = test_od()
+            .variables()
+            .iter()
+            .filter(|v| v.id() != gain)
+            .copied()
+            .collect::<Vec<_, 5>>();
+        let mut bus = VirtualBus::new(ObjectDictionary::new(
+            0x01,
+            0,
+            [0; 8],
+            [Default::default()],
+            [],
+            variables,
+            NodeId::new(5).unwrap(),
+        ));
+
+        let uploaded = upload(
+            &mut SdoClient::new(),
+            &mut bus,
+            &StdClock::new(),
+            NodeId::new(5).unwrap(),
+            &eds,
+        )
+        .unwrap();
+        assert_eq!(uploaded.aborted, [(gain, SdoAbortCode::ObjectDoesNotExist)]);
+        assert!(uploaded.dcf.contains("[2001]\nParameterName=Gain\nObjectType=0x7\nDataType=0x0008\nAccessType=rw\nPDOMapping=0\nDefaultValue=0.25\n\n"));
+    }
+}
@@ -0,0 +1,539 @@
+use core::time::Duration;
+
+use embedded_can::{Frame, Id, StandardId};
+use heapless::{Deque, Vec};
+use num_derive::{FromPrimitive, ToPrimitive};
+use num_traits::FromPrimitive;
+
+use crate::{
+    frame::EncodedCANOpenFrame,
+    node::NodeId,
+    object_dictionary::{CobId, EntryId, ObjectDictionary},
+    time::Instant,
+};
+
+/// EMCY frames are sent on `0x80 + NodeId` by default.
+pub const EMCY_ID_OFFSET: u16 = 0x80;
+
+/// COB-ID EMCY. Bit 31 set disables the producer.
+pub const COB_ID_EMCY: EntryId = EntryId::new(0x1014, 0x0);
+
+/// Inhibit time EMCY, in multiples of 100µs.
+pub const INHIBIT_TIME_EMCY: EntryId = EntryId::new(0x1015, 0x0);
+
+/// Emergency consumer object. Sub-index n holds the EMCY COB-ID of node n.
+pub const EMERGENCY_CONSUMER_INDEX: u16 = 0x1028;
+
+/// Error register (0x1001) bits.
+pub const GENERIC_ERROR: u8 = 0x01;
+pub const CURRENT_ERROR: u8 = 0x02;
+pub const VOLTAGE_ERROR: u8 = 0x04;
+pub const TEMPERATURE_ERROR: u8 = 0x08;
+pub const COMMUNICATION_ERROR: u8 = 0x10;
+pub const DEVICE_PROFILE_ERROR: u8 = 0x20;
+pub const MANUFACTURER_ERROR: u8 = 0x80;
+
+/// Returns the error register bits set while `code` is active, besides the
+/// generic error bit.
+pub fn error_register_bits(code: u16) -> u8 {
+    match code >> 8 {
+        0x20..=0x2F => CURRENT_ERROR,
+        0x30..=0x3F => VOLTAGE_ERROR,
+        0x40..=0x4F => TEMPERATURE_ERROR,
+        0x81 | 0x82 => COMMUNICATION_ERROR,
+        0xFF => MANUFACTURER_ERROR,
+        _ => 0,
+    }
+}
+
+/// Standard error codes of CiA 301. Manufacturer specific codes are passed as
+/// a raw `u16` instead.
+#[derive(FromPrimitive, ToPrimitive, Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u16)]
+pub enum ErrorCode {
+    /// Error reset or no error.
+    NoError = 0x0000,
+    Generic = 0x1000,
+    Current = 0x2000,
+    CurrentInputSide = 0x2100,
+    CurrentInsideDevice = 0x2200,
+    CurrentOutputSide = 0x2300,
+    Voltage = 0x3000,
+    MainsVoltage = 0x3100,
+    VoltageInsideDevice = 0x3200,
+    OutputVoltage = 0x3300,
+    Temperature = 0x4000,
+    AmbientTemperature = 0x4100,
+    DeviceTemperature = 0x4200,
+    DeviceHardware = 0x5000,
+    DeviceSoftware = 0x6000,
+    InternalSoftware = 0x6100,
+    UserSoftware = 0x6200,
+    DataSet = 0x6300,
+    AdditionalModules = 0x7000,
+    Monitoring = 0x8000,
+    Communication = 0x8100,
+    CanOverrun = 0x8110,
+    CanErrorPassive = 0x8120,
+    LifeGuardOrHeartbeat = 0x8130,
+    RecoveredFromBusOff = 0x8140,
+    CanIdCollision = 0x8150,
+    ProtocolError = 0x8200,
+    PdoLengthError = 0x8210,
+    PdoLengthExceeded = 0x8220,
+    DamMpdoNotProcessed = 0x8230,
+    UnexpectedSyncLength = 0x8240,
+    RpdoTimeout = 0x8250,
+    External = 0x9000,
+    AdditionalFunctions = 0xF000,
+    DeviceSpecific = 0xFF00,
+}
+
+impl ErrorCode {
+    pub fn from_u16(raw: u16) -> Option<Self> {
+        FromPrimitive::from_u16(raw)
+    }
+}
+
+impl From<ErrorCode> for u16 {
+    fn from(code: ErrorCode) -> u16 {
+        code as u16
+    }
+}
+
+/// Encodes an emergency frame.
+pub fn encode(
+    id: impl Into<Id>,
+    code: u16,
+    register: u8,
+    manufacturer_data: [u8; 5],
+) -> EncodedCANOpenFrame {
+    let mut data: Vec<u8, 8> = Vec::new();
+    data.extend_from_slice(&code.to_le_bytes()).unwrap();
+    data.push(register).unwrap();
+    data.extend_from_slice(&manufacturer_data).unwrap();
+    EncodedCANOpenFrame::from_vec_data(id, data)
+}
+
+#[derive(Copy, Clone)]
+struct PendingEmergency {
+    code: u16,
+    register: u8,
+    manufacturer_data: [u8; 5],
+}
+
+/// Tracks the active errors of a node and produces its emergency messages.
+///
+/// Up to `N` errors can be active at once, and as many messages can wait for
+/// the inhibit time to elapse. Once the last active error is cleared, an error
+/// reset message is sent.
+pub struct EmcyProducer<const N: usize> {
+    active: Vec<u16, N>,
+    pending: Deque<PendingEmergency, N>,
+    last_sent: Option<Instant>,
+}
+
+impl<const N: usize> Default for EmcyProducer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> EmcyProducer<N> {
+    pub fn new() -> Self {
+        Self {
+            active: Vec::new(),
+            pending: Deque::new(),
+            last_sent: None,
+        }
+    }
+
+    /// Drops the messages waiting to be sent, keeping the active errors.
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.last_sent = None;
+    }
+
+    /// Returns the codes of the active errors, oldest first.
+    pub fn active_errors(&self) -> &[u16] {
+        &self.active
+    }
+
+    /// Returns the error register (0x1001) value derived from the active
+    /// errors.
+    pub fn error_register(&self) -> u8 {
+        self.active.iter().fold(0, |register, code| {
+            register | GENERIC_ERROR | error_register_bits(*code)
+        })
+    }
+
+    /// Raises an error and queues its emergency message, returning whether it
+    /// was not already active. An active error is not sent again.
+    pub fn raise(&mut self, code: impl Into<u16>, manufacturer_data: [u8; 5]) -> bool {
+        let code = code.into();
+        if self.active.contains(&code) {
+            return false;
+        }
+        // When the set is full the error is still reported, only not tracked.
+        self.active.push(code).ok();
+        self.push(PendingEmergency {
+            code,
+            register: self.error_register(),
+            manufacturer_data,
+        });
+        true
+    }
+
+    /// Clears an active error, queuing an error reset message if it was the
+    /// last one.
+    pub fn clear(&mut self, code: impl Into<u16>) {
+        let code = code.into();
+        let Some(position) = self.active.iter().position(|active| *active == code) else {
+            return;
+        };
+        self.active.remove(position);
+        if self.active.is_empty() {
+            self.push(PendingEmergency {
+                code: ErrorCode::NoError.into(),
+                register: 0,
+                manufacturer_data: [0; 5],
+            });
+        }
+    }
+
+    /// Returns the next emergency message to send at `now`, once the inhibit
+    /// time since the previous one has elapsed.
+    pub fn poll_frame<
+        const ENTRY_COUNT: usize,
+        const RPDO_COUNT: usize,
+        const TPDO_COUNT: usize,
+    >(
+        &mut self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        now: Instant,
+    ) -> Option<EncodedCANOpenFrame> {
+        let inhibit_time =
+            Duration::from_micros(od.read_unsigned(INHIBIT_TIME_EMCY).unwrap_or(0) * 100);
+        if self
+            .last_sent
+            .is_some_and(|last_sent| now < last_sent + inhibit_time)
+        {
+            return None;
+        }
+
+        let emergency = self.pending.pop_front()?;
+        let id = Self::cob_id(od)?;
+        self.last_sent = Some(now);
+        Some(encode(
+            id,
+            emergency.code,
+            emergency.register,
+            emergency.manufacturer_data,
+        ))
+    }
+
+    /// Reads the EMCY COB-ID, or `None` if the producer is disabled.
+    fn cob_id<const ENTRY_COUNT: usize, const RPDO_COUNT: usize, const TPDO_COUNT: usize>(
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+    ) -> Option<Id> {
+        let Some(raw) = od.read_unsigned(COB_ID_EMCY) else {
+            let id = EMCY_ID_OFFSET + od.node_id().raw() as u16;
+            return Some(Id::Standard(StandardId::new(id).unwrap()));
+        };
+        let cob_id = CobId::from_raw(raw as u32);
+        cob_id.is_valid().then(|| cob_id.can_id())
+    }
+
+    fn push(&mut self, emergency: PendingEmergency) {
+        if self.pending.is_full() {
+            self.pending.pop_front();
+        }
+        self.pending.push_back(emergency).ok();
+    }
+}
+
+/// An emergency message received from another node.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Emergency {
+    pub node: NodeId,
+    pub code: u16,
+    pub register: u8,
+    pub manufacturer_data: [u8; 5],
+}
+
+impl Emergency {
+    /// Decodes an emergency frame sent by `node`.
+    pub fn decode(node: NodeId, frame: &impl Frame) -> Option<Self> {
+        if frame.is_remote_frame() || frame.dlc() != 8 {
+            return None;
+        }
+        let data = frame.data();
+        Some(Self {
+            node,
+            code: u16::from_le_bytes([data[0], data[1]]),
+            register: data[2],
+            manufacturer_data: data[3..8].try_into().unwrap(),
+        })
+    }
+
+    /// Returns the standard error code, if the code is one.
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        ErrorCode::from_u16(self.code)
+    }
+
+    /// Whether the message reports that the node has no active error left.
+    pub fn is_error_reset(&self) -> bool {
+        self.code == ErrorCode::NoError as u16
+    }
+}
+
+struct RemoteErrors<const ERRORS: usize> {
+    node: NodeId,
+    active: Vec<u16, ERRORS>,
+}
+
+/// Receives the emergency messages of the nodes configured in the emergency
+/// consumer object, and tracks the active errors of up to `NODES` nodes.
+pub struct EmcyConsumer<const NODES: usize, const ERRORS: usize> {
+    nodes: Vec<RemoteErrors<ERRORS>, NODES>,
+}
+
+impl<const NODES: usize, const ERRORS: usize> Default for EmcyConsumer<NODES, ERRORS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const NODES: usize, const ERRORS: usize> EmcyConsumer<NODES, ERRORS> {
+    pub fn new() -> Self {
+        Self { nodes: Vec::new() }
+    }
+
+    /// Forgets the active errors of every node.
+    pub fn reset(&mut self) {
+        self.nodes.clear();
+    }
+
+    /// Returns the errors reported active by `node`, oldest first.
+    pub fn active_errors(&self, node: NodeId) -> &[u16] {
+        self.nodes
+            .iter()
+            .find(|remote| remote.node == node)
+            .map(|remote| remote.active.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Handles an emergency frame, calling `on_emergency` with the decoded
+    /// message and the errors now active on its node. Returns `None` if the
+    /// frame is not a consumed emergency message.
+    pub fn process_frame<
+        const ENTRY_COUNT: usize,
+        const RPDO_COUNT: usize,
+        const TPDO_COUNT: usize,
+    >(
+        &mut self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        frame: &impl Frame,
+        mut on_emergency: impl FnMut(&Emergency, &[u16]),
+    ) -> Option<Emergency> {
+        let node = Self::find_node(od, frame.id())?;
+        let emergency = Emergency::decode(node, frame)?;
+
+        if self.nodes.iter().all(|remote| remote.node != node) {
+            // When full the message is still reported, only not tracked.
+            self.nodes
+                .push(RemoteErrors {
+                    node,
+                    active: Vec::new(),
+                })
+                .ok();
+        }
+        if let Some(remote) = self.nodes.iter_mut().find(|remote| remote.node == node) {
+            if emergency.is_error_reset() {
+                remote.active.clear();
+            } else if !remote.active.contains(&emergency.code) {
+                remote.active.push(emergency.code).ok();
+            }
+        }
+
+        on_emergency(&emergency, self.active_errors(node));
+        Some(emergency)
+    }
+
+    /// Looks up the node whose EMCY COB-ID is `id`.
+    fn find_node<const ENTRY_COUNT: usize, const RPDO_COUNT: usize, const TPDO_COUNT: usize>(
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        id: Id,
+    ) -> Option<NodeId> {
+        let count = od.read_unsigned(EntryId::new(EMERGENCY_CONSUMER_INDEX, 0))?;
+        (1..=count.min(127) as u8).find_map(|sub_index| {
+            let raw = od.read_unsigned(EntryId::new(EMERGENCY_CONSUMER_INDEX, sub_index))?;
+            let cob_id = CobId::from_raw(raw as u32);
+            (cob_id.is_valid() && cob_id.can_id() == id)
+                .then(|| NodeId::new(sub_index))
+                .flatten()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_can::Frame;
+    use heapless::Vec;
+
+    use crate::frame::EncodedCANOpenFrame;
+    use crate::node::NodeId;
+    use crate::object_dictionary::{AccessType, ObjectDictionary, Variable, VariableType};
+    use crate::parameter_coder::{DefaultU16Coder, DefaultU32Coder};
+    use crate::time::Instant;
+
+    use crate::object_dictionary::EntryId;
+
+    use super::{
+        EmcyConsumer, EmcyProducer, Emergency, ErrorCode, COB_ID_EMCY, EMERGENCY_CONSUMER_INDEX,
+        INHIBIT_TIME_EMCY,
+    };
+
+    fn test_od(cob_id: u32) -> ObjectDictionary<2, 0, 0> {
+        ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [],
+            Vec::from_slice(&[
+                Variable::new(
+                    COB_ID_EMCY,
+                    "COB-ID EMCY",
+                    VariableType::UInt32(cob_id, &DefaultU32Coder),
+                    AccessType::ReadWrite,
+                ),
+                Variable::new(
+                    INHIBIT_TIME_EMCY,
+                    "Inhibit time EMCY",
+                    VariableType::UInt16(100, &DefaultU16Coder),
+                    AccessType::ReadWrite,
+                ),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::new(3).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_raise_and_clear() {
+        let od = test_od(0x83);
+        let mut producer = EmcyProducer::<8>::new();
+
+        assert!(producer.raise(ErrorCode::DeviceTemperature, [1, 2, 3, 4, 5]));
+        assert!(!producer.raise(ErrorCode::DeviceTemperature, [1, 2, 3, 4, 5]));
+        producer.raise(0xFF01u16, [0; 5]);
+        assert_eq!(producer.active_errors(), [0x4200, 0xFF01]);
+
+        assert_eq!(producer.error_register(), 0x89);
+
+        let frame = producer.poll_frame(&od, Instant::from_millis(0)).unwrap();
+        assert_eq!(frame.id(), EncodedCANOpenFrame::new(0x083, &[]).id());
+        assert_eq!(frame.data(), [0x00, 0x42, 0x09, 1, 2, 3, 4, 5]);
+
+        // The 10ms inhibit time delays the second message.
+        assert!(producer.poll_frame(&od, Instant::from_millis(5)).is_none());
+        let frame = producer.poll_frame(&od, Instant::from_millis(10)).unwrap();
+        assert_eq!(frame.data(), [0x01, 0xFF, 0x89, 0, 0, 0, 0, 0]);
+
+        producer.clear(ErrorCode::DeviceTemperature);
+        assert!(producer.poll_frame(&od, Instant::from_millis(20)).is_none());
+        producer.clear(0xFF01u16);
+        let frame = producer.poll_frame(&od, Instant::from_millis(20)).unwrap();
+        assert_eq!(frame.data(), [0; 8]);
+        assert_eq!(producer.error_register(), 0);
+    }
+
+    #[test]
+    fn test_disabled_cob_id() {
+        let od = test_od(0x8000_0083);
+        let mut producer = EmcyProducer::<8>::new();
+        producer.raise(ErrorCode::Generic, [0; 5]);
+        assert!(producer.poll_frame(&od, Instant::from_millis(0)).is_none());
+        assert_eq!(producer.active_errors(), [0x1000]);
+    }
+
+    fn consumer_od() -> ObjectDictionary<3, 0, 0> {
+        let entry = |sub_index, value| {
+            Variable::new(
+                EntryId::new(EMERGENCY_CONSUMER_INDEX, sub_index),
+                "Emergency consumer object",
+                VariableType::UInt32(value, &DefaultU32Coder),
+                AccessType::ReadWrite,
+            )
+        };
+        ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [],
+            Vec::from_slice(&[
+                Variable::new(
+                    EntryId::new(EMERGENCY_CONSUMER_INDEX, 0),
+                    "Emergency consumer object",
+                    VariableType::Array(2),
+                    AccessType::ReadOnly,
+                ),
+                entry(1, 0x81),
+                entry(2, 0x8000_0082),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::new(10).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_consumer_tracks_active_errors() {
+        let od = consumer_od();
+        let mut consumer = EmcyConsumer::<4, 4>::new();
+        let node_1 = NodeId::new(1).unwrap();
+        let mut reported = std::vec::Vec::new();
+
+        let frame = EncodedCANOpenFrame::new(0x081, &[0x10, 0x82, 0x11, 1, 2, 3, 4, 5]);
+        let emergency = consumer
+            .process_frame(&od, &frame, |emergency, active| {
+                reported.push((*emergency, active.to_vec()))
+            })
+            .unwrap();
+        assert_eq!(
+            emergency,
+            Emergency {
+                node: node_1,
+                code: 0x8210,
+                register: 0x11,
+                manufacturer_data: [1, 2, 3, 4, 5],
+            }
+        );
+        assert_eq!(emergency.error_code(), Some(ErrorCode::PdoLengthError));
+        assert_eq!(reported, [(emergency, vec![0x8210])]);
+
+        let frame = EncodedCANOpenFrame::new(0x081, &[0x00, 0x42, 0x09, 0, 0, 0, 0, 0]);
+        consumer.process_frame(&od, &frame, |_, _| {});
+        assert_eq!(consumer.active_errors(node_1), [0x8210, 0x4200]);
+
+        let reset = EncodedCANOpenFrame::new(0x081, &[0; 8]);
+        assert!(consumer
+            .process_frame(&od, &reset, |_, _| {})
+            .unwrap()
+            .is_error_reset());
+        assert!(consumer.active_errors(node_1).is_empty());
+    }
+
+    #[test]
+    fn test_consumer_ignores_unconfigured_nodes() {
+        let od = consumer_od();
+        let mut consumer = EmcyConsumer::<4, 4>::new();
+        let disabled = EncodedCANOpenFrame::new(0x082, &[0x00, 0x10, 0x01, 0, 0, 0, 0, 0]);
+        assert!(consumer.process_frame(&od, &disabled, |_, _| {}).is_none());
+        let unknown = EncodedCANOpenFrame::new(0x083, &[0x00, 0x10, 0x01, 0, 0, 0, 0, 0]);
+        assert!(consumer.process_frame(&od, &unknown, |_, _| {}).is_none());
+    }
+}
@@ -4,6 +4,8 @@ use heapless::Vec;
 pub struct EncodedCANOpenFrame {
     id: Id,
     data: Vec<u8, 8>,
+    /// The requested data length of a remote frame, which carries no data.
+    remote_dlc: Option<usize>,
 }
 
 impl Frame for EncodedCANOpenFrame {
@@ -11,22 +13,27 @@ impl Frame for EncodedCANOpenFrame {
         Some(Self {
             id: id.into(),
             data: Vec::from_slice(data).ok()?,
+            remote_dlc: None,
         })
     }
 
     fn new_remote(id: impl Into<Id>, dlc: usize) -> Option<Self> {
-        todo!()
+        if dlc > 8 {
+            return None;
+        }
+        Some(Self {
+            id: id.into(),
+            data: Vec::new(),
+            remote_dlc: Some(dlc),
+        })
     }
 
     fn is_extended(&self) -> bool {
-        match self.id {
-            Id::Extended(_) => true,
-            _ => false,
-        }
+        matches!(self.id, Id::Extended(_))
     }
 
     fn is_remote_frame(&self) -> bool {
-        false
+        self.remote_dlc.is_some()
     }
 
     fn id(&self) -> Id {
@@ -34,7 +41,7 @@ impl Frame for EncodedCANOpenFrame {
     }
 
     fn dlc(&self) -> usize {
-        self.data.len()
+        self.remote_dlc.unwrap_or(self.data.len())
     }
 
     fn data(&self) -> &[u8] {
@@ -46,7 +53,8 @@ impl EncodedCANOpenFrame {
     pub(crate) fn from_vec_data(id: impl Into<Id>, data: Vec<u8, 8>) -> Self {
         Self {
             id: id.into(),
-            data: data,
+            data,
+            remote_dlc: None,
         }
     }
 }
@@ -59,6 +67,7 @@ impl EncodedCANOpenFrame {
         Self {
             id: unsafe { Id::Standard(embedded_can::StandardId::new_unchecked(std_id)) },
             data: Vec::from_slice(data).unwrap(),
+            remote_dlc: None,
         }
     }
 }
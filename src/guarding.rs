@@ -0,0 +1,449 @@
+use core::time::Duration;
+
+use embedded_can::{Frame, Id, StandardId};
+use heapless::Vec;
+
+use crate::{
+    frame::EncodedCANOpenFrame,
+    heartbeat::HEARTBEAT_ID_OFFSET,
+    nmt::NmtState,
+    node::NodeId,
+    object_dictionary::{EntryId, ObjectDictionary},
+    time::Instant,
+};
+
+/// Guard Time, in milliseconds.
+pub const GUARD_TIME: EntryId = EntryId::new(0x100C, 0x0);
+
+/// Life Time Factor. The node life time is Guard Time × Life Time Factor.
+pub const LIFE_TIME_FACTOR: EntryId = EntryId::new(0x100D, 0x0);
+
+const TOGGLE_BIT: u8 = 0x80;
+
+/// An event raised by node guarding or life guarding.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GuardingEvent {
+    /// No guarding request was received within the node life time.
+    LifeGuardingLost,
+    /// Guarding requests were received again after a life guarding loss.
+    LifeGuardingResumed,
+    /// A guarded node did not answer within its node life time.
+    NodeGuardingLost(NodeId),
+    /// A guarded node answered again after a node guarding loss.
+    NodeGuardingResumed(NodeId),
+    /// A guarded node answered without alternating the toggle bit.
+    ToggleError(NodeId),
+    /// A guarded node reported a different NMT state than the previous one.
+    StateChanged { node: NodeId, state: NmtState },
+}
+
+/// Answers node guarding requests and detects the loss of the guarding master.
+///
+/// Life guarding starts with the first guarding request, if the node life time
+/// configured in [`GUARD_TIME`] and [`LIFE_TIME_FACTOR`] is not 0.
+pub struct LifeGuard {
+    toggle: bool,
+    last_request: Option<Instant>,
+    lost: bool,
+}
+
+impl Default for LifeGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LifeGuard {
+    pub fn new() -> Self {
+        Self {
+            toggle: false,
+            last_request: None,
+            lost: false,
+        }
+    }
+
+    /// Resets the toggle bit and stops life guarding.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Handles a guarding request received at `now`, returning the response.
+    pub fn process_frame(
+        &mut self,
+        node_id: NodeId,
+        state: NmtState,
+        frame: &impl Frame,
+        now: Instant,
+        mut on_event: impl FnMut(GuardingEvent),
+    ) -> Option<EncodedCANOpenFrame> {
+        let id = guarding_id(node_id);
+        if !frame.is_remote_frame() || frame.id() != Id::Standard(id) {
+            return None;
+        }
+
+        self.last_request = Some(now);
+        if self.lost {
+            self.lost = false;
+            on_event(GuardingEvent::LifeGuardingResumed);
+        }
+
+        let toggle = if self.toggle { TOGGLE_BIT } else { 0 };
+        self.toggle = !self.toggle;
+        Some(EncodedCANOpenFrame::from_vec_data(
+            id,
+            Vec::from_slice(&[toggle | state as u8]).unwrap(),
+        ))
+    }
+
+    /// Checks for a life guarding loss at `now`.
+    pub fn tick<const ENTRY_COUNT: usize, const RPDO_COUNT: usize, const TPDO_COUNT: usize>(
+        &mut self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        now: Instant,
+        mut on_event: impl FnMut(GuardingEvent),
+    ) {
+        let (Some(last_request), Some(life_time)) = (self.last_request, node_life_time(od)) else {
+            return;
+        };
+        if !self.lost && now.saturating_duration_since(last_request) > life_time {
+            self.lost = true;
+            on_event(GuardingEvent::LifeGuardingLost);
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+struct GuardedNode {
+    node_id: NodeId,
+    guard_time: Duration,
+    life_time_factor: u8,
+    next_request: Option<Instant>,
+    request_pending: bool,
+    answered: bool,
+    missed: u8,
+    lost: bool,
+    toggle: Option<bool>,
+    state: Option<NmtState>,
+}
+
+/// Error returned when no more nodes can be guarded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CapacityError;
+
+/// Polls up to `N` nodes with guarding requests and checks their answers.
+///
+/// Requests to transmit are retrieved with [`NodeGuardingMaster::poll_frame`]
+/// after each [`NodeGuardingMaster::tick`]. A node is reported lost once it
+/// missed as many consecutive requests as its life time factor.
+pub struct NodeGuardingMaster<const N: usize> {
+    nodes: Vec<GuardedNode, N>,
+}
+
+impl<const N: usize> Default for NodeGuardingMaster<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> NodeGuardingMaster<N> {
+    pub fn new() -> Self {
+        Self { nodes: Vec::new() }
+    }
+
+    /// Starts guarding `node_id` every `guard_time_ms`, replacing a previous
+    /// configuration for the same node.
+    pub fn guard(
+        &mut self,
+        node_id: NodeId,
+        guard_time_ms: u16,
+        life_time_factor: u8,
+    ) -> Result<(), CapacityError> {
+        let node = GuardedNode {
+            node_id,
+            guard_time: Duration::from_millis(guard_time_ms as u64),
+            life_time_factor: life_time_factor.max(1),
+            next_request: None,
+            request_pending: false,
+            answered: true,
+            missed: 0,
+            lost: false,
+            toggle: None,
+            state: None,
+        };
+        match self.nodes.iter_mut().find(|n| n.node_id == node_id) {
+            Some(existing) => *existing = node,
+            None => self.nodes.push(node).map_err(|_| CapacityError)?,
+        }
+        Ok(())
+    }
+
+    /// Stops guarding `node_id`.
+    pub fn unguard(&mut self, node_id: NodeId) {
+        self.nodes.retain(|n| n.node_id != node_id);
+    }
+
+    /// Returns the last NMT state reported by a guarded node.
+    pub fn state(&self, node_id: NodeId) -> Option<NmtState> {
+        self.nodes
+            .iter()
+            .find(|n| n.node_id == node_id)
+            .and_then(|n| n.state)
+    }
+
+    /// Schedules the guarding requests due at `now`, reporting the nodes
+    /// that missed too many of them.
+    pub fn tick(&mut self, now: Instant, mut on_event: impl FnMut(GuardingEvent)) {
+        for node in self.nodes.iter_mut() {
+            if node.next_request.is_some_and(|next| now < next) {
+                continue;
+            }
+            if node.answered {
+                node.missed = 0;
+            } else {
+                node.missed = node.missed.saturating_add(1);
+                if node.missed >= node.life_time_factor && !node.lost {
+                    node.lost = true;
+                    on_event(GuardingEvent::NodeGuardingLost(node.node_id));
+                }
+            }
+            node.answered = false;
+            node.request_pending = true;
+            node.next_request = Some(now + node.guard_time);
+        }
+    }
+
+    /// Returns the next guarding request to transmit, if any.
+    pub fn poll_frame(&mut self) -> Option<EncodedCANOpenFrame> {
+        let node = self.nodes.iter_mut().find(|n| n.request_pending)?;
+        node.request_pending = false;
+        EncodedCANOpenFrame::new_remote(guarding_id(node.node_id), 1)
+    }
+
+    /// Handles a guarding answer, returning whether it came from a guarded
+    /// node.
+    pub fn process_frame(
+        &mut self,
+        frame: &impl Frame,
+        mut on_event: impl FnMut(GuardingEvent),
+    ) -> bool {
+        let raw_id = match frame.id() {
+            Id::Standard(id) => id.as_raw(),
+            Id::Extended(_) => return false,
+        };
+        if frame.is_remote_frame() || frame.dlc() != 1 {
+            return false;
+        }
+        let Some(node) = self
+            .nodes
+            .iter_mut()
+            .find(|n| guarding_id(n.node_id).as_raw() == raw_id)
+        else {
+            return false;
+        };
+
+        let data = frame.data()[0];
+        let toggle = data & TOGGLE_BIT != 0;
+        node.answered = true;
+        if node.lost {
+            node.lost = false;
+            on_event(GuardingEvent::NodeGuardingResumed(node.node_id));
+        }
+        if node.toggle.is_some_and(|previous| previous == toggle) {
+            on_event(GuardingEvent::ToggleError(node.node_id));
+        }
+        node.toggle = Some(toggle);
+
+        if let Some(state) = NmtState::from_u8(data & !TOGGLE_BIT) {
+            if node.state.replace(state) != Some(state) {
+                on_event(GuardingEvent::StateChanged {
+                    node: node.node_id,
+                    state,
+                });
+            }
+        }
+        true
+    }
+}
+
+fn guarding_id(node_id: NodeId) -> StandardId {
+    StandardId::new(HEARTBEAT_ID_OFFSET + node_id.raw() as u16).unwrap()
+}
+
+/// Returns the node life time, or `None` if life guarding is disabled.
+fn node_life_time<const ENTRY_COUNT: usize, const RPDO_COUNT: usize, const TPDO_COUNT: usize>(
+    od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+) -> Option<Duration> {
+    let guard_time = od.read_unsigned(GUARD_TIME)?;
+    let factor = od.read_unsigned(LIFE_TIME_FACTOR)?;
+    match guard_time * factor {
+        0 => None,
+        ms => Some(Duration::from_millis(ms)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_can::{Frame, StandardId};
+    use heapless::Vec;
+
+    use crate::frame::EncodedCANOpenFrame;
+    use crate::nmt::NmtState;
+    use crate::node::NodeId;
+    use crate::object_dictionary::{AccessType, ObjectDictionary, Variable, VariableType};
+    use crate::parameter_coder::{DefaultU16Coder, DefaultU8Coder};
+    use crate::time::Instant;
+
+    use super::{GuardingEvent, LifeGuard, NodeGuardingMaster, GUARD_TIME, LIFE_TIME_FACTOR};
+
+    fn test_od() -> ObjectDictionary<2, 0, 0> {
+        ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [],
+            Vec::from_slice(&[
+                Variable::new(
+                    GUARD_TIME,
+                    "Guard time",
+                    VariableType::UInt16(100, &DefaultU16Coder),
+                    AccessType::ReadWrite,
+                ),
+                Variable::new(
+                    LIFE_TIME_FACTOR,
+                    "Life time factor",
+                    VariableType::UInt8(3, &DefaultU8Coder),
+                    AccessType::ReadWrite,
+                ),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::new(5).unwrap(),
+        )
+    }
+
+    fn request(node: u16) -> EncodedCANOpenFrame {
+        EncodedCANOpenFrame::new_remote(StandardId::new(0x700 + node).unwrap(), 1).unwrap()
+    }
+
+    #[test]
+    fn test_remote_frame() {
+        let frame = request(5);
+        assert!(frame.is_remote_frame());
+        assert_eq!(frame.dlc(), 1);
+        assert!(frame.data().is_empty());
+        assert!(EncodedCANOpenFrame::new_remote(StandardId::new(0x705).unwrap(), 9).is_none());
+        assert!(!EncodedCANOpenFrame::new(0x705, &[0x05]).is_remote_frame());
+    }
+
+    #[test]
+    fn test_slave_toggles_answers() {
+        let node_id = NodeId::new(5).unwrap();
+        let mut guard = LifeGuard::new();
+        let state = NmtState::Operational;
+
+        let answer = guard
+            .process_frame(node_id, state, &request(5), Instant::from_millis(0), |_| {})
+            .unwrap();
+        assert_eq!(answer.id(), EncodedCANOpenFrame::new(0x705, &[]).id());
+        assert_eq!(answer.data(), [0x05]);
+        let answer = guard
+            .process_frame(node_id, state, &request(5), Instant::from_millis(0), |_| {})
+            .unwrap();
+        assert_eq!(answer.data(), [0x85]);
+
+        assert!(guard
+            .process_frame(node_id, state, &request(6), Instant::from_millis(0), |_| {})
+            .is_none());
+        let data_frame = EncodedCANOpenFrame::new(0x705, &[0x05]);
+        assert!(guard
+            .process_frame(node_id, state, &data_frame, Instant::from_millis(0), |_| {})
+            .is_none());
+    }
+
+    #[test]
+    fn test_slave_life_guarding() {
+        let od = test_od();
+        let node_id = NodeId::new(5).unwrap();
+        let mut guard = LifeGuard::new();
+        let mut events = std::vec::Vec::new();
+
+        // Not started before the first request.
+        guard.tick(&od, Instant::from_millis(1000), |e| events.push(e));
+        assert!(events.is_empty());
+
+        let state = NmtState::PreOperational;
+        guard.process_frame(
+            node_id,
+            state,
+            &request(5),
+            Instant::from_millis(1000),
+            |e| events.push(e),
+        );
+        guard.tick(&od, Instant::from_millis(1300), |e| events.push(e));
+        assert!(events.is_empty());
+        guard.tick(&od, Instant::from_millis(1301), |e| events.push(e));
+        guard.tick(&od, Instant::from_millis(1400), |e| events.push(e));
+        assert_eq!(events, [GuardingEvent::LifeGuardingLost]);
+
+        guard.process_frame(
+            node_id,
+            state,
+            &request(5),
+            Instant::from_millis(1500),
+            |e| events.push(e),
+        );
+        assert_eq!(events[1], GuardingEvent::LifeGuardingResumed);
+    }
+
+    #[test]
+    fn test_master_guarding() {
+        let node_id = NodeId::new(7).unwrap();
+        let mut master = NodeGuardingMaster::<4>::new();
+        master.guard(node_id, 100, 2).unwrap();
+        let mut events = std::vec::Vec::new();
+
+        master.tick(Instant::from_millis(0), |e| events.push(e));
+        let rtr = master.poll_frame().unwrap();
+        assert!(rtr.is_remote_frame());
+        assert_eq!(rtr.id(), EncodedCANOpenFrame::new(0x707, &[]).id());
+        assert!(master.poll_frame().is_none());
+
+        assert!(
+            master.process_frame(&EncodedCANOpenFrame::new(0x707, &[0x7F]), |e| events
+                .push(e))
+        );
+        assert_eq!(master.state(node_id), Some(NmtState::PreOperational));
+
+        master.tick(Instant::from_millis(100), |e| events.push(e));
+        master.poll_frame().unwrap();
+        // Same toggle bit as the previous answer.
+        master.process_frame(&EncodedCANOpenFrame::new(0x707, &[0x7F]), |e| {
+            events.push(e)
+        });
+        assert_eq!(
+            events,
+            [
+                GuardingEvent::StateChanged {
+                    node: node_id,
+                    state: NmtState::PreOperational
+                },
+                GuardingEvent::ToggleError(node_id)
+            ]
+        );
+        events.clear();
+
+        master.tick(Instant::from_millis(200), |e| events.push(e));
+        master.tick(Instant::from_millis(300), |e| events.push(e));
+        assert!(events.is_empty());
+        master.tick(Instant::from_millis(400), |e| events.push(e));
+        assert_eq!(events, [GuardingEvent::NodeGuardingLost(node_id)]);
+        master.tick(Instant::from_millis(500), |e| events.push(e));
+        assert_eq!(events.len(), 1);
+
+        master.process_frame(&EncodedCANOpenFrame::new(0x707, &[0x85]), |e| {
+            events.push(e)
+        });
+        assert_eq!(events[1], GuardingEvent::NodeGuardingResumed(node_id));
+    }
+}
@@ -0,0 +1,460 @@
+use core::time::Duration;
+
+use embedded_can::{Frame, Id, StandardId};
+use heapless::Vec;
+
+use crate::{
+    frame::EncodedCANOpenFrame,
+    nmt::NmtState,
+    node::NodeId,
+    object_dictionary::{EntryId, ObjectDictionary},
+    time::Instant,
+};
+
+/// Heartbeat frames are sent on the NMT error control COB-ID.
+pub const HEARTBEAT_ID_OFFSET: u16 = 0x700;
+
+/// Producer Heartbeat Time, in milliseconds. 0 disables the producer.
+pub const PRODUCER_HEARTBEAT_TIME: EntryId = EntryId::new(0x1017, 0x0);
+
+/// Consumer Heartbeat Time array. Sub-index 0 holds the number of entries,
+/// each following one the monitored node id in bits 16..=23 and the timeout in
+/// milliseconds in bits 0..=15.
+pub const CONSUMER_HEARTBEAT_TIME_INDEX: u16 = 0x1016;
+
+/// Periodically produces heartbeat frames carrying the NMT state of a node.
+///
+/// The period is read from [`PRODUCER_HEARTBEAT_TIME`] on every tick, so a
+/// new value written over SDO takes effect immediately.
+pub struct HeartbeatProducer {
+    period_ms: u16,
+    next: Option<Instant>,
+}
+
+impl Default for HeartbeatProducer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HeartbeatProducer {
+    pub fn new() -> Self {
+        Self {
+            period_ms: 0,
+            next: None,
+        }
+    }
+
+    /// Restarts the producer, e.g. after the communication has been reset.
+    pub fn reset(&mut self) {
+        self.period_ms = 0;
+        self.next = None;
+    }
+
+    /// Returns the heartbeat frame to send at `now`, if one is due.
+    pub fn tick<const ENTRY_COUNT: usize, const RPDO_COUNT: usize, const TPDO_COUNT: usize>(
+        &mut self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        state: NmtState,
+        now: Instant,
+    ) -> Option<EncodedCANOpenFrame> {
+        let period_ms = od.read_unsigned(PRODUCER_HEARTBEAT_TIME).unwrap_or(0) as u16;
+        if period_ms != self.period_ms {
+            self.period_ms = period_ms;
+            self.next = None;
+        }
+        if period_ms == 0 || state == NmtState::Initialising {
+            self.next = None;
+            return None;
+        }
+
+        let period = Duration::from_millis(period_ms as u64);
+        match self.next {
+            None => {
+                self.next = Some(now + period);
+                None
+            }
+            Some(next) if now >= next => {
+                // Schedule from the previous deadline to avoid drift, unless
+                // ticks have fallen behind by more than a period.
+                let following = next + period;
+                self.next = Some(if following > now {
+                    following
+                } else {
+                    now + period
+                });
+                Some(Self::encode(od.node_id(), state))
+            }
+            Some(_) => None,
+        }
+    }
+
+    pub fn encode(node_id: NodeId, state: NmtState) -> EncodedCANOpenFrame {
+        EncodedCANOpenFrame::from_vec_data(
+            StandardId::new(HEARTBEAT_ID_OFFSET + node_id.raw() as u16).unwrap(),
+            Vec::from_slice(&[state as u8]).unwrap(),
+        )
+    }
+}
+
+/// An event raised by the [`HeartbeatConsumer`] for a monitored node.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HeartbeatEvent {
+    /// A boot-up frame was received.
+    BootUp(NodeId),
+    /// A heartbeat reported a different NMT state than the previous one.
+    StateChanged { node: NodeId, state: NmtState },
+    /// No heartbeat was received within the consumer heartbeat time.
+    Timeout(NodeId),
+}
+
+#[derive(Copy, Clone, Default)]
+struct Monitor {
+    node_id: Option<NodeId>,
+    state: Option<NmtState>,
+    last_seen: Option<Instant>,
+}
+
+/// Monitors the heartbeats of up to `N` nodes, as configured in the Consumer
+/// Heartbeat Time array.
+///
+/// A monitor is armed by the first heartbeat received from its node, and
+/// disarmed again when it times out. The configuration is read from the
+/// object dictionary on every call, so changes made over SDO apply
+/// immediately.
+pub struct HeartbeatConsumer<const N: usize> {
+    monitors: [Monitor; N],
+}
+
+impl<const N: usize> Default for HeartbeatConsumer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> HeartbeatConsumer<N> {
+    pub fn new() -> Self {
+        Self {
+            monitors: [Monitor::default(); N],
+        }
+    }
+
+    /// Disarms every monitor.
+    pub fn reset(&mut self) {
+        self.monitors = [Monitor::default(); N];
+    }
+
+    /// Returns the last NMT state reported by `node`, if it is monitored and
+    /// has been heard of.
+    pub fn state(&self, node: NodeId) -> Option<NmtState> {
+        self.monitors
+            .iter()
+            .find(|monitor| monitor.node_id == Some(node))
+            .and_then(|monitor| monitor.state)
+    }
+
+    /// Returns the time the last heartbeat of `node` was received, if its
+    /// monitor is armed.
+    pub fn last_seen(&self, node: NodeId) -> Option<Instant> {
+        self.monitors
+            .iter()
+            .find(|monitor| monitor.node_id == Some(node))
+            .and_then(|monitor| monitor.last_seen)
+    }
+
+    /// Handles a heartbeat or boot-up frame received at `now`, returning
+    /// whether it came from a monitored node.
+    pub fn process_frame<
+        const ENTRY_COUNT: usize,
+        const RPDO_COUNT: usize,
+        const TPDO_COUNT: usize,
+    >(
+        &mut self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        frame: &impl Frame,
+        now: Instant,
+        mut on_event: impl FnMut(HeartbeatEvent),
+    ) -> bool {
+        let Some((node, state)) = Self::decode(frame) else {
+            return false;
+        };
+        self.update_configuration(od);
+
+        let Some(monitor) = self
+            .monitors
+            .iter_mut()
+            .find(|monitor| monitor.node_id == Some(node))
+        else {
+            return false;
+        };
+        monitor.last_seen = Some(now);
+        let previous = monitor.state.replace(state);
+        if state == NmtState::Initialising {
+            on_event(HeartbeatEvent::BootUp(node));
+        } else if previous != Some(state) {
+            on_event(HeartbeatEvent::StateChanged { node, state });
+        }
+        true
+    }
+
+    /// Checks the armed monitors for timeouts at `now`.
+    pub fn tick<const ENTRY_COUNT: usize, const RPDO_COUNT: usize, const TPDO_COUNT: usize>(
+        &mut self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        now: Instant,
+        mut on_event: impl FnMut(HeartbeatEvent),
+    ) {
+        self.update_configuration(od);
+
+        for (sub_index, monitor) in (1..).zip(self.monitors.iter_mut()) {
+            let (Some(node), Some(last_seen)) = (monitor.node_id, monitor.last_seen) else {
+                continue;
+            };
+            let Some((_, time_ms)) = Self::read_entry(od, sub_index) else {
+                continue;
+            };
+            if now.saturating_duration_since(last_seen) > Duration::from_millis(time_ms as u64) {
+                monitor.last_seen = None;
+                on_event(HeartbeatEvent::Timeout(node));
+            }
+        }
+    }
+
+    fn update_configuration<
+        const ENTRY_COUNT: usize,
+        const RPDO_COUNT: usize,
+        const TPDO_COUNT: usize,
+    >(
+        &mut self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+    ) {
+        for (sub_index, monitor) in (1..).zip(self.monitors.iter_mut()) {
+            let node_id = Self::read_entry(od, sub_index).map(|(node_id, _)| node_id);
+            if node_id != monitor.node_id {
+                *monitor = Monitor {
+                    node_id,
+                    ..Default::default()
+                };
+            }
+        }
+    }
+
+    /// Reads an enabled entry of the Consumer Heartbeat Time array.
+    fn read_entry<const ENTRY_COUNT: usize, const RPDO_COUNT: usize, const TPDO_COUNT: usize>(
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        sub_index: u8,
+    ) -> Option<(NodeId, u16)> {
+        let count = od.read_unsigned(EntryId::new(CONSUMER_HEARTBEAT_TIME_INDEX, 0))?;
+        if sub_index as u64 > count {
+            return None;
+        }
+        let raw = od.read_unsigned(EntryId::new(CONSUMER_HEARTBEAT_TIME_INDEX, sub_index))?;
+        let node_id = (raw >> 16) as u8;
+        let time_ms = raw as u16;
+        if node_id == 0 || time_ms == 0 {
+            return None;
+        }
+        Some((NodeId::new(node_id)?, time_ms))
+    }
+
+    fn decode(frame: &impl Frame) -> Option<(NodeId, NmtState)> {
+        let raw_id = match frame.id() {
+            Id::Standard(id) => id.as_raw(),
+            Id::Extended(_) => return None,
+        };
+        if frame.is_remote_frame()
+            || frame.dlc() != 1
+            || raw_id <= HEARTBEAT_ID_OFFSET
+            || raw_id > HEARTBEAT_ID_OFFSET + NodeId::node_id_mask()
+        {
+            return None;
+        }
+        let node = NodeId::new((raw_id - HEARTBEAT_ID_OFFSET) as u8)?;
+        // The toggle bit is only used by node guarding.
+        Some((node, NmtState::from_u8(frame.data()[0] & 0x7F)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_can::Frame;
+    use heapless::Vec;
+
+    use crate::frame::EncodedCANOpenFrame;
+    use crate::nmt::NmtState;
+    use crate::node::NodeId;
+    use crate::object_dictionary::EntryId;
+    use crate::object_dictionary::{AccessType, ObjectDictionary, Variable, VariableType};
+    use crate::parameter_coder::{DefaultU16Coder, DefaultU32Coder};
+    use crate::time::Instant;
+
+    use super::{
+        HeartbeatConsumer, HeartbeatEvent, HeartbeatProducer, CONSUMER_HEARTBEAT_TIME_INDEX,
+        PRODUCER_HEARTBEAT_TIME,
+    };
+
+    fn test_od(period_ms: u16) -> ObjectDictionary<1, 0, 0> {
+        ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [],
+            Vec::from_slice(&[Variable::new(
+                PRODUCER_HEARTBEAT_TIME,
+                "Producer heartbeat time",
+                VariableType::UInt16(period_ms, &DefaultU16Coder),
+                AccessType::ReadWrite,
+            )])
+            .ok()
+            .unwrap(),
+            NodeId::new(3).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_periodic_heartbeat() {
+        let od = test_od(100);
+        let mut producer = HeartbeatProducer::new();
+
+        let state = NmtState::PreOperational;
+        assert!(producer.tick(&od, state, Instant::from_millis(0)).is_none());
+        assert!(producer
+            .tick(&od, state, Instant::from_millis(99))
+            .is_none());
+
+        let frame = producer
+            .tick(&od, state, Instant::from_millis(100))
+            .unwrap();
+        assert_eq!(frame.id(), EncodedCANOpenFrame::new(0x703, &[]).id());
+        assert_eq!(frame.data(), [0x7F]);
+
+        assert!(producer
+            .tick(&od, state, Instant::from_millis(150))
+            .is_none());
+        let frame = producer
+            .tick(&od, NmtState::Operational, Instant::from_millis(205))
+            .unwrap();
+        assert_eq!(frame.data(), [0x05]);
+        // The next deadline is kept on the original grid.
+        assert!(producer
+            .tick(&od, NmtState::Operational, Instant::from_millis(300))
+            .is_some());
+    }
+
+    #[test]
+    fn test_disabled_and_reconfigured() {
+        let mut od = test_od(0);
+        let mut producer = HeartbeatProducer::new();
+        let state = NmtState::Operational;
+
+        assert!(producer.tick(&od, state, Instant::from_millis(0)).is_none());
+        assert!(producer
+            .tick(&od, state, Instant::from_millis(10_000))
+            .is_none());
+
+        od.write_raw(PRODUCER_HEARTBEAT_TIME, &50u16.to_le_bytes())
+            .unwrap();
+        assert!(producer
+            .tick(&od, state, Instant::from_millis(10_010))
+            .is_none());
+        assert!(producer
+            .tick(&od, state, Instant::from_millis(10_060))
+            .is_some());
+
+        od.write_raw(PRODUCER_HEARTBEAT_TIME, &0u16.to_le_bytes())
+            .unwrap();
+        assert!(producer
+            .tick(&od, state, Instant::from_millis(20_000))
+            .is_none());
+    }
+
+    fn consumer_od(entries: [u32; 2]) -> ObjectDictionary<3, 0, 0> {
+        let variables = [
+            Variable::new(
+                EntryId::new(CONSUMER_HEARTBEAT_TIME_INDEX, 0),
+                "Consumer heartbeat time",
+                VariableType::Array(2),
+                AccessType::ReadOnly,
+            ),
+            Variable::new(
+                EntryId::new(CONSUMER_HEARTBEAT_TIME_INDEX, 1),
+                "Consumer heartbeat time 1",
+                VariableType::UInt32(entries[0], &DefaultU32Coder),
+                AccessType::ReadWrite,
+            ),
+            Variable::new(
+                EntryId::new(CONSUMER_HEARTBEAT_TIME_INDEX, 2),
+                "Consumer heartbeat time 2",
+                VariableType::UInt32(entries[1], &DefaultU32Coder),
+                AccessType::ReadWrite,
+            ),
+        ];
+        ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [],
+            Vec::from_slice(&variables).ok().unwrap(),
+            NodeId::new(1).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_consumer_events() {
+        let od = consumer_od([0x0002_0064, 0x0003_00C8]);
+        let mut consumer = HeartbeatConsumer::<2>::new();
+        let mut events = std::vec::Vec::new();
+        let node_2 = NodeId::new(2).unwrap();
+
+        let boot_up = EncodedCANOpenFrame::new(0x702, &[0x00]);
+        assert!(consumer.process_frame(&od, &boot_up, Instant::from_millis(0), |e| events.push(e)));
+        let pre_op = EncodedCANOpenFrame::new(0x702, &[0x7F]);
+        consumer.process_frame(&od, &pre_op, Instant::from_millis(50), |e| events.push(e));
+        consumer.process_frame(&od, &pre_op, Instant::from_millis(100), |e| events.push(e));
+        assert_eq!(
+            events,
+            [
+                HeartbeatEvent::BootUp(node_2),
+                HeartbeatEvent::StateChanged {
+                    node: node_2,
+                    state: NmtState::PreOperational
+                }
+            ]
+        );
+        assert_eq!(consumer.state(node_2), Some(NmtState::PreOperational));
+        events.clear();
+
+        // Node 3 never sent a heartbeat, so only node 2 times out, and once.
+        consumer.tick(&od, Instant::from_millis(200), |e| events.push(e));
+        assert!(events.is_empty());
+        consumer.tick(&od, Instant::from_millis(201), |e| events.push(e));
+        consumer.tick(&od, Instant::from_millis(1000), |e| events.push(e));
+        assert_eq!(events, [HeartbeatEvent::Timeout(node_2)]);
+        assert_eq!(consumer.last_seen(node_2), None);
+    }
+
+    #[test]
+    fn test_consumer_ignores_unmonitored_nodes() {
+        let mut od = consumer_od([0x0002_0064, 0]);
+        let mut consumer = HeartbeatConsumer::<2>::new();
+        let heartbeat = EncodedCANOpenFrame::new(0x704, &[0x05]);
+        assert!(
+            !consumer.process_frame(&od, &heartbeat, Instant::from_millis(0), |_| {
+                panic!("unexpected event")
+            })
+        );
+
+        od.write_raw(
+            EntryId::new(CONSUMER_HEARTBEAT_TIME_INDEX, 2),
+            &0x0004_0064u32.to_le_bytes(),
+        )
+        .unwrap();
+        assert!(consumer.process_frame(&od, &heartbeat, Instant::from_millis(0), |_| {}));
+        assert_eq!(
+            consumer.state(NodeId::new(4).unwrap()),
+            Some(NmtState::Operational)
+        );
+    }
+}
@@ -1,6 +1,22 @@
+// Lets the tests compile generated code, which refers to `::canopen`.
+#[cfg(test)]
+extern crate self as canopen;
+
+pub mod concise_dcf;
+pub mod eds;
+pub mod emcy;
 pub mod frame;
+pub mod guarding;
+pub mod heartbeat;
+pub mod lss;
+pub mod nmt;
 pub mod node;
 pub mod object_dictionary;
 pub mod parameter_coder;
 pub mod pdo;
 pub mod sdo;
+pub mod sync;
+#[cfg(test)]
+mod test_utils;
+pub mod time;
+pub mod time_stamp;
@@ -0,0 +1,184 @@
+pub mod master;
+pub mod slave;
+
+pub use master::{LssError, LssMaster};
+pub use slave::{LssEvent, LssSlave};
+
+use embedded_can::{Id, StandardId};
+use heapless::Vec;
+use num_derive::{FromPrimitive, ToPrimitive};
+use num_traits::FromPrimitive;
+
+use crate::{
+    frame::EncodedCANOpenFrame,
+    node::NodeId,
+    object_dictionary::{EntryId, ObjectDictionary},
+};
+
+/// COB-ID of the frames sent by the LSS master.
+pub const LSS_MASTER_ID: u16 = 0x7E5;
+
+/// COB-ID of the frames sent by the LSS slaves.
+pub const LSS_SLAVE_ID: u16 = 0x7E4;
+
+/// Sub-indices 1 to 4 of the identity object hold the LSS address.
+pub const IDENTITY_OBJECT_INDEX: u16 = 0x1018;
+
+/// Node-ID of an unconfigured node in the LSS services.
+const UNCONFIGURED_NODE_ID: u8 = 0xFF;
+
+/// Bit checked value of the Fastscan request restarting the scan.
+const FASTSCAN_CONFIRM: u8 = 0x80;
+
+const SWITCH_STATE_GLOBAL: u8 = 0x04;
+const CONFIGURE_NODE_ID: u8 = 0x11;
+const CONFIGURE_BIT_TIMING: u8 = 0x13;
+const ACTIVATE_BIT_TIMING: u8 = 0x15;
+const STORE_CONFIGURATION: u8 = 0x17;
+const SWITCH_STATE_SELECTIVE_VENDOR_ID: u8 = 0x40;
+const SWITCH_STATE_SELECTIVE_SERIAL_NUMBER: u8 = 0x43;
+const SWITCH_STATE_SELECTIVE_RESPONSE: u8 = 0x44;
+const IDENTIFY_SLAVE_RESPONSE: u8 = 0x4F;
+const FASTSCAN: u8 = 0x51;
+const INQUIRE_VENDOR_ID: u8 = 0x5A;
+const INQUIRE_SERIAL_NUMBER: u8 = 0x5D;
+const INQUIRE_NODE_ID: u8 = 0x5E;
+
+/// The LSS state of a slave.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LssState {
+    Waiting,
+    Configuration,
+}
+
+/// The identity of a node, as held by 0x1018.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LssAddress {
+    pub vendor_id: u32,
+    pub product_code: u32,
+    pub revision_number: u32,
+    pub serial_number: u32,
+}
+
+impl LssAddress {
+    /// Reads the address from the identity object. Missing entries read as 0.
+    pub fn from_od<const ENTRY_COUNT: usize, const RPDO_COUNT: usize, const TPDO_COUNT: usize>(
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+    ) -> Self {
+        let read = |sub_index| {
+            od.read_unsigned(EntryId::new(IDENTITY_OBJECT_INDEX, sub_index))
+                .unwrap_or(0) as u32
+        };
+        Self {
+            vendor_id: read(1),
+            product_code: read(2),
+            revision_number: read(3),
+            serial_number: read(4),
+        }
+    }
+
+    /// Returns vendor-ID, product code, revision and serial number, in the
+    /// order they are used by the LSS services.
+    pub fn to_array(&self) -> [u32; 4] {
+        [
+            self.vendor_id,
+            self.product_code,
+            self.revision_number,
+            self.serial_number,
+        ]
+    }
+}
+
+/// Bit timings of the CiA 301 bit timing table, selected by their index.
+#[derive(FromPrimitive, ToPrimitive, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum BitTiming {
+    Kbps1000 = 0,
+    Kbps800 = 1,
+    Kbps500 = 2,
+    Kbps250 = 3,
+    Kbps125 = 4,
+    Kbps50 = 6,
+    Kbps20 = 7,
+    Kbps10 = 8,
+    Auto = 9,
+}
+
+impl BitTiming {
+    pub fn from_u8(raw: u8) -> Option<Self> {
+        FromPrimitive::from_u8(raw)
+    }
+
+    /// Returns the bit rate in bit/s, or `None` for automatic detection.
+    pub fn bit_rate(&self) -> Option<u32> {
+        match self {
+            Self::Kbps1000 => Some(1_000_000),
+            Self::Kbps800 => Some(800_000),
+            Self::Kbps500 => Some(500_000),
+            Self::Kbps250 => Some(250_000),
+            Self::Kbps125 => Some(125_000),
+            Self::Kbps50 => Some(50_000),
+            Self::Kbps20 => Some(20_000),
+            Self::Kbps10 => Some(10_000),
+            Self::Auto => None,
+        }
+    }
+}
+
+/// The configuration set through LSS.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct LssConfiguration {
+    /// `None` for an unconfigured node.
+    pub node_id: Option<NodeId>,
+    /// `None` if the bit timing was never configured.
+    pub bit_timing: Option<BitTiming>,
+}
+
+/// Why the configuration could not be stored.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum StoreError {
+    NotSupported = 1,
+    AccessError = 2,
+}
+
+/// Non-volatile storage of the configuration set through LSS, supplied by the
+/// application.
+pub trait LssStorage {
+    /// Returns the stored configuration, or `None` if none was ever stored.
+    fn load(&mut self) -> Option<LssConfiguration>;
+
+    fn store(&mut self, configuration: &LssConfiguration) -> Result<(), StoreError>;
+}
+
+/// Storage for nodes that cannot store their LSS configuration.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NoLssStorage;
+
+impl LssStorage for NoLssStorage {
+    fn load(&mut self) -> Option<LssConfiguration> {
+        None
+    }
+
+    fn store(&mut self, _configuration: &LssConfiguration) -> Result<(), StoreError> {
+        Err(StoreError::NotSupported)
+    }
+}
+
+fn encode_node_id(node_id: Option<NodeId>) -> u8 {
+    node_id.map_or(UNCONFIGURED_NODE_ID, |node_id| node_id.raw())
+}
+
+/// Encodes an LSS frame: the command specifier, followed by up to 7 bytes of
+/// data padded with zeros.
+fn encode(id: u16, command: u8, data: &[u8]) -> EncodedCANOpenFrame {
+    let mut bytes: Vec<u8, 8> = Vec::new();
+    bytes.push(command).unwrap();
+    bytes.extend_from_slice(data).unwrap();
+    bytes.resize_default(8).unwrap();
+    EncodedCANOpenFrame::from_vec_data(StandardId::new(id).unwrap(), bytes)
+}
+
+fn lss_id(id: u16) -> Id {
+    Id::Standard(StandardId::new(id).unwrap())
+}
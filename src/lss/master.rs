@@ -0,0 +1,745 @@
+use core::time::Duration;
+
+use embedded_can::{nb::Can, Error, ErrorKind, Frame};
+use heapless::Deque;
+
+use crate::{
+    frame::EncodedCANOpenFrame,
+    node::NodeId,
+    time::{Clock, Instant},
+};
+
+use super::{
+    encode, encode_node_id, lss_id, BitTiming, LssAddress, LssState, ACTIVATE_BIT_TIMING,
+    CONFIGURE_BIT_TIMING, CONFIGURE_NODE_ID, FASTSCAN, FASTSCAN_CONFIRM, IDENTIFY_SLAVE_RESPONSE,
+    LSS_MASTER_ID, LSS_SLAVE_ID, STORE_CONFIGURATION, SWITCH_STATE_GLOBAL,
+    SWITCH_STATE_SELECTIVE_RESPONSE, SWITCH_STATE_SELECTIVE_VENDOR_ID,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LssError {
+    /// Another service is still in progress on this master.
+    Busy,
+    /// No service has been started.
+    Idle,
+    /// No slave answered in time.
+    Timeout,
+    /// A slave answered with an error code.
+    Rejected { error: u8 },
+    /// The underlying CAN interface reported an error.
+    Bus(ErrorKind),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum FastscanStep {
+    /// Checks that at least one unconfigured slave is present.
+    Confirm,
+    /// Checks whether a slave has bit `bit` of part `part` cleared, the higher
+    /// bits being already known.
+    Scan { part: usize, bit: u8 },
+    /// Checks the whole part, moving the matching slave on to the next one.
+    Verify { part: usize },
+}
+
+impl FastscanStep {
+    fn encode(&self, address: &[u32; 4]) -> EncodedCANOpenFrame {
+        let (id_number, bit_checked, part, next_part) = match *self {
+            Self::Confirm => (0, FASTSCAN_CONFIRM, 0, 0),
+            Self::Scan { part, bit } => (address[part], bit, part, part),
+            Self::Verify { part } => (address[part], 0, part, (part + 1) % 4),
+        };
+        let id_number = id_number.to_le_bytes();
+        encode(
+            LSS_MASTER_ID,
+            FASTSCAN,
+            &[
+                id_number[0],
+                id_number[1],
+                id_number[2],
+                id_number[3],
+                bit_checked,
+                part as u8,
+                next_part as u8,
+            ],
+        )
+    }
+}
+
+enum MasterState {
+    Idle,
+    Confirmed {
+        response: u8,
+        deadline: Instant,
+        answered: bool,
+        error: Option<u8>,
+    },
+    Fastscan {
+        step: FastscanStep,
+        address: [u32; 4],
+        deadline: Instant,
+        acknowledged: bool,
+    },
+    Done(Result<Option<LssAddress>, LssError>),
+}
+
+/// An LSS master, configuring the node-ID and bit timing of LSS slaves.
+///
+/// Services are started with the `start_*` methods, which return the request
+/// to send, followed by those returned by [`LssMaster::poll_frame`]. Received
+/// frames are then fed to [`LssMaster::process_frame`] and
+/// [`LssMaster::tick`] is called with the current time, sending the requests it
+/// returns, until the result is available from [`LssMaster::poll_result`] or
+/// [`LssMaster::poll_fastscan`].
+///
+/// As several slaves may be in the configuration state, each request waits for
+/// the whole timeout and collects every answer.
+///
+/// [`LssMaster::fastscan`], [`LssMaster::assign_node_ids`] and the other
+/// blocking methods drive the same state machine over a non-blocking CAN
+/// interface.
+pub struct LssMaster {
+    state: MasterState,
+    queued: Deque<EncodedCANOpenFrame, 3>,
+    timeout: Duration,
+}
+
+impl Default for LssMaster {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LssMaster {
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(50);
+
+    pub fn new() -> Self {
+        Self {
+            state: MasterState::Idle,
+            queued: Deque::new(),
+            timeout: Self::DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Sets how long to wait for the slaves to answer each request.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Returns `true` while a service is waiting for answers.
+    pub fn is_busy(&self) -> bool {
+        !matches!(self.state, MasterState::Idle | MasterState::Done(_))
+    }
+
+    /// Encodes the request switching all slaves to `state`. It is not answered.
+    pub fn encode_switch_state_global(state: LssState) -> EncodedCANOpenFrame {
+        let mode = (state == LssState::Configuration) as u8;
+        encode(LSS_MASTER_ID, SWITCH_STATE_GLOBAL, &[mode])
+    }
+
+    /// Encodes the request making the slaves in the configuration state switch
+    /// to their configured bit timing. It is not answered.
+    pub fn encode_activate_bit_timing(switch_delay_ms: u16) -> EncodedCANOpenFrame {
+        encode(
+            LSS_MASTER_ID,
+            ACTIVATE_BIT_TIMING,
+            &switch_delay_ms.to_le_bytes(),
+        )
+    }
+
+    /// Starts switching the slave with `address` to the configuration state.
+    pub fn start_switch_state_selective(
+        &mut self,
+        address: &LssAddress,
+        now: Instant,
+    ) -> Result<EncodedCANOpenFrame, LssError> {
+        self.start_confirmed(SWITCH_STATE_SELECTIVE_RESPONSE, now)?;
+        let mut requests = address
+            .to_array()
+            .into_iter()
+            .zip(SWITCH_STATE_SELECTIVE_VENDOR_ID..)
+            .map(|(value, command)| encode(LSS_MASTER_ID, command, &value.to_le_bytes()));
+        let first = requests.next().unwrap();
+        for request in requests {
+            self.queued.push_back(request).ok();
+        }
+        Ok(first)
+    }
+
+    /// Starts configuring the node-ID of the slaves in the configuration
+    /// state, `None` making them unconfigured.
+    pub fn start_configure_node_id(
+        &mut self,
+        node_id: Option<NodeId>,
+        now: Instant,
+    ) -> Result<EncodedCANOpenFrame, LssError> {
+        self.start_confirmed(CONFIGURE_NODE_ID, now)?;
+        Ok(encode(
+            LSS_MASTER_ID,
+            CONFIGURE_NODE_ID,
+            &[encode_node_id(node_id)],
+        ))
+    }
+
+    /// Starts configuring the bit timing of the slaves in the configuration
+    /// state.
+    pub fn start_configure_bit_timing(
+        &mut self,
+        bit_timing: BitTiming,
+        now: Instant,
+    ) -> Result<EncodedCANOpenFrame, LssError> {
+        self.start_confirmed(CONFIGURE_BIT_TIMING, now)?;
+        Ok(encode(
+            LSS_MASTER_ID,
+            CONFIGURE_BIT_TIMING,
+            &[0, bit_timing as u8],
+        ))
+    }
+
+    /// Starts making the slaves in the configuration state store their
+    /// configuration.
+    pub fn start_store_configuration(
+        &mut self,
+        now: Instant,
+    ) -> Result<EncodedCANOpenFrame, LssError> {
+        self.start_confirmed(STORE_CONFIGURATION, now)?;
+        Ok(encode(LSS_MASTER_ID, STORE_CONFIGURATION, &[]))
+    }
+
+    /// Starts a Fastscan, searching for the unconfigured slave with the lowest
+    /// LSS address among those in the waiting state. The slave found is
+    /// switched to the configuration state.
+    pub fn start_fastscan(&mut self, now: Instant) -> Result<EncodedCANOpenFrame, LssError> {
+        self.start()?;
+        let step = FastscanStep::Confirm;
+        let address = [0; 4];
+        self.state = MasterState::Fastscan {
+            step,
+            address,
+            deadline: now + self.timeout,
+            acknowledged: false,
+        };
+        Ok(step.encode(&address))
+    }
+
+    /// Returns the next request of the service in progress that can be sent
+    /// right away, if any.
+    pub fn poll_frame(&mut self) -> Option<EncodedCANOpenFrame> {
+        self.queued.pop_front()
+    }
+
+    /// Feeds a received frame to the service in progress. Frames that are not
+    /// LSS answers are ignored.
+    pub fn process_frame(&mut self, frame: &impl Frame) {
+        if frame.is_remote_frame() || frame.id() != lss_id(LSS_SLAVE_ID) || frame.dlc() != 8 {
+            return;
+        }
+        let data = frame.data();
+        match &mut self.state {
+            MasterState::Confirmed {
+                response,
+                answered,
+                error,
+                ..
+            } if data[0] == *response => {
+                *answered = true;
+                let code = data[1];
+                if *response != SWITCH_STATE_SELECTIVE_RESPONSE && code != 0 {
+                    error.get_or_insert(code);
+                }
+            }
+            MasterState::Fastscan { acknowledged, .. } if data[0] == IDENTIFY_SLAVE_RESPONSE => {
+                *acknowledged = true;
+            }
+            _ => {}
+        }
+    }
+
+    /// Completes the request in progress once its timeout has elapsed at
+    /// `now`, returning the next request to send, if any.
+    pub fn tick(&mut self, now: Instant) -> Option<EncodedCANOpenFrame> {
+        match &mut self.state {
+            MasterState::Confirmed {
+                deadline,
+                answered,
+                error,
+                ..
+            } if now >= *deadline => {
+                let result = match (*answered, *error) {
+                    (_, Some(error)) => Err(LssError::Rejected { error }),
+                    (true, None) => Ok(None),
+                    (false, None) => Err(LssError::Timeout),
+                };
+                self.state = MasterState::Done(result);
+                None
+            }
+            MasterState::Fastscan {
+                step,
+                address,
+                deadline,
+                acknowledged,
+            } if now >= *deadline => {
+                let next = match *step {
+                    FastscanStep::Confirm if *acknowledged => {
+                        Some(FastscanStep::Scan { part: 0, bit: 31 })
+                    }
+                    FastscanStep::Confirm => {
+                        self.state = MasterState::Done(Ok(None));
+                        return None;
+                    }
+                    FastscanStep::Scan { part, bit } => {
+                        // No slave has the bit cleared, so the one being
+                        // searched for has it set.
+                        if !*acknowledged {
+                            address[part] |= 1 << bit;
+                        }
+                        Some(match bit {
+                            0 => FastscanStep::Verify { part },
+                            bit => FastscanStep::Scan { part, bit: bit - 1 },
+                        })
+                    }
+                    FastscanStep::Verify { .. } if !*acknowledged => {
+                        self.state = MasterState::Done(Err(LssError::Timeout));
+                        return None;
+                    }
+                    FastscanStep::Verify { part: 3 } => None,
+                    FastscanStep::Verify { part } => Some(FastscanStep::Scan {
+                        part: part + 1,
+                        bit: 31,
+                    }),
+                };
+                let Some(next) = next else {
+                    let [vendor_id, product_code, revision_number, serial_number] = *address;
+                    self.state = MasterState::Done(Ok(Some(LssAddress {
+                        vendor_id,
+                        product_code,
+                        revision_number,
+                        serial_number,
+                    })));
+                    return None;
+                };
+                *step = next;
+                *deadline = now + self.timeout;
+                *acknowledged = false;
+                Some(next.encode(address))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns once the confirmed service has completed.
+    pub fn poll_result(&mut self) -> nb::Result<(), LssError> {
+        self.take_result().map(|_| ())
+    }
+
+    /// Returns the LSS address found by the Fastscan, or `None` if no
+    /// unconfigured slave answered.
+    pub fn poll_fastscan(&mut self) -> nb::Result<Option<LssAddress>, LssError> {
+        self.take_result()
+    }
+
+    /// Switches all slaves to `state`.
+    pub fn switch_state_global<B: Can>(
+        &mut self,
+        bus: &mut B,
+        state: LssState,
+    ) -> Result<(), LssError> {
+        Self::transmit(bus, &Self::encode_switch_state_global(state))
+    }
+
+    /// Switches the slave with `address` to the configuration state, blocking
+    /// until it has answered.
+    pub fn switch_state_selective<B: Can>(
+        &mut self,
+        bus: &mut B,
+        clock: &impl Clock,
+        address: &LssAddress,
+    ) -> Result<(), LssError> {
+        let request = self.start_switch_state_selective(address, clock.now())?;
+        self.run_blocking(bus, clock, request)?;
+        nb::block!(self.poll_result())
+    }
+
+    /// Configures the node-ID of the slaves in the configuration state,
+    /// blocking until they have answered.
+    pub fn configure_node_id<B: Can>(
+        &mut self,
+        bus: &mut B,
+        clock: &impl Clock,
+        node_id: Option<NodeId>,
+    ) -> Result<(), LssError> {
+        let request = self.start_configure_node_id(node_id, clock.now())?;
+        self.run_blocking(bus, clock, request)?;
+        nb::block!(self.poll_result())
+    }
+
+    /// Configures the bit timing of the slaves in the configuration state,
+    /// blocking until they have answered.
+    pub fn configure_bit_timing<B: Can>(
+        &mut self,
+        bus: &mut B,
+        clock: &impl Clock,
+        bit_timing: BitTiming,
+    ) -> Result<(), LssError> {
+        let request = self.start_configure_bit_timing(bit_timing, clock.now())?;
+        self.run_blocking(bus, clock, request)?;
+        nb::block!(self.poll_result())
+    }
+
+    /// Makes the slaves in the configuration state store their configuration,
+    /// blocking until they have answered.
+    pub fn store_configuration<B: Can>(
+        &mut self,
+        bus: &mut B,
+        clock: &impl Clock,
+    ) -> Result<(), LssError> {
+        let request = self.start_store_configuration(clock.now())?;
+        self.run_blocking(bus, clock, request)?;
+        nb::block!(self.poll_result())
+    }
+
+    /// Runs a Fastscan, blocking until it has completed.
+    pub fn fastscan<B: Can>(
+        &mut self,
+        bus: &mut B,
+        clock: &impl Clock,
+    ) -> Result<Option<LssAddress>, LssError> {
+        let request = self.start_fastscan(clock.now())?;
+        self.run_blocking(bus, clock, request)?;
+        nb::block!(self.poll_fastscan())
+    }
+
+    /// Finds the unconfigured slaves one at a time with Fastscan, giving each
+    /// the next node-ID of `node_ids` until either runs out. When `store` is
+    /// set, each slave is also made to store its new node-ID.
+    ///
+    /// Returns the LSS address of each slave configured, with its node-ID.
+    pub fn assign_node_ids<B: Can>(
+        &mut self,
+        bus: &mut B,
+        clock: &impl Clock,
+        node_ids: impl IntoIterator<Item = NodeId>,
+        store: bool,
+    ) -> Result<std::vec::Vec<(LssAddress, NodeId)>, LssError> {
+        let mut assigned = std::vec::Vec::new();
+        self.switch_state_global(bus, LssState::Waiting)?;
+        for node_id in node_ids {
+            let Some(address) = self.fastscan(bus, clock)? else {
+                break;
+            };
+            self.configure_node_id(bus, clock, Some(node_id))?;
+            if store {
+                self.store_configuration(bus, clock)?;
+            }
+            // Back in the waiting state, the slave starts using its node-ID and
+            // no longer takes part in Fastscan.
+            self.switch_state_global(bus, LssState::Waiting)?;
+            assigned.push((address, node_id));
+        }
+        Ok(assigned)
+    }
+
+    /// Configures the bit timing of all slaves, optionally storing it, then
+    /// activates it.
+    ///
+    /// After `2 * switch_delay_ms` the slaves resume on the new bit timing,
+    /// and the master should do the same before switching them back to the
+    /// waiting state.
+    pub fn change_bit_timing<B: Can>(
+        &mut self,
+        bus: &mut B,
+        clock: &impl Clock,
+        bit_timing: BitTiming,
+        switch_delay_ms: u16,
+        store: bool,
+    ) -> Result<(), LssError> {
+        self.switch_state_global(bus, LssState::Configuration)?;
+        self.configure_bit_timing(bus, clock, bit_timing)?;
+        if store {
+            self.store_configuration(bus, clock)?;
+        }
+        Self::transmit(bus, &Self::encode_activate_bit_timing(switch_delay_ms))
+    }
+
+    fn run_blocking<B: Can>(
+        &mut self,
+        bus: &mut B,
+        clock: &impl Clock,
+        request: EncodedCANOpenFrame,
+    ) -> Result<(), LssError> {
+        let mut request = Some(request);
+        while request.is_some() || self.is_busy() {
+            let result = match request.take() {
+                Some(frame) => Self::transmit(bus, &frame).map(|_| request = self.poll_frame()),
+                None => match bus.receive() {
+                    Ok(frame) => {
+                        self.process_frame(&frame);
+                        Ok(())
+                    }
+                    Err(nb::Error::WouldBlock) => {
+                        request = self.tick(clock.now());
+                        Ok(())
+                    }
+                    Err(nb::Error::Other(err)) => Err(LssError::Bus(err.kind())),
+                },
+            };
+            if result.is_err() {
+                self.state = MasterState::Idle;
+                self.queued.clear();
+                return result;
+            }
+        }
+        Ok(())
+    }
+
+    fn transmit<B: Can>(bus: &mut B, frame: &EncodedCANOpenFrame) -> Result<(), LssError> {
+        let frame = B::Frame::new(frame.id(), frame.data()).unwrap();
+        nb::block!(bus.transmit(&frame))
+            .map(|_| ())
+            .map_err(|err| LssError::Bus(err.kind()))
+    }
+
+    fn start(&mut self) -> Result<(), LssError> {
+        if self.is_busy() {
+            return Err(LssError::Busy);
+        }
+        self.queued.clear();
+        Ok(())
+    }
+
+    fn start_confirmed(&mut self, response: u8, now: Instant) -> Result<(), LssError> {
+        self.start()?;
+        self.state = MasterState::Confirmed {
+            response,
+            deadline: now + self.timeout,
+            answered: false,
+            error: None,
+        };
+        Ok(())
+    }
+
+    fn take_result(&mut self) -> nb::Result<Option<LssAddress>, LssError> {
+        match core::mem::replace(&mut self.state, MasterState::Idle) {
+            MasterState::Done(result) => result.map_err(nb::Error::Other),
+            MasterState::Idle => Err(nb::Error::Other(LssError::Idle)),
+            state => {
+                self.state = state;
+                Err(nb::Error::WouldBlock)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+    use std::collections::VecDeque;
+
+    use embedded_can::nb::Can;
+    use heapless::Vec;
+
+    use crate::frame::EncodedCANOpenFrame;
+    use crate::lss::{
+        BitTiming, LssAddress, LssConfiguration, LssEvent, LssSlave, LssState, LssStorage,
+        NoLssStorage, StoreError,
+    };
+    use crate::nmt::NmtState;
+    use crate::node::{Node, NodeEvent, NodeId};
+    use crate::object_dictionary::{AccessType, EntryId, ObjectDictionary, Variable, VariableType};
+    use crate::parameter_coder::DefaultU32Coder;
+    use crate::test_utils::{BusError, TestClock};
+    use crate::time::Instant;
+
+    use super::{LssError, LssMaster};
+
+    struct UnconfiguredStorage;
+
+    impl LssStorage for UnconfiguredStorage {
+        fn load(&mut self) -> Option<LssConfiguration> {
+            Some(LssConfiguration::default())
+        }
+
+        fn store(&mut self, _configuration: &LssConfiguration) -> Result<(), StoreError> {
+            Ok(())
+        }
+    }
+
+    /// A bus with unconfigured nodes attached to it.
+    struct NodeBus {
+        nodes: std::vec::Vec<Node<4, 0, 0, UnconfiguredStorage>>,
+        rx: VecDeque<EncodedCANOpenFrame>,
+    }
+
+    impl Can for NodeBus {
+        type Frame = EncodedCANOpenFrame;
+        type Error = BusError;
+
+        fn transmit(&mut self, frame: &Self::Frame) -> nb::Result<Option<Self::Frame>, BusError> {
+            for node in &mut self.nodes {
+                node.process_frame(frame);
+                while let Some(response) = node.poll_frame() {
+                    self.rx.push_back(response);
+                }
+            }
+            Ok(None)
+        }
+
+        fn receive(&mut self) -> nb::Result<Self::Frame, BusError> {
+            self.rx.pop_front().ok_or(nb::Error::WouldBlock)
+        }
+    }
+
+    fn test_od(serial_number: u32) -> ObjectDictionary<4, 0, 0> {
+        let identity = |sub_index, name, value| {
+            Variable::new(
+                EntryId::new(0x1018, sub_index),
+                name,
+                VariableType::UInt32(value, &DefaultU32Coder),
+                AccessType::ReadOnly,
+            )
+        };
+        ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [],
+            Vec::from_slice(&[
+                identity(1, "Vendor-ID", 0x0000_0123),
+                identity(2, "Product code", 0x0000_0456),
+                identity(3, "Revision number", 0x0001_0000),
+                identity(4, "Serial number", serial_number),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::new(1).unwrap(),
+        )
+    }
+
+    fn node_bus(serial_numbers: &[u32]) -> NodeBus {
+        NodeBus {
+            nodes: serial_numbers
+                .iter()
+                .map(|serial_number| {
+                    let mut node =
+                        Node::with_lss_storage(test_od(*serial_number), UnconfiguredStorage);
+                    node.boot();
+                    node
+                })
+                .collect(),
+            rx: VecDeque::new(),
+        }
+    }
+
+    fn address(serial_number: u32) -> LssAddress {
+        LssAddress {
+            vendor_id: 0x123,
+            product_code: 0x456,
+            revision_number: 0x1_0000,
+            serial_number,
+        }
+    }
+
+    #[test]
+    fn test_fastscan_assigns_node_ids() {
+        let mut bus = node_bus(&[0xCAFE_0002, 0x8000_0001, 0xCAFE_0001]);
+        let clock = TestClock::default();
+        let mut master = LssMaster::new();
+        master.set_timeout(Duration::from_millis(5));
+
+        let node_ids = (10..20).map(|id| NodeId::new(id).unwrap());
+        let assigned = master
+            .assign_node_ids(&mut bus, &clock, node_ids, true)
+            .unwrap();
+        assert_eq!(
+            assigned,
+            [
+                (address(0x8000_0001), NodeId::new(10).unwrap()),
+                (address(0xCAFE_0001), NodeId::new(11).unwrap()),
+                (address(0xCAFE_0002), NodeId::new(12).unwrap()),
+            ]
+        );
+
+        let node_ids: std::vec::Vec<_> = bus.nodes.iter().map(|node| node.node_id()).collect();
+        assert_eq!(
+            node_ids,
+            [NodeId::new(12), NodeId::new(10), NodeId::new(11)]
+        );
+        assert!(bus
+            .nodes
+            .iter()
+            .all(|node| node.nmt_state() == NmtState::PreOperational));
+        assert_eq!(master.fastscan(&mut bus, &clock), Ok(None));
+    }
+
+    #[test]
+    fn test_change_bit_timing() {
+        let mut bus = node_bus(&[1, 2]);
+        let clock = TestClock::default();
+        let mut master = LssMaster::new();
+        master.set_timeout(Duration::from_millis(5));
+
+        assert_eq!(
+            master.change_bit_timing(&mut bus, &clock, BitTiming::Kbps250, 20, true),
+            Ok(())
+        );
+        for node in &mut bus.nodes {
+            assert_eq!(
+                node.poll_event(),
+                Some(NodeEvent::Lss(LssEvent::ActivateBitTiming {
+                    bit_timing: BitTiming::Kbps250,
+                    switch_delay_ms: 20
+                }))
+            );
+        }
+    }
+
+    #[test]
+    fn test_confirmed_service_steps() {
+        let od = test_od(7);
+        let mut slave = LssSlave::new(LssConfiguration {
+            node_id: NodeId::new(3),
+            bit_timing: None,
+        });
+        let mut master = LssMaster::new();
+        let mut send = |frame: &EncodedCANOpenFrame| {
+            slave
+                .process_frame(&od, &mut NoLssStorage, frame, |_| {})
+                .unwrap()
+        };
+
+        let mut requests = vec![master
+            .start_switch_state_selective(&address(7), Instant::from_millis(0))
+            .unwrap()];
+        requests.extend(core::iter::from_fn(|| master.poll_frame()));
+        assert_eq!(requests.len(), 4);
+        let responses: std::vec::Vec<_> = requests.iter().filter_map(&mut send).collect();
+        assert_eq!(responses.len(), 1);
+
+        master.process_frame(&responses[0]);
+        assert_eq!(master.poll_result(), Err(nb::Error::WouldBlock));
+        master.tick(Instant::from_millis(50));
+        assert_eq!(master.poll_result(), Ok(()));
+
+        let request = master
+            .start_configure_node_id(NodeId::new(0), Instant::from_millis(100))
+            .unwrap();
+        master.process_frame(&send(&request).unwrap());
+        master.tick(Instant::from_millis(150));
+        assert_eq!(
+            master.poll_result(),
+            Err(nb::Error::Other(LssError::Rejected { error: 1 }))
+        );
+
+        send(&LssMaster::encode_switch_state_global(LssState::Waiting));
+        master
+            .start_store_configuration(Instant::from_millis(200))
+            .unwrap();
+        assert!(master.tick(Instant::from_millis(249)).is_none());
+        assert_eq!(master.poll_result(), Err(nb::Error::WouldBlock));
+        master.tick(Instant::from_millis(250));
+        assert_eq!(
+            master.poll_result(),
+            Err(nb::Error::Other(LssError::Timeout))
+        );
+    }
+}
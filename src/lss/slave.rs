@@ -0,0 +1,439 @@
+use embedded_can::Frame;
+
+use crate::{frame::EncodedCANOpenFrame, node::NodeId, object_dictionary::ObjectDictionary};
+
+use super::{
+    encode, encode_node_id, lss_id, BitTiming, LssAddress, LssConfiguration, LssState, LssStorage,
+    ACTIVATE_BIT_TIMING, CONFIGURE_BIT_TIMING, CONFIGURE_NODE_ID, FASTSCAN, FASTSCAN_CONFIRM,
+    IDENTIFY_SLAVE_RESPONSE, INQUIRE_NODE_ID, INQUIRE_SERIAL_NUMBER, INQUIRE_VENDOR_ID,
+    LSS_MASTER_ID, LSS_SLAVE_ID, STORE_CONFIGURATION, SWITCH_STATE_GLOBAL,
+    SWITCH_STATE_SELECTIVE_RESPONSE, SWITCH_STATE_SELECTIVE_SERIAL_NUMBER,
+    SWITCH_STATE_SELECTIVE_VENDOR_ID, UNCONFIGURED_NODE_ID,
+};
+
+/// An event raised by an [`LssSlave`] for the application.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LssEvent {
+    /// The bit timing has to be switched to `bit_timing`: after
+    /// `switch_delay_ms` the node stops transmitting, and after another
+    /// `switch_delay_ms` it resumes on the new bit timing.
+    ActivateBitTiming {
+        bit_timing: BitTiming,
+        switch_delay_ms: u16,
+    },
+    /// An unconfigured node was given a node-ID and has to reset its
+    /// communication to start using it.
+    NodeIdAssigned(NodeId),
+}
+
+/// The LSS slave of a node, configuring its node-ID and bit timing.
+///
+/// A node-ID configured in the configuration state becomes active on the next
+/// reset of communication, or when switching back to the waiting state if the
+/// node was unconfigured. Unconfigured slaves take part in Fastscan.
+pub struct LssSlave {
+    state: LssState,
+    /// Number of consecutive switch state selective frames matched so far.
+    selective_matches: usize,
+    /// Part of the LSS address being scanned by Fastscan.
+    fastscan_part: usize,
+    active_node_id: Option<NodeId>,
+    pending: LssConfiguration,
+}
+
+impl LssSlave {
+    /// Creates a slave in the waiting state, using `configuration` as loaded
+    /// from storage.
+    pub fn new(configuration: LssConfiguration) -> Self {
+        Self {
+            state: LssState::Waiting,
+            selective_matches: 0,
+            fastscan_part: 0,
+            active_node_id: configuration.node_id,
+            pending: configuration,
+        }
+    }
+
+    pub fn state(&self) -> LssState {
+        self.state
+    }
+
+    /// Returns the node-ID in use, or `None` if the node is unconfigured.
+    pub fn active_node_id(&self) -> Option<NodeId> {
+        self.active_node_id
+    }
+
+    /// Returns the configuration set by the LSS master, which is not
+    /// necessarily active yet.
+    pub fn pending_configuration(&self) -> LssConfiguration {
+        self.pending
+    }
+
+    /// Makes the pending node-ID active, as done on a reset of communication,
+    /// and returns it.
+    pub fn activate_pending_node_id(&mut self) -> Option<NodeId> {
+        self.active_node_id = self.pending.node_id;
+        self.active_node_id
+    }
+
+    /// Handles a frame, returning `None` if it is not an LSS request, else the
+    /// response to send, if any.
+    pub fn process_frame<
+        const ENTRY_COUNT: usize,
+        const RPDO_COUNT: usize,
+        const TPDO_COUNT: usize,
+    >(
+        &mut self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        storage: &mut impl LssStorage,
+        frame: &impl Frame,
+        mut on_event: impl FnMut(LssEvent),
+    ) -> Option<Option<EncodedCANOpenFrame>> {
+        if frame.is_remote_frame() || frame.id() != lss_id(LSS_MASTER_ID) || frame.dlc() != 8 {
+            return None;
+        }
+        let data = frame.data();
+        let command = data[0];
+        let value = u32::from_le_bytes(data[1..5].try_into().unwrap());
+
+        if command == SWITCH_STATE_GLOBAL {
+            match data[1] {
+                0 => self.switch_to_waiting(&mut on_event),
+                1 => self.state = LssState::Configuration,
+                _ => {}
+            }
+            return Some(None);
+        }
+        if (SWITCH_STATE_SELECTIVE_VENDOR_ID..=SWITCH_STATE_SELECTIVE_SERIAL_NUMBER)
+            .contains(&command)
+        {
+            return Some(self.switch_state_selective(od, command, value));
+        }
+        if command == FASTSCAN {
+            return Some(self.fastscan(od, value, data[5], data[6], data[7]));
+        }
+        if self.state != LssState::Configuration {
+            return Some(None);
+        }
+
+        let response = match command {
+            CONFIGURE_NODE_ID => {
+                let error = match data[1] {
+                    UNCONFIGURED_NODE_ID => {
+                        self.pending.node_id = None;
+                        0
+                    }
+                    1..=127 => {
+                        self.pending.node_id = NodeId::new(data[1]);
+                        0
+                    }
+                    _ => 1,
+                };
+                encode(LSS_SLAVE_ID, command, &[error])
+            }
+            CONFIGURE_BIT_TIMING => {
+                // Only the standard bit timing table, selector 0, is supported.
+                let bit_timing = (data[1] == 0)
+                    .then(|| BitTiming::from_u8(data[2]))
+                    .flatten();
+                if bit_timing.is_some() {
+                    self.pending.bit_timing = bit_timing;
+                }
+                encode(LSS_SLAVE_ID, command, &[bit_timing.is_none() as u8])
+            }
+            ACTIVATE_BIT_TIMING => {
+                if let Some(bit_timing) = self.pending.bit_timing {
+                    on_event(LssEvent::ActivateBitTiming {
+                        bit_timing,
+                        switch_delay_ms: u16::from_le_bytes([data[1], data[2]]),
+                    });
+                }
+                return Some(None);
+            }
+            STORE_CONFIGURATION => {
+                let error = match storage.store(&self.pending) {
+                    Ok(()) => 0,
+                    Err(error) => error as u8,
+                };
+                encode(LSS_SLAVE_ID, command, &[error])
+            }
+            INQUIRE_VENDOR_ID..=INQUIRE_SERIAL_NUMBER => {
+                let address = LssAddress::from_od(od).to_array();
+                let value = address[(command - INQUIRE_VENDOR_ID) as usize];
+                encode(LSS_SLAVE_ID, command, &value.to_le_bytes())
+            }
+            INQUIRE_NODE_ID => encode(
+                LSS_SLAVE_ID,
+                command,
+                &[encode_node_id(self.active_node_id)],
+            ),
+            _ => return Some(None),
+        };
+        Some(Some(response))
+    }
+
+    fn switch_to_waiting(&mut self, on_event: &mut impl FnMut(LssEvent)) {
+        self.state = LssState::Waiting;
+        self.selective_matches = 0;
+        if self.active_node_id.is_none() {
+            if let Some(node_id) = self.activate_pending_node_id() {
+                on_event(LssEvent::NodeIdAssigned(node_id));
+            }
+        }
+    }
+
+    fn fastscan<const ENTRY_COUNT: usize, const RPDO_COUNT: usize, const TPDO_COUNT: usize>(
+        &mut self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        id_number: u32,
+        bit_checked: u8,
+        part: u8,
+        next_part: u8,
+    ) -> Option<EncodedCANOpenFrame> {
+        if self.state != LssState::Waiting || self.active_node_id.is_some() {
+            return None;
+        }
+        if bit_checked == FASTSCAN_CONFIRM {
+            self.fastscan_part = 0;
+            return Some(encode(LSS_SLAVE_ID, IDENTIFY_SLAVE_RESPONSE, &[]));
+        }
+        let (part, next_part) = (part as usize, next_part as usize);
+        if bit_checked > 31 || part > 3 || next_part > 3 || part != self.fastscan_part {
+            return None;
+        }
+
+        // Only the bits from `bit_checked` upwards are compared.
+        let mask = u32::MAX << bit_checked;
+        if (LssAddress::from_od(od).to_array()[part] ^ id_number) & mask != 0 {
+            return None;
+        }
+        self.fastscan_part = next_part;
+        if bit_checked == 0 && next_part < part {
+            self.state = LssState::Configuration;
+        }
+        Some(encode(LSS_SLAVE_ID, IDENTIFY_SLAVE_RESPONSE, &[]))
+    }
+
+    fn switch_state_selective<
+        const ENTRY_COUNT: usize,
+        const RPDO_COUNT: usize,
+        const TPDO_COUNT: usize,
+    >(
+        &mut self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        command: u8,
+        value: u32,
+    ) -> Option<EncodedCANOpenFrame> {
+        let part = (command - SWITCH_STATE_SELECTIVE_VENDOR_ID) as usize;
+        if part == 0 {
+            self.selective_matches = 0;
+        }
+        if self.state != LssState::Waiting
+            || part != self.selective_matches
+            || LssAddress::from_od(od).to_array()[part] != value
+        {
+            self.selective_matches = 0;
+            return None;
+        }
+
+        self.selective_matches += 1;
+        if self.selective_matches < 4 {
+            return None;
+        }
+        self.selective_matches = 0;
+        self.state = LssState::Configuration;
+        Some(encode(LSS_SLAVE_ID, SWITCH_STATE_SELECTIVE_RESPONSE, &[]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_can::Frame;
+    use heapless::Vec;
+
+    use crate::frame::EncodedCANOpenFrame;
+    use crate::lss::{BitTiming, LssConfiguration, LssState, LssStorage, StoreError};
+    use crate::node::NodeId;
+    use crate::object_dictionary::{AccessType, EntryId, ObjectDictionary, Variable, VariableType};
+    use crate::parameter_coder::DefaultU32Coder;
+
+    use super::{LssEvent, LssSlave};
+
+    #[derive(Default)]
+    struct TestStorage(Option<LssConfiguration>);
+
+    impl LssStorage for TestStorage {
+        fn load(&mut self) -> Option<LssConfiguration> {
+            self.0
+        }
+
+        fn store(&mut self, configuration: &LssConfiguration) -> Result<(), StoreError> {
+            self.0 = Some(*configuration);
+            Ok(())
+        }
+    }
+
+    fn test_od() -> ObjectDictionary<4, 0, 0> {
+        let identity = |sub_index, name, value| {
+            Variable::new(
+                EntryId::new(0x1018, sub_index),
+                name,
+                VariableType::UInt32(value, &DefaultU32Coder),
+                AccessType::ReadOnly,
+            )
+        };
+        ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [],
+            Vec::from_slice(&[
+                identity(1, "Vendor-ID", 0x0000_0123),
+                identity(2, "Product code", 0x0000_0456),
+                identity(3, "Revision number", 0x0001_0000),
+                identity(4, "Serial number", 0xCAFE_0001),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::new(1).unwrap(),
+        )
+    }
+
+    fn request(command: u8, data: &[u8]) -> EncodedCANOpenFrame {
+        let mut bytes = [0; 8];
+        bytes[0] = command;
+        bytes[1..=data.len()].copy_from_slice(data);
+        EncodedCANOpenFrame::new(0x7E5, &bytes)
+    }
+
+    fn send(
+        slave: &mut LssSlave,
+        storage: &mut TestStorage,
+        frame: &EncodedCANOpenFrame,
+    ) -> Option<std::vec::Vec<u8>> {
+        let od = test_od();
+        let response = slave
+            .process_frame(&od, storage, frame, |event| panic!("{event:?}"))
+            .unwrap()?;
+        assert_eq!(response.id(), EncodedCANOpenFrame::new(0x7E4, &[]).id());
+        Some(response.data().to_vec())
+    }
+
+    #[test]
+    fn test_switch_state_selective() {
+        let mut slave = LssSlave::new(LssConfiguration::default());
+        let mut storage = TestStorage::default();
+
+        // A wrong serial number restarts the sequence.
+        for (command, value) in [(0x40, 0x123), (0x41, 0x456), (0x42, 0x1_0000), (0x43, 0)] {
+            let frame = request(command, &u32::to_le_bytes(value));
+            assert_eq!(send(&mut slave, &mut storage, &frame), None);
+        }
+        assert_eq!(slave.state(), LssState::Waiting);
+
+        let sequence = [(0x40, 0x123), (0x41, 0x456), (0x42, 0x1_0000)];
+        for (command, value) in sequence {
+            let frame = request(command, &u32::to_le_bytes(value));
+            assert_eq!(send(&mut slave, &mut storage, &frame), None);
+        }
+        let frame = request(0x43, &0xCAFE_0001u32.to_le_bytes());
+        assert_eq!(
+            send(&mut slave, &mut storage, &frame),
+            Some(vec![0x44, 0, 0, 0, 0, 0, 0, 0])
+        );
+        assert_eq!(slave.state(), LssState::Configuration);
+    }
+
+    #[test]
+    fn test_configure_and_store() {
+        let mut slave = LssSlave::new(LssConfiguration {
+            node_id: NodeId::new(3),
+            bit_timing: None,
+        });
+        let mut storage = TestStorage::default();
+
+        // Ignored in the waiting state.
+        assert_eq!(send(&mut slave, &mut storage, &request(0x11, &[10])), None);
+        send(&mut slave, &mut storage, &request(0x04, &[1]));
+
+        assert_eq!(
+            send(&mut slave, &mut storage, &request(0x11, &[128])),
+            Some(vec![0x11, 1, 0, 0, 0, 0, 0, 0])
+        );
+        assert_eq!(
+            send(&mut slave, &mut storage, &request(0x11, &[10])),
+            Some(vec![0x11, 0, 0, 0, 0, 0, 0, 0])
+        );
+        assert_eq!(
+            send(&mut slave, &mut storage, &request(0x13, &[0, 5])),
+            Some(vec![0x13, 1, 0, 0, 0, 0, 0, 0])
+        );
+        assert_eq!(
+            send(&mut slave, &mut storage, &request(0x13, &[0, 3])),
+            Some(vec![0x13, 0, 0, 0, 0, 0, 0, 0])
+        );
+        assert_eq!(
+            send(&mut slave, &mut storage, &request(0x17, &[])),
+            Some(vec![0x17, 0, 0, 0, 0, 0, 0, 0])
+        );
+        assert_eq!(
+            storage.0,
+            Some(LssConfiguration {
+                node_id: NodeId::new(10),
+                bit_timing: Some(BitTiming::Kbps250),
+            })
+        );
+
+        // The new node-ID is only active after a reset of communication.
+        assert_eq!(
+            send(&mut slave, &mut storage, &request(0x5E, &[])),
+            Some(vec![0x5E, 3, 0, 0, 0, 0, 0, 0])
+        );
+        assert_eq!(slave.activate_pending_node_id(), NodeId::new(10));
+    }
+
+    #[test]
+    fn test_inquire_identity() {
+        let mut slave = LssSlave::new(LssConfiguration::default());
+        let mut storage = TestStorage::default();
+        send(&mut slave, &mut storage, &request(0x04, &[1]));
+
+        assert_eq!(
+            send(&mut slave, &mut storage, &request(0x5D, &[])),
+            Some(vec![0x5D, 0x01, 0x00, 0xFE, 0xCA, 0, 0, 0])
+        );
+        assert_eq!(
+            send(&mut slave, &mut storage, &request(0x5E, &[])),
+            Some(vec![0x5E, 0xFF, 0, 0, 0, 0, 0, 0])
+        );
+    }
+
+    #[test]
+    fn test_events() {
+        let od = test_od();
+        let mut slave = LssSlave::new(LssConfiguration::default());
+        let mut storage = TestStorage::default();
+        let mut events = std::vec::Vec::new();
+        for frame in [
+            request(0x04, &[1]),
+            request(0x11, &[42]),
+            request(0x13, &[0, 0]),
+            request(0x15, &[100, 0]),
+            request(0x04, &[0]),
+        ] {
+            slave.process_frame(&od, &mut storage, &frame, |event| events.push(event));
+        }
+        assert_eq!(
+            events,
+            [
+                LssEvent::ActivateBitTiming {
+                    bit_timing: BitTiming::Kbps1000,
+                    switch_delay_ms: 100,
+                },
+                LssEvent::NodeIdAssigned(NodeId::new(42).unwrap()),
+            ]
+        );
+        assert_eq!(slave.active_node_id(), NodeId::new(42));
+    }
+}
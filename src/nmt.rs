@@ -0,0 +1,238 @@
+use embedded_can::{Frame, Id, StandardId};
+use heapless::Vec;
+use num_derive::{FromPrimitive, ToPrimitive};
+use num_traits::FromPrimitive;
+
+use crate::{frame::EncodedCANOpenFrame, node::NodeId};
+
+/// NMT states, encoded as reported in boot-up, heartbeat and guarding frames.
+#[derive(FromPrimitive, ToPrimitive, Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum NmtState {
+    Initialising = 0x00,
+    Stopped = 0x04,
+    Operational = 0x05,
+    PreOperational = 0x7F,
+}
+
+impl NmtState {
+    pub fn from_u8(raw: u8) -> Option<Self> {
+        FromPrimitive::from_u8(raw)
+    }
+
+    /// SDO is available in Pre-operational and Operational.
+    pub fn allows_sdo(&self) -> bool {
+        matches!(self, Self::PreOperational | Self::Operational)
+    }
+
+    /// PDO is only available in Operational.
+    pub fn allows_pdo(&self) -> bool {
+        matches!(self, Self::Operational)
+    }
+
+    /// SYNC, TIME and EMCY are available in Pre-operational and Operational.
+    pub fn allows_special_function_objects(&self) -> bool {
+        matches!(self, Self::PreOperational | Self::Operational)
+    }
+}
+
+#[derive(FromPrimitive, ToPrimitive, Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum NmtCommand {
+    Start = 0x01,
+    Stop = 0x02,
+    EnterPreOperational = 0x80,
+    ResetNode = 0x81,
+    ResetCommunication = 0x82,
+}
+
+impl NmtCommand {
+    pub const COB_ID: u16 = 0x000;
+
+    /// Decodes an NMT command frame, returning the command and the addressed
+    /// node, or `None` for a broadcast.
+    pub fn try_decode(frame: &impl Frame) -> Option<(Self, Option<NodeId>)> {
+        match frame.id() {
+            Id::Standard(id) if id.as_raw() == Self::COB_ID => {}
+            _ => return None,
+        }
+        if frame.is_remote_frame() || frame.dlc() != 2 {
+            return None;
+        }
+
+        let data = frame.data();
+        let command = FromPrimitive::from_u8(data[0])?;
+        let target = match data[1] {
+            0 => None,
+            id => Some(NodeId::new(id)?),
+        };
+        Some((command, target))
+    }
+
+    /// Encodes the command for `target`, or for all nodes when `None`.
+    pub fn encode(&self, target: Option<NodeId>) -> EncodedCANOpenFrame {
+        let node_id = target.map(|id| id.raw()).unwrap_or(0);
+        EncodedCANOpenFrame::from_vec_data(
+            StandardId::new(Self::COB_ID).unwrap(),
+            Vec::from_slice(&[*self as u8, node_id]).unwrap(),
+        )
+    }
+}
+
+/// The NMT slave state machine of a node.
+pub struct NmtSlave {
+    state: NmtState,
+}
+
+impl Default for NmtSlave {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NmtSlave {
+    pub const BOOT_UP_ID_OFFSET: u16 = 0x700;
+
+    pub fn new() -> Self {
+        Self {
+            state: NmtState::Initialising,
+        }
+    }
+
+    pub fn state(&self) -> NmtState {
+        self.state
+    }
+
+    /// Completes initialisation, entering Pre-operational and returning the
+    /// boot-up frame to send.
+    pub fn boot(&mut self, node_id: NodeId) -> EncodedCANOpenFrame {
+        self.state = NmtState::PreOperational;
+        EncodedCANOpenFrame::from_vec_data(
+            StandardId::new(Self::BOOT_UP_ID_OFFSET + node_id.raw() as u16).unwrap(),
+            Vec::from_slice(&[NmtState::Initialising as u8]).unwrap(),
+        )
+    }
+
+    /// Applies an NMT command frame addressed to `node_id`.
+    ///
+    /// Returns the executed command, or `None` if the command was ignored.
+    /// After a reset command the slave is back in Initialising, and
+    /// [`NmtSlave::boot`] has to be called once the application or
+    /// communication parameters have been reset.
+    pub fn process_frame(&mut self, node_id: NodeId, frame: &impl Frame) -> Option<NmtCommand> {
+        let (command, target) = NmtCommand::try_decode(frame)?;
+        if target.is_some_and(|target| target != node_id) {
+            return None;
+        }
+        self.apply(command).then_some(command)
+    }
+
+    /// Applies `command`, returning `false` if it was ignored as the slave is
+    /// still initialising.
+    pub fn apply(&mut self, command: NmtCommand) -> bool {
+        if self.state == NmtState::Initialising {
+            return false;
+        }
+        self.state = match command {
+            NmtCommand::Start => NmtState::Operational,
+            NmtCommand::Stop => NmtState::Stopped,
+            NmtCommand::EnterPreOperational => NmtState::PreOperational,
+            NmtCommand::ResetNode | NmtCommand::ResetCommunication => NmtState::Initialising,
+        };
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_can::Frame;
+
+    use crate::frame::EncodedCANOpenFrame;
+    use crate::node::NodeId;
+
+    use super::{NmtCommand, NmtSlave, NmtState};
+
+    #[test]
+    fn test_boot_up() {
+        let mut nmt = NmtSlave::new();
+        assert_eq!(nmt.state(), NmtState::Initialising);
+
+        let frame = nmt.boot(NodeId::new(0x12).unwrap());
+        assert_eq!(nmt.state(), NmtState::PreOperational);
+        assert_eq!(frame.id(), EncodedCANOpenFrame::new(0x712, &[]).id());
+        assert_eq!(frame.data(), [0x00]);
+    }
+
+    #[test]
+    fn test_transitions() {
+        let node_id = NodeId::new(5).unwrap();
+        let mut nmt = NmtSlave::new();
+        nmt.boot(node_id);
+
+        let start = EncodedCANOpenFrame::new(0x000, &[0x01, 0x05]);
+        assert_eq!(nmt.process_frame(node_id, &start), Some(NmtCommand::Start));
+        assert_eq!(nmt.state(), NmtState::Operational);
+
+        let stop_all = EncodedCANOpenFrame::new(0x000, &[0x02, 0x00]);
+        assert_eq!(
+            nmt.process_frame(node_id, &stop_all),
+            Some(NmtCommand::Stop)
+        );
+        assert_eq!(nmt.state(), NmtState::Stopped);
+
+        let pre_op = EncodedCANOpenFrame::new(0x000, &[0x80, 0x05]);
+        assert_eq!(
+            nmt.process_frame(node_id, &pre_op),
+            Some(NmtCommand::EnterPreOperational)
+        );
+        assert_eq!(nmt.state(), NmtState::PreOperational);
+
+        let reset = EncodedCANOpenFrame::new(0x000, &[0x82, 0x00]);
+        assert_eq!(
+            nmt.process_frame(node_id, &reset),
+            Some(NmtCommand::ResetCommunication)
+        );
+        assert_eq!(nmt.state(), NmtState::Initialising);
+    }
+
+    #[test]
+    fn test_ignores_other_nodes_and_frames() {
+        let node_id = NodeId::new(5).unwrap();
+        let mut nmt = NmtSlave::new();
+        nmt.boot(node_id);
+
+        let other_node = EncodedCANOpenFrame::new(0x000, &[0x01, 0x06]);
+        assert_eq!(nmt.process_frame(node_id, &other_node), None);
+
+        let unknown_command = EncodedCANOpenFrame::new(0x000, &[0x03, 0x05]);
+        assert_eq!(nmt.process_frame(node_id, &unknown_command), None);
+
+        let wrong_id = EncodedCANOpenFrame::new(0x001, &[0x01, 0x05]);
+        assert_eq!(nmt.process_frame(node_id, &wrong_id), None);
+
+        assert_eq!(nmt.state(), NmtState::PreOperational);
+    }
+
+    #[test]
+    fn test_ignores_commands_while_initialising() {
+        let node_id = NodeId::new(5).unwrap();
+        let mut nmt = NmtSlave::new();
+
+        let start = EncodedCANOpenFrame::new(0x000, &[0x01, 0x05]);
+        assert_eq!(nmt.process_frame(node_id, &start), None);
+        assert_eq!(nmt.state(), NmtState::Initialising);
+        assert!(!nmt.apply(NmtCommand::Start));
+
+        nmt.boot(node_id);
+        assert!(nmt.apply(NmtCommand::Start));
+        assert_eq!(nmt.state(), NmtState::Operational);
+    }
+
+    #[test]
+    fn test_encode_command() {
+        let frame = NmtCommand::ResetNode.encode(Some(NodeId::new(0x20).unwrap()));
+        assert_eq!(frame.id(), EncodedCANOpenFrame::new(0x000, &[]).id());
+        assert_eq!(frame.data(), [0x81, 0x20]);
+        assert_eq!(NmtCommand::Start.encode(None).data(), [0x01, 0x00]);
+    }
+}
@@ -1,3 +1,21 @@
+use embedded_can::Frame;
+use heapless::Deque;
+
+use crate::{
+    emcy::{EmcyConsumer, EmcyProducer, Emergency, ErrorCode},
+    frame::EncodedCANOpenFrame,
+    guarding::{GuardingEvent, LifeGuard},
+    heartbeat::{HeartbeatConsumer, HeartbeatEvent, HeartbeatProducer},
+    lss::{LssConfiguration, LssEvent, LssSlave, LssStorage, NoLssStorage},
+    nmt::{NmtCommand, NmtSlave, NmtState},
+    object_dictionary::ObjectDictionary,
+    pdo::{RpdoConsumer, RpdoError, TpdoError, TpdoProducer},
+    sdo::SdoServer,
+    sync::{SyncConsumer, SyncProducer},
+    time::Instant,
+    time_stamp::{TimeConsumer, TimeOfDay, TimeProducer},
+};
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct NodeId(u8);
 
@@ -10,6 +28,9 @@ impl NodeId {
         }
     }
 
+    /// # Safety
+    ///
+    /// `node_id` must be a valid CANopen node id (0..=127).
     pub unsafe fn new_unchecked(node_id: u8) -> Self {
         Self(node_id)
     }
@@ -28,3 +49,982 @@ impl Default for NodeId {
         Self(126)
     }
 }
+
+/// Number of frames that can be queued for transmission by a [`Node`].
+const TX_QUEUE_SIZE: usize = 16;
+
+/// Number of nodes whose heartbeat can be monitored by a [`Node`].
+const HEARTBEAT_CONSUMER_SIZE: usize = 16;
+
+/// Number of errors that can be active at once on a [`Node`], or tracked for
+/// each node monitored by its EMCY consumer.
+const ACTIVE_ERROR_COUNT: usize = 8;
+
+/// Number of nodes whose active errors can be tracked by a [`Node`].
+const EMCY_CONSUMER_SIZE: usize = 16;
+
+/// Number of events that can be queued by a [`Node`] for the application.
+const EVENT_QUEUE_SIZE: usize = 16;
+
+/// An event raised by a [`Node`], retrieved with [`Node::poll_event`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NodeEvent {
+    Heartbeat(HeartbeatEvent),
+    Guarding(GuardingEvent),
+    /// A SYNC was received or produced, with its counter if it carried one.
+    Sync {
+        counter: Option<u8>,
+    },
+    /// An emergency message was received from a node listed in 0x1028.
+    Emergency(Emergency),
+    /// A TIME frame was received.
+    Time(TimeOfDay),
+    Lss(LssEvent),
+    /// A TPDO was not sent because one of its mapped entries could not be
+    /// read.
+    Tpdo(TpdoError),
+    /// A received RPDO was too short, or one of its mapped entries could not
+    /// be written.
+    Rpdo(RpdoError),
+}
+
+/// A CANopen slave node serving an [`ObjectDictionary`].
+///
+/// Received frames are fed to [`Node::process_frame`], frames to transmit are
+/// retrieved with [`Node::poll_frame`] until it returns `None`, and
+/// [`Node::tick`] drives the time-based services. Services are
+/// enabled according to the NMT state: SDO in Pre-operational and
+/// Operational, PDO in Operational only.
+///
+/// Synchronous TPDOs that are still queued when the synchronous window (0x1007)
+/// closes are discarded on the next tick.
+///
+/// The NMT command Reset Communication restores the default values of the
+/// communication parameters (0x1000 to 0x1FFF), including the PDO
+/// configurations, and clears the pre-defined error field. Reset Node restores
+/// those of the manufacturer and profile parameters (up to 0x9FFF) as well.
+///
+/// The node-ID and bit timing can be configured through LSS, and are persisted
+/// in the [`LssStorage`] given to [`Node::with_lss_storage`]. An unconfigured
+/// node only takes part in LSS until it is assigned a node-ID.
+pub struct Node<
+    const ENTRY_COUNT: usize,
+    const RPDO_COUNT: usize,
+    const TPDO_COUNT: usize,
+    S: LssStorage = NoLssStorage,
+> {
+    od: ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+    nmt: NmtSlave,
+    lss: LssSlave,
+    lss_storage: S,
+    sdo_server: SdoServer,
+    heartbeat: HeartbeatProducer,
+    heartbeat_consumer: HeartbeatConsumer<HEARTBEAT_CONSUMER_SIZE>,
+    life_guard: LifeGuard,
+    tpdo_producer: TpdoProducer<TPDO_COUNT>,
+    rpdo_consumer: RpdoConsumer<RPDO_COUNT>,
+    sync_consumer: SyncConsumer,
+    sync_producer: SyncProducer,
+    emcy: EmcyProducer<ACTIVE_ERROR_COUNT>,
+    emcy_consumer: EmcyConsumer<EMCY_CONSUMER_SIZE, ACTIVE_ERROR_COUNT>,
+    time_consumer: TimeConsumer,
+    tx_queue: Deque<EncodedCANOpenFrame, TX_QUEUE_SIZE>,
+    sync_tx_queue: Deque<EncodedCANOpenFrame, TX_QUEUE_SIZE>,
+    events: Deque<NodeEvent, EVENT_QUEUE_SIZE>,
+    now: Instant,
+}
+
+impl<const ENTRY_COUNT: usize, const RPDO_COUNT: usize, const TPDO_COUNT: usize>
+    Node<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>
+{
+    /// Creates a node using the node-ID of `od`, which cannot store the
+    /// configuration set through LSS.
+    pub fn new(od: ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>) -> Self {
+        Self::with_lss_storage(od, NoLssStorage)
+    }
+}
+
+impl<const ENTRY_COUNT: usize, const RPDO_COUNT: usize, const TPDO_COUNT: usize, S: LssStorage>
+    Node<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT, S>
+{
+    /// Creates a node using the configuration loaded from `lss_storage`, or
+    /// the node-ID of `od` if none was stored.
+    pub fn with_lss_storage(
+        mut od: ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        mut lss_storage: S,
+    ) -> Self {
+        let configuration = lss_storage.load().unwrap_or(LssConfiguration {
+            node_id: Some(od.node_id()),
+            bit_timing: None,
+        });
+        if let Some(node_id) = configuration.node_id.filter(|id| *id != od.node_id()) {
+            od.set_node_id(node_id);
+            // As done by Reset Communication when powered on.
+            od.restore_defaults(0x1000..=0x1FFF);
+        }
+        Self {
+            od,
+            nmt: NmtSlave::new(),
+            lss: LssSlave::new(configuration),
+            lss_storage,
+            sdo_server: SdoServer::new(),
+            heartbeat: HeartbeatProducer::new(),
+            heartbeat_consumer: HeartbeatConsumer::new(),
+            life_guard: LifeGuard::new(),
+            tpdo_producer: TpdoProducer::new(),
+            rpdo_consumer: RpdoConsumer::new(),
+            sync_consumer: SyncConsumer::new(),
+            sync_producer: SyncProducer::new(),
+            emcy: EmcyProducer::new(),
+            emcy_consumer: EmcyConsumer::new(),
+            time_consumer: TimeConsumer::new(),
+            tx_queue: Deque::new(),
+            sync_tx_queue: Deque::new(),
+            events: Deque::new(),
+            now: Instant::default(),
+        }
+    }
+
+    pub fn od(&self) -> &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT> {
+        &self.od
+    }
+
+    pub fn od_mut(&mut self) -> &mut ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT> {
+        &mut self.od
+    }
+
+    pub fn nmt_state(&self) -> NmtState {
+        self.nmt.state()
+    }
+
+    /// Returns the node-ID in use, or `None` if the node is unconfigured.
+    pub fn node_id(&self) -> Option<NodeId> {
+        self.lss.active_node_id().map(|_| self.od.node_id())
+    }
+
+    /// Finishes initialisation and queues the boot-up frame. An unconfigured
+    /// node boots once assigned a node-ID through LSS.
+    pub fn boot(&mut self) {
+        if self.lss.active_node_id().is_none() {
+            return;
+        }
+        let frame = self.nmt.boot(self.od.node_id());
+        self.queue(frame);
+    }
+
+    pub fn process_frame(&mut self, frame: &impl Frame) {
+        let mut assigned = None;
+        let events = &mut self.events;
+        if let Some(response) =
+            self.lss
+                .process_frame(&self.od, &mut self.lss_storage, frame, |event| {
+                    if let LssEvent::NodeIdAssigned(node_id) = event {
+                        assigned = Some(node_id);
+                    }
+                    Self::push_event(events, NodeEvent::Lss(event))
+                })
+        {
+            if let Some(response) = response {
+                self.queue(response);
+            }
+            if let Some(node_id) = assigned {
+                self.od.set_node_id(node_id);
+                self.handle_nmt_command(NmtCommand::ResetCommunication);
+            }
+            return;
+        }
+        if self.lss.active_node_id().is_none() {
+            return;
+        }
+
+        if let Some(command) = self.nmt.process_frame(self.od.node_id(), frame) {
+            self.handle_nmt_command(command);
+            return;
+        }
+        if self.nmt.state() == NmtState::Initialising {
+            return;
+        }
+
+        if self.nmt.state().allows_special_function_objects() {
+            if let Some(counter) = self.sync_consumer.process_frame(&self.od, frame, self.now) {
+                self.handle_sync(counter);
+                return;
+            }
+            let events = &mut self.events;
+            if self
+                .emcy_consumer
+                .process_frame(&self.od, frame, |emergency, _| {
+                    Self::push_event(events, NodeEvent::Emergency(*emergency))
+                })
+                .is_some()
+            {
+                return;
+            }
+            if let Some(time) = self.time_consumer.process_frame(&self.od, frame, self.now) {
+                Self::push_event(&mut self.events, NodeEvent::Time(time));
+                return;
+            }
+        }
+        if self.nmt.state().allows_pdo() {
+            if let Some(result) = self.rpdo_consumer.process_frame(&mut self.od, frame) {
+                self.handle_rpdo_result(result);
+                return;
+            }
+        }
+
+        let events = &mut self.events;
+        let mut resumed = false;
+        if let Some(answer) = self.life_guard.process_frame(
+            self.od.node_id(),
+            self.nmt.state(),
+            frame,
+            self.now,
+            |event| {
+                resumed = event == GuardingEvent::LifeGuardingResumed;
+                Self::push_event(events, NodeEvent::Guarding(event))
+            },
+        ) {
+            self.queue(answer);
+            if resumed {
+                self.clear_error(ErrorCode::LifeGuardOrHeartbeat);
+            }
+            return;
+        }
+
+        let events = &mut self.events;
+        if self
+            .heartbeat_consumer
+            .process_frame(&self.od, frame, self.now, |event| {
+                Self::push_event(events, NodeEvent::Heartbeat(event))
+            })
+        {
+            return;
+        }
+
+        if self.nmt.state().allows_sdo() {
+            if let Some(response) = self.sdo_server.process_frame(&mut self.od, frame) {
+                self.queue(response);
+            }
+        }
+    }
+
+    /// Runs the time-based services, `now` being the current time of a
+    /// monotonic clock. Received frames are timestamped with the time of the
+    /// last tick.
+    pub fn tick(&mut self, now: Instant) {
+        self.now = now;
+        if self.nmt.state() == NmtState::Initialising {
+            return;
+        }
+
+        if !self.sync_consumer.in_window(&self.od, now) {
+            self.sync_tx_queue.clear();
+        }
+        if self.nmt.state().allows_special_function_objects() {
+            if let Some(sync) = self.sync_producer.tick(&self.od, now) {
+                let counter = sync.data().first().copied();
+                self.queue(sync);
+                self.sync_consumer.produced(now);
+                self.handle_sync(counter);
+            }
+        }
+
+        if let Some(frame) = self.heartbeat.tick(&self.od, self.nmt.state(), now) {
+            self.queue(frame);
+        }
+        if self.nmt.state().allows_pdo() {
+            let tx_queue = &mut self.tx_queue;
+            let result = self
+                .tpdo_producer
+                .tick(&self.od, now, |frame| push_dropping_oldest(tx_queue, frame));
+            self.handle_tpdo_result(result);
+        }
+
+        let events = &mut self.events;
+        let mut timeouts = 0;
+        self.heartbeat_consumer.tick(&self.od, now, |event| {
+            if matches!(event, HeartbeatEvent::Timeout(_)) {
+                timeouts += 1;
+            }
+            Self::push_event(events, NodeEvent::Heartbeat(event))
+        });
+        self.life_guard.tick(&self.od, now, |event| {
+            if event == GuardingEvent::LifeGuardingLost {
+                timeouts += 1;
+            }
+            Self::push_event(events, NodeEvent::Guarding(event))
+        });
+        if timeouts > 0 {
+            self.raise_error(ErrorCode::LifeGuardOrHeartbeat, [0; 5]);
+        }
+    }
+
+    /// Requests the transmission of an event-driven or acyclic synchronous
+    /// TPDO, e.g. because one of its mapped values changed. It is sent on the
+    /// next tick or SYNC.
+    pub fn trigger_tpdo(&mut self, pdo: usize) {
+        self.tpdo_producer.trigger(pdo);
+    }
+
+    /// Raises an error, sending its emergency message. Errors detected by the
+    /// communication services, such as heartbeat timeouts, are raised by the
+    /// node itself and have to be cleared by the application.
+    pub fn raise_error(&mut self, code: impl Into<u16>, manufacturer_data: [u8; 5]) {
+        let code = code.into();
+        if self.emcy.raise(code, manufacturer_data) {
+            self.od.push_error_history(code as u32);
+        }
+        self.od.set_error_register(self.emcy.error_register());
+    }
+
+    /// Clears an active error. Once none is left, an error reset emergency
+    /// message is sent.
+    pub fn clear_error(&mut self, code: impl Into<u16>) {
+        self.emcy.clear(code);
+        self.od.set_error_register(self.emcy.error_register());
+    }
+
+    /// Returns the codes of the active errors, oldest first.
+    pub fn active_errors(&self) -> &[u16] {
+        self.emcy.active_errors()
+    }
+
+    /// Returns the errors reported active by a node listed in the emergency
+    /// consumer object.
+    pub fn remote_errors(&self, node: NodeId) -> &[u16] {
+        self.emcy_consumer.active_errors(node)
+    }
+
+    /// Sends a TIME frame if this node is the time producer, as configured in
+    /// 0x1012.
+    pub fn send_time(&mut self, time: TimeOfDay) {
+        if !self.nmt.state().allows_special_function_objects() {
+            return;
+        }
+        if let Some(frame) = TimeProducer::encode(&self.od, time) {
+            self.queue(frame);
+        }
+    }
+
+    /// Returns the time of day received from the time producer, advanced by
+    /// the time elapsed up to the last tick.
+    pub fn time_of_day(&self) -> Option<TimeOfDay> {
+        self.time_consumer.time_of_day(self.now)
+    }
+
+    /// Returns the next event raised for the application, if any.
+    pub fn poll_event(&mut self) -> Option<NodeEvent> {
+        self.events.pop_front()
+    }
+
+    /// Returns the last NMT state reported by a node monitored by the
+    /// heartbeat consumer.
+    pub fn remote_state(&self, node: NodeId) -> Option<NmtState> {
+        self.heartbeat_consumer.state(node)
+    }
+
+    /// Returns the next frame to transmit, if any.
+    pub fn poll_frame(&mut self) -> Option<EncodedCANOpenFrame> {
+        let emcy_allowed = self.nmt.state().allows_special_function_objects();
+        self.tx_queue
+            .pop_front()
+            .or_else(|| {
+                emcy_allowed
+                    .then(|| self.emcy.poll_frame(&self.od, self.now))
+                    .flatten()
+            })
+            .or_else(|| self.sync_tx_queue.pop_front())
+            .or_else(|| self.sdo_server.poll_frame(self.od.node_id()))
+    }
+
+    fn handle_nmt_command(&mut self, command: NmtCommand) {
+        if !self.nmt.state().allows_sdo() {
+            self.sdo_server.reset();
+        }
+        let parameters = match command {
+            // The manufacturer and profile parameters, then the communication
+            // ones.
+            NmtCommand::ResetNode => 0x1000..=0x9FFF,
+            NmtCommand::ResetCommunication => 0x1000..=0x1FFF,
+            _ => return,
+        };
+        if let Some(node_id) = self.lss.activate_pending_node_id() {
+            self.od.set_node_id(node_id);
+        }
+        self.od.restore_defaults(parameters);
+        self.heartbeat.reset();
+        self.heartbeat_consumer.reset();
+        self.life_guard.reset();
+        self.tpdo_producer.reset();
+        self.rpdo_consumer.reset();
+        self.sync_consumer.reset();
+        self.sync_producer.reset();
+        self.sync_tx_queue.clear();
+        self.emcy.reset();
+        self.emcy_consumer.reset();
+        self.boot();
+    }
+
+    fn handle_rpdo_result(&mut self, result: Result<(), RpdoError>) {
+        if let Err(error) = result {
+            if let RpdoError::TooShort { .. } = error {
+                self.raise_error(ErrorCode::PdoLengthError, [0; 5]);
+            }
+            Self::push_event(&mut self.events, NodeEvent::Rpdo(error));
+        }
+    }
+
+    fn handle_tpdo_result(&mut self, result: Result<(), TpdoError>) {
+        if let Err(error) = result {
+            Self::push_event(&mut self.events, NodeEvent::Tpdo(error));
+        }
+    }
+
+    fn handle_sync(&mut self, counter: Option<u8>) {
+        Self::push_event(&mut self.events, NodeEvent::Sync { counter });
+        if !self.nmt.state().allows_pdo() {
+            return;
+        }
+
+        let result = self.rpdo_consumer.process_sync(&mut self.od);
+        self.handle_rpdo_result(result);
+        // TPDOs left over from the previous cycle are stale.
+        self.sync_tx_queue.clear();
+        let sync_tx_queue = &mut self.sync_tx_queue;
+        let result = self
+            .tpdo_producer
+            .process_sync(&self.od, |frame| push_dropping_oldest(sync_tx_queue, frame));
+        self.handle_tpdo_result(result);
+    }
+
+    fn push_event(events: &mut Deque<NodeEvent, EVENT_QUEUE_SIZE>, event: NodeEvent) {
+        push_dropping_oldest(events, event);
+    }
+
+    fn queue(&mut self, frame: EncodedCANOpenFrame) {
+        push_dropping_oldest(&mut self.tx_queue, frame);
+    }
+}
+
+fn push_dropping_oldest<T, const N: usize>(queue: &mut Deque<T, N>, item: T) {
+    // When the queue is full the oldest item is dropped, as a stale frame or
+    // event is of less use than the latest one.
+    if queue.is_full() {
+        queue.pop_front();
+    }
+    queue.push_back(item).ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_can::Frame;
+    use heapless::Vec;
+
+    use crate::emcy::{ErrorCode, COB_ID_EMCY};
+    use crate::frame::EncodedCANOpenFrame;
+    use crate::heartbeat::HeartbeatEvent;
+    use crate::lss::{LssConfiguration, LssEvent, LssStorage, StoreError};
+    use crate::nmt::NmtState;
+    use crate::object_dictionary::{
+        AccessType, CobId, EntryId, FrameId, NodeIdRelative, ObjectDictionary, PdoMapability,
+        Variable, VariableType,
+    };
+    use crate::parameter_coder::{DefaultU16Coder, DefaultU32Coder, DefaultU8Coder};
+    use crate::pdo::{PdoConfiguration, PdoEntryMapping, PdoTransmissionType, RpdoError};
+    use crate::sdo::SdoAbortCode;
+    use crate::time::Instant;
+    use crate::time_stamp::TimeOfDay;
+
+    use super::{Node, NodeEvent, NodeId};
+
+    fn test_node() -> Node<4, 0, 1> {
+        let mut mappings = [PdoEntryMapping::default(); 8];
+        mappings[0] = PdoEntryMapping::new(0x2000, 0, 8);
+        Node::new(ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [PdoConfiguration::new(
+                CobId::new(false, false, FrameId::Standard(0x185)),
+                PdoTransmissionType::Synchronous(1),
+                1,
+                mappings,
+                0,
+            )],
+            [],
+            Vec::from_slice(&[
+                Variable::new(
+                    EntryId::new(0x1016, 0x0),
+                    "Consumer heartbeat time",
+                    VariableType::Array(1),
+                    AccessType::ReadOnly,
+                ),
+                Variable::new(
+                    EntryId::new(0x1016, 0x1),
+                    "Consumer heartbeat time 1",
+                    VariableType::UInt32(0x0009_0064, &DefaultU32Coder),
+                    AccessType::ReadWrite,
+                ),
+                Variable::new(
+                    EntryId::new(0x1017, 0x0),
+                    "Producer heartbeat time",
+                    VariableType::UInt16(0, &DefaultU16Coder),
+                    AccessType::ReadWrite,
+                ),
+                Variable::builder(EntryId::new(0x2000, 0x0))
+                    .name("value")
+                    .pdo(PdoMapability::All)
+                    .value(VariableType::UInt8(0x42, &DefaultU8Coder))
+                    .unwrap(),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::new(5).unwrap(),
+        ))
+    }
+
+    fn upload_request() -> EncodedCANOpenFrame {
+        EncodedCANOpenFrame::new(0x605, &[2 << 5, 0x00, 0x20, 0x00, 0, 0, 0, 0])
+    }
+
+    #[test]
+    fn test_boot_up() {
+        let mut node = test_node();
+        assert_eq!(node.nmt_state(), NmtState::Initialising);
+
+        node.process_frame(&upload_request());
+        assert!(node.poll_frame().is_none());
+
+        node.boot();
+        assert_eq!(node.nmt_state(), NmtState::PreOperational);
+        let boot_up = node.poll_frame().unwrap();
+        assert_eq!(boot_up.id(), EncodedCANOpenFrame::new(0x705, &[]).id());
+        assert_eq!(boot_up.data(), [0x00]);
+        assert!(node.poll_frame().is_none());
+    }
+
+    #[test]
+    fn test_sdo_gated_by_state() {
+        let mut node = test_node();
+        node.boot();
+        node.poll_frame();
+
+        node.process_frame(&upload_request());
+        let response = node.poll_frame().unwrap();
+        assert_eq!(response.data()[4], 0x42);
+
+        node.process_frame(&EncodedCANOpenFrame::new(0x000, &[0x02, 0x05]));
+        assert_eq!(node.nmt_state(), NmtState::Stopped);
+        node.process_frame(&upload_request());
+        assert!(node.poll_frame().is_none());
+
+        node.process_frame(&EncodedCANOpenFrame::new(0x000, &[0x01, 0x00]));
+        assert_eq!(node.nmt_state(), NmtState::Operational);
+        node.process_frame(&upload_request());
+        assert!(node.poll_frame().is_some());
+    }
+
+    #[test]
+    fn test_reset_reboots() {
+        let mut node = test_node();
+        node.boot();
+        node.poll_frame();
+
+        node.process_frame(&EncodedCANOpenFrame::new(0x000, &[0x01, 0x05]));
+        node.process_frame(&EncodedCANOpenFrame::new(0x000, &[0x81, 0x05]));
+        assert_eq!(node.nmt_state(), NmtState::PreOperational);
+        let boot_up = node.poll_frame().unwrap();
+        assert_eq!(boot_up.id(), EncodedCANOpenFrame::new(0x705, &[]).id());
+    }
+
+    #[test]
+    fn test_reset_restores_defaults() {
+        let mut node = test_node();
+        node.boot();
+        let heartbeat_time = EntryId::new(0x1017, 0);
+        let value = EntryId::new(0x2000, 0);
+        let set = |node: &mut Node<4, 0, 1>, id, raw: u64| {
+            node.od_mut().write_unsigned(id, raw).unwrap();
+        };
+
+        set(&mut node, heartbeat_time, 1000);
+        set(&mut node, value, 0x10);
+        node.process_frame(&EncodedCANOpenFrame::new(0x000, &[0x82, 0x05]));
+        assert_eq!(node.od().read_unsigned(heartbeat_time), Some(0));
+        assert_eq!(node.od().read_unsigned(value), Some(0x10));
+
+        set(&mut node, heartbeat_time, 1000);
+        node.process_frame(&EncodedCANOpenFrame::new(0x000, &[0x81, 0x05]));
+        assert_eq!(node.od().read_unsigned(heartbeat_time), Some(0));
+        assert_eq!(node.od().read_unsigned(value), Some(0x42));
+
+        // The PDO parameters and error history are communication parameters
+        // as well.
+        let od = node.od_mut();
+        od.write_raw(EntryId::new(0x1A00, 0), &[0]).unwrap();
+        od.write_raw(EntryId::new(0x1A00, 1), &0x2000_0004u32.to_le_bytes())
+            .unwrap();
+        od.write_raw(EntryId::new(0x1A00, 0), &[1]).unwrap();
+        od.push_error_history(0x8130);
+        assert_eq!(od.tpdo_configuration(0).unwrap().mappings()[0].length(), 4);
+        node.process_frame(&EncodedCANOpenFrame::new(0x000, &[0x82, 0x05]));
+        assert_eq!(
+            node.od().tpdo_configuration(0).unwrap().mappings()[0].length(),
+            8
+        );
+        assert!(node.od().error_history().is_empty());
+    }
+
+    #[test]
+    fn test_heartbeat_configured_over_sdo() {
+        let mut node = test_node();
+        node.boot();
+        node.poll_frame();
+
+        node.tick(Instant::from_millis(0));
+        node.tick(Instant::from_millis(1000));
+        assert!(node.poll_frame().is_none());
+
+        // Expedited download of 200ms to 0x1017.
+        node.process_frame(&EncodedCANOpenFrame::new(
+            0x605,
+            &[0x2B, 0x17, 0x10, 0x00, 200, 0, 0, 0],
+        ));
+        assert_eq!(node.poll_frame().unwrap().data()[0], 0x60);
+
+        node.tick(Instant::from_millis(1100));
+        assert!(node.poll_frame().is_none());
+        node.tick(Instant::from_millis(1300));
+        let heartbeat = node.poll_frame().unwrap();
+        assert_eq!(heartbeat.id(), EncodedCANOpenFrame::new(0x705, &[]).id());
+        assert_eq!(heartbeat.data(), [0x7F]);
+    }
+
+    #[test]
+    fn test_heartbeat_timeout_raises_emcy() {
+        let mut node = test_node();
+        node.boot();
+        node.poll_frame();
+        let node_9 = NodeId::new(9).unwrap();
+
+        node.tick(Instant::from_millis(0));
+        node.process_frame(&EncodedCANOpenFrame::new(0x709, &[0x05]));
+        assert_eq!(node.remote_state(node_9), Some(NmtState::Operational));
+        assert_eq!(
+            node.poll_event(),
+            Some(NodeEvent::Heartbeat(HeartbeatEvent::StateChanged {
+                node: node_9,
+                state: NmtState::Operational
+            }))
+        );
+
+        node.tick(Instant::from_millis(150));
+        assert_eq!(
+            node.poll_event(),
+            Some(NodeEvent::Heartbeat(HeartbeatEvent::Timeout(node_9)))
+        );
+        let emcy = node.poll_frame().unwrap();
+        assert_eq!(emcy.id(), EncodedCANOpenFrame::new(0x085, &[]).id());
+        assert_eq!(emcy.data(), [0x30, 0x81, 0x11, 0, 0, 0, 0, 0]);
+        assert_eq!(node.od().error_register(), 0x11);
+        assert_eq!(node.od().error_history(), [0x8130]);
+        assert!(node.poll_frame().is_none());
+    }
+
+    #[test]
+    fn test_answers_node_guarding() {
+        let mut node = test_node();
+        node.boot();
+        node.poll_frame();
+
+        let request =
+            EncodedCANOpenFrame::new_remote(embedded_can::StandardId::new(0x705).unwrap(), 1)
+                .unwrap();
+        node.process_frame(&request);
+        assert_eq!(node.poll_frame().unwrap().data(), [0x7F]);
+        node.process_frame(&request);
+        assert_eq!(node.poll_frame().unwrap().data(), [0xFF]);
+    }
+
+    #[test]
+    fn test_rpdo_in_operational() {
+        let mut mappings = [PdoEntryMapping::default(); 8];
+        mappings[0] = PdoEntryMapping::new(0x2000, 0, 8);
+        let mut node: Node<1, 1, 0> = Node::new(ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [PdoConfiguration::new(
+                CobId::new(true, false, FrameId::Standard(0x205)),
+                PdoTransmissionType::EventDriven,
+                1,
+                mappings,
+                0,
+            )],
+            Vec::from_slice(&[Variable::builder(EntryId::new(0x2000, 0x0))
+                .name("value")
+                .pdo(PdoMapability::All)
+                .value(VariableType::UInt8(0, &DefaultU8Coder))
+                .unwrap()])
+            .ok()
+            .unwrap(),
+            NodeId::new(5).unwrap(),
+        ));
+        node.boot();
+        node.poll_frame();
+
+        let rpdo = EncodedCANOpenFrame::new(0x205, &[0x42]);
+        node.process_frame(&rpdo);
+        assert_eq!(node.od().read_unsigned(EntryId::new(0x2000, 0)), Some(0));
+
+        node.process_frame(&EncodedCANOpenFrame::new(0x000, &[0x01, 0x05]));
+        node.process_frame(&rpdo);
+        assert_eq!(node.od().read_unsigned(EntryId::new(0x2000, 0)), Some(0x42));
+
+        node.process_frame(&EncodedCANOpenFrame::new(0x205, &[]));
+        let emcy = node.poll_frame().unwrap();
+        assert_eq!(emcy.id(), EncodedCANOpenFrame::new(0x085, &[]).id());
+        assert_eq!(&emcy.data()[..2], [0x10, 0x82]);
+    }
+
+    #[test]
+    fn test_rpdo_write_error() {
+        let mut mappings = [PdoEntryMapping::default(); 8];
+        mappings[0] = PdoEntryMapping::new(0x2000, 0, 8);
+        let mut node: Node<1, 1, 0> = Node::new(ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [PdoConfiguration::new(
+                CobId::new(true, false, FrameId::Standard(0x205)),
+                PdoTransmissionType::EventDriven,
+                1,
+                mappings,
+                0,
+            )],
+            Vec::from_slice(&[Variable::builder(EntryId::new(0x2000, 0x0))
+                .name("value")
+                .pdo(PdoMapability::All)
+                .high_limit(VariableType::UInt8(0x10, &DefaultU8Coder))
+                .value(VariableType::UInt8(0, &DefaultU8Coder))
+                .unwrap()])
+            .ok()
+            .unwrap(),
+            NodeId::new(5).unwrap(),
+        ));
+        node.boot();
+        node.poll_frame();
+        node.process_frame(&EncodedCANOpenFrame::new(0x000, &[0x01, 0x05]));
+
+        node.process_frame(&EncodedCANOpenFrame::new(0x205, &[0x42]));
+        assert_eq!(node.od().read_unsigned(EntryId::new(0x2000, 0)), Some(0));
+        assert_eq!(
+            node.poll_event(),
+            Some(NodeEvent::Rpdo(RpdoError::Write {
+                pdo: 0,
+                entry: EntryId::new(0x2000, 0),
+                code: SdoAbortCode::ValueTooHigh,
+            }))
+        );
+
+        node.process_frame(&EncodedCANOpenFrame::new(0x205, &[]));
+        assert_eq!(
+            node.poll_event(),
+            Some(NodeEvent::Rpdo(RpdoError::TooShort { pdo: 0 }))
+        );
+    }
+
+    #[test]
+    fn test_sync_tpdo_window() {
+        let mut mappings = [PdoEntryMapping::default(); 8];
+        mappings[0] = PdoEntryMapping::new(0x2000, 0, 8);
+        let mut node: Node<2, 0, 1> = Node::new(ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [PdoConfiguration::new(
+                CobId::new(true, false, FrameId::Standard(0x185)),
+                PdoTransmissionType::Synchronous(1),
+                1,
+                mappings,
+                0,
+            )],
+            [],
+            Vec::from_slice(&[
+                Variable::new(
+                    EntryId::new(0x1007, 0x0),
+                    "Synchronous window length",
+                    VariableType::UInt32(1_000, &DefaultU32Coder),
+                    AccessType::ReadWrite,
+                ),
+                Variable::builder(EntryId::new(0x2000, 0x0))
+                    .name("value")
+                    .pdo(PdoMapability::All)
+                    .value(VariableType::UInt8(0x42, &DefaultU8Coder))
+                    .unwrap(),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::new(5).unwrap(),
+        ));
+        node.boot();
+        node.poll_frame();
+        node.process_frame(&EncodedCANOpenFrame::new(0x000, &[0x01, 0x05]));
+
+        let sync = EncodedCANOpenFrame::new(0x080, &[]);
+        node.tick(Instant::from_millis(0));
+        node.process_frame(&sync);
+        assert_eq!(node.poll_event(), Some(NodeEvent::Sync { counter: None }));
+        let tpdo = node.poll_frame().unwrap();
+        assert_eq!(tpdo.id(), EncodedCANOpenFrame::new(0x185, &[]).id());
+        assert_eq!(tpdo.data(), [0x42]);
+
+        // Not transmitted before the window closed.
+        node.tick(Instant::from_millis(10));
+        node.process_frame(&sync);
+        node.tick(Instant::from_millis(12));
+        assert!(node.poll_frame().is_none());
+    }
+
+    #[test]
+    fn test_time_of_day() {
+        let mut node = test_node();
+        node.boot();
+        node.poll_frame();
+        assert_eq!(node.time_of_day(), None);
+
+        let time = TimeOfDay::new(14670, 43_200_000).unwrap();
+        node.tick(Instant::from_millis(1_000));
+        node.process_frame(&EncodedCANOpenFrame::new(0x100, &time.to_bytes()));
+        assert_eq!(node.poll_event(), Some(NodeEvent::Time(time)));
+        node.tick(Instant::from_millis(1_250));
+        assert_eq!(node.time_of_day(), TimeOfDay::new(14670, 43_200_250));
+
+        // Not the time producer by default.
+        node.send_time(time);
+        assert!(node.poll_frame().is_none());
+    }
+
+    struct UnconfiguredStorage;
+
+    impl LssStorage for UnconfiguredStorage {
+        fn load(&mut self) -> Option<LssConfiguration> {
+            Some(LssConfiguration::default())
+        }
+
+        fn store(&mut self, _configuration: &LssConfiguration) -> Result<(), StoreError> {
+            Err(StoreError::AccessError)
+        }
+    }
+
+    #[test]
+    fn test_lss_assigns_node_id() {
+        let node = test_node();
+        let mut node = Node::with_lss_storage(node.od, UnconfiguredStorage);
+        node.boot();
+        assert_eq!(node.node_id(), None);
+        assert_eq!(node.nmt_state(), NmtState::Initialising);
+        assert!(node.poll_frame().is_none());
+
+        node.process_frame(&EncodedCANOpenFrame::new(
+            0x7E5,
+            &[0x04, 1, 0, 0, 0, 0, 0, 0],
+        ));
+        node.process_frame(&EncodedCANOpenFrame::new(
+            0x7E5,
+            &[0x11, 9, 0, 0, 0, 0, 0, 0],
+        ));
+        assert_eq!(
+            node.poll_frame().unwrap().data(),
+            [0x11, 0, 0, 0, 0, 0, 0, 0]
+        );
+        node.process_frame(&EncodedCANOpenFrame::new(
+            0x7E5,
+            &[0x17, 0, 0, 0, 0, 0, 0, 0],
+        ));
+        assert_eq!(
+            node.poll_frame().unwrap().data(),
+            [0x17, 2, 0, 0, 0, 0, 0, 0]
+        );
+
+        node.process_frame(&EncodedCANOpenFrame::new(
+            0x7E5,
+            &[0x04, 0, 0, 0, 0, 0, 0, 0],
+        ));
+        assert_eq!(
+            node.poll_event(),
+            Some(NodeEvent::Lss(LssEvent::NodeIdAssigned(
+                NodeId::new(9).unwrap()
+            )))
+        );
+        assert_eq!(node.node_id(), NodeId::new(9));
+        assert_eq!(node.nmt_state(), NmtState::PreOperational);
+        let boot_up = node.poll_frame().unwrap();
+        assert_eq!(boot_up.id(), EncodedCANOpenFrame::new(0x709, &[]).id());
+    }
+
+    #[test]
+    fn test_lss_moves_node_id_relative_cob_ids() {
+        let mut mappings = [PdoEntryMapping::default(); 8];
+        mappings[0] = PdoEntryMapping::new(0x2000, 0, 8);
+        let od: ObjectDictionary<2, 0, 1> = ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [PdoConfiguration::new(
+                CobId::new(true, false, FrameId::Standard(0x185)),
+                PdoTransmissionType::Synchronous(1),
+                1,
+                mappings,
+                0,
+            )
+            .with_node_id_relative_cob_id()],
+            [],
+            Vec::from_slice(&[
+                Variable::builder(COB_ID_EMCY)
+                    .name("COB-ID EMCY")
+                    .node_id_relative(NodeIdRelative {
+                        default_value: true,
+                        ..Default::default()
+                    })
+                    .value(VariableType::UInt32(0x85, &DefaultU32Coder))
+                    .unwrap(),
+                Variable::builder(EntryId::new(0x2000, 0x0))
+                    .name("value")
+                    .pdo(PdoMapability::All)
+                    .value(VariableType::UInt8(0x42, &DefaultU8Coder))
+                    .unwrap(),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::new(5).unwrap(),
+        );
+        let mut node = Node::with_lss_storage(od, UnconfiguredStorage);
+        node.boot();
+
+        // Switch state global to configuration, configure node-ID 9, then
+        // back to waiting.
+        for data in [[0x04, 1], [0x11, 9], [0x04, 0]] {
+            node.process_frame(&EncodedCANOpenFrame::new(
+                0x7E5,
+                &[data[0], data[1], 0, 0, 0, 0, 0, 0],
+            ));
+        }
+        assert_eq!(node.node_id(), NodeId::new(9));
+        assert_eq!(node.od().read_unsigned(COB_ID_EMCY), Some(0x89));
+        while node.poll_frame().is_some() {}
+
+        node.process_frame(&EncodedCANOpenFrame::new(0x000, &[0x01, 0x09]));
+        node.tick(Instant::from_millis(0));
+        node.process_frame(&EncodedCANOpenFrame::new(0x080, &[]));
+        let tpdo = node.poll_frame().unwrap();
+        assert_eq!(tpdo.id(), EncodedCANOpenFrame::new(0x189, &[]).id());
+        assert_eq!(tpdo.data(), [0x42]);
+
+        node.raise_error(ErrorCode::Generic, [0; 5]);
+        let emcy = node.poll_frame().unwrap();
+        assert_eq!(emcy.id(), EncodedCANOpenFrame::new(0x089, &[]).id());
+    }
+}
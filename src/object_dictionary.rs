@@ -1,8 +1,93 @@
-use std::process::id;
+use core::{cmp::Ordering, ops::RangeBounds};
 
-use num_traits::ToBytes;
+use embedded_can::{ExtendedId, Id, StandardId};
+use heapless::Vec;
 
-use crate::{node::NodeId, parameter_coder::*, pdo::PdoConfiguration};
+use crate::{
+    concise_dcf::{self, CONCISE_DCF_CAPACITY, CONCISE_DCF_INDEX},
+    node::NodeId,
+    parameter_coder::*,
+    pdo::{
+        PdoConfiguration, PdoParameter, RPDO_COMMUNICATION_INDEX, RPDO_MAPPING_INDEX,
+        TPDO_COMMUNICATION_INDEX, TPDO_MAPPING_INDEX,
+    },
+    sdo::SdoAbortCode,
+};
+
+/// Capacity in bytes of string and octet-string values.
+pub const STRING_CAPACITY: usize = 32;
+
+/// The bytes of a string or octet-string value, of at most
+/// [`STRING_CAPACITY`] bytes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct FixedBytes {
+    len: u8,
+    bytes: [u8; STRING_CAPACITY],
+}
+
+impl FixedBytes {
+    pub const fn new() -> Self {
+        Self {
+            len: 0,
+            bytes: [0; STRING_CAPACITY],
+        }
+    }
+
+    /// Returns `None` if `bytes` does not fit in [`STRING_CAPACITY`].
+    pub fn from_slice(bytes: &[u8]) -> Option<Self> {
+        let mut fixed = Self::new();
+        fixed.bytes.get_mut(..bytes.len())?.copy_from_slice(bytes);
+        fixed.len = bytes.len() as u8;
+        Some(fixed)
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl Default for FixedBytes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl core::fmt::Debug for FixedBytes {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.as_slice().fmt(f)
+    }
+}
+
+/// Streams the value of a domain, which may not fit in memory, in and out of
+/// SDO transfers.
+///
+/// Handlers are shared by the object dictionary, so they keep their state
+/// behind interior mutability.
+pub trait DomainHandler {
+    /// Size in bytes of the value to upload.
+    fn size(&self) -> usize;
+
+    /// Largest value in bytes that can be downloaded.
+    fn capacity(&self) -> usize;
+
+    /// Fills `buf` with the bytes of the value starting at `offset`.
+    fn read(&self, offset: usize, buf: &mut [u8]) -> Result<(), SdoAbortCode>;
+
+    /// Writes bytes of a downloaded value at `offset`, in order. A write at
+    /// offset 0 starts a new value.
+    fn write(&self, offset: usize, data: &[u8]) -> Result<(), SdoAbortCode>;
+
+    /// Completes the download of a value of `size` bytes.
+    fn finish_write(&self, size: usize) -> Result<(), SdoAbortCode>;
+}
 
 #[derive(Clone, Copy)]
 pub enum VariableType {
@@ -13,13 +98,26 @@ pub enum VariableType {
     UInt8(u8, &'static dyn U8Coder),
     Int16(i16, &'static dyn I16Coder),
     UInt16(u16, &'static dyn U16Coder),
+    Int24(i32, &'static dyn I24Coder),
+    UInt24(u32, &'static dyn U24Coder),
     Int32(i32, &'static dyn I32Coder),
     UInt32(u32, &'static dyn U32Coder),
+    Int40(i64, &'static dyn I40Coder),
+    UInt40(u64, &'static dyn U40Coder),
+    Int48(i64, &'static dyn I48Coder),
+    UInt48(u64, &'static dyn U48Coder),
+    Int56(i64, &'static dyn I56Coder),
+    UInt56(u64, &'static dyn U56Coder),
     Int64(i64, &'static dyn I64Coder),
     UInt64(u64, &'static dyn U64Coder),
     Float32(f32, &'static dyn F32Coder),
     Float64(f64, &'static dyn F64Coder),
     RawBytes(usize),
+    VisibleString(FixedBytes),
+    OctetString(FixedBytes),
+    /// UTF-16 code units, little-endian encoded.
+    UnicodeString(FixedBytes),
+    Domain(&'static dyn DomainHandler),
 }
 
 impl VariableType {
@@ -31,47 +129,295 @@ impl VariableType {
             | VariableType::Array(_)
             | VariableType::Record(_) => 1,
             VariableType::Int16(_, _) | VariableType::UInt16(_, _) => 2,
+            VariableType::Int24(_, _) | VariableType::UInt24(_, _) => 3,
             VariableType::Int32(_, _)
             | VariableType::UInt32(_, _)
             | VariableType::Float32(_, _) => 4,
+            VariableType::Int40(_, _) | VariableType::UInt40(_, _) => 5,
+            VariableType::Int48(_, _) | VariableType::UInt48(_, _) => 6,
+            VariableType::Int56(_, _) | VariableType::UInt56(_, _) => 7,
             VariableType::Int64(_, _)
             | VariableType::UInt64(_, _)
             | VariableType::Float64(_, _) => 8,
             VariableType::RawBytes(size) => *size,
+            VariableType::VisibleString(value)
+            | VariableType::OctetString(value)
+            | VariableType::UnicodeString(value) => value.len(),
+            VariableType::Domain(domain) => domain.size(),
+        }
+    }
+
+    /// Largest raw size a value can be written with.
+    fn max_size(&self) -> usize {
+        match self {
+            VariableType::VisibleString(_)
+            | VariableType::OctetString(_)
+            | VariableType::UnicodeString(_) => STRING_CAPACITY,
+            VariableType::Domain(domain) => domain.capacity(),
+            _ => self.raw_size(),
+        }
+    }
+
+    /// Strings and domains can be written with fewer bytes than their
+    /// maximum size.
+    fn has_variable_size(&self) -> bool {
+        matches!(
+            self,
+            VariableType::VisibleString(_)
+                | VariableType::OctetString(_)
+                | VariableType::UnicodeString(_)
+                | VariableType::Domain(_)
+        )
+    }
+
+    fn to_raw(self) -> Option<Vec<u8, STRING_CAPACITY>> {
+        match self {
+            VariableType::Array(count) | VariableType::Record(count) => {
+                Vec::from_slice(&[count]).ok()
+            }
+            VariableType::Boolean(value, coder) => Vec::from_slice(&[coder.to_raw(value)]).ok(),
+            VariableType::Int8(value, coder) => Vec::from_slice(&[coder.to_raw(value)]).ok(),
+            VariableType::UInt8(value, coder) => Vec::from_slice(&[coder.to_raw(value)]).ok(),
+            VariableType::Int16(value, coder) => Vec::from_slice(&coder.to_raw(value)).ok(),
+            VariableType::UInt16(value, coder) => Vec::from_slice(&coder.to_raw(value)).ok(),
+            VariableType::Int24(value, coder) => Vec::from_slice(&coder.to_raw(value)).ok(),
+            VariableType::UInt24(value, coder) => Vec::from_slice(&coder.to_raw(value)).ok(),
+            VariableType::Int32(value, coder) => Vec::from_slice(&coder.to_raw(value)).ok(),
+            VariableType::UInt32(value, coder) => Vec::from_slice(&coder.to_raw(value)).ok(),
+            VariableType::Int40(value, coder) => Vec::from_slice(&coder.to_raw(value)).ok(),
+            VariableType::UInt40(value, coder) => Vec::from_slice(&coder.to_raw(value)).ok(),
+            VariableType::Int48(value, coder) => Vec::from_slice(&coder.to_raw(value)).ok(),
+            VariableType::UInt48(value, coder) => Vec::from_slice(&coder.to_raw(value)).ok(),
+            VariableType::Int56(value, coder) => Vec::from_slice(&coder.to_raw(value)).ok(),
+            VariableType::UInt56(value, coder) => Vec::from_slice(&coder.to_raw(value)).ok(),
+            VariableType::Int64(value, coder) => Vec::from_slice(&coder.to_raw(value)).ok(),
+            VariableType::UInt64(value, coder) => Vec::from_slice(&coder.to_raw(value)).ok(),
+            VariableType::Float32(value, coder) => Vec::from_slice(&coder.to_raw(value)).ok(),
+            VariableType::Float64(value, coder) => Vec::from_slice(&coder.to_raw(value)).ok(),
+            VariableType::RawBytes(_) | VariableType::Domain(_) => None,
+            VariableType::VisibleString(value)
+            | VariableType::OctetString(value)
+            | VariableType::UnicodeString(value) => Vec::from_slice(value.as_slice()).ok(),
+        }
+    }
+
+    fn set_from_raw(&mut self, raw: &[u8]) -> Result<(), SdoAbortCode> {
+        match self {
+            VariableType::UnicodeString(_) if !raw.len().is_multiple_of(2) => {
+                return Err(SdoAbortCode::InvalidValue)
+            }
+            VariableType::VisibleString(value)
+            | VariableType::OctetString(value)
+            | VariableType::UnicodeString(value) => {
+                *value = FixedBytes::from_slice(raw).ok_or(SdoAbortCode::TooLong)?;
+                return Ok(());
+            }
+            VariableType::Domain(_) => return Err(SdoAbortCode::UnsupportedAccess),
+            _ => {}
+        }
+        if raw.len() > self.raw_size() {
+            return Err(SdoAbortCode::TooLong);
+        }
+        if raw.len() < self.raw_size() {
+            return Err(SdoAbortCode::TooShort);
+        }
+
+        match self {
+            VariableType::Array(_) | VariableType::Record(_) => {
+                return Err(SdoAbortCode::ReadOnlyError)
+            }
+            VariableType::Boolean(value, coder) => *value = coder.from_raw(raw[0]),
+            VariableType::Int8(value, coder) => *value = coder.from_raw(raw[0]),
+            VariableType::UInt8(value, coder) => *value = coder.from_raw(raw[0]),
+            VariableType::Int16(value, coder) => *value = coder.from_raw(raw.try_into().unwrap()),
+            VariableType::UInt16(value, coder) => *value = coder.from_raw(raw.try_into().unwrap()),
+            VariableType::Int24(value, coder) => *value = coder.from_raw(raw.try_into().unwrap()),
+            VariableType::UInt24(value, coder) => *value = coder.from_raw(raw.try_into().unwrap()),
+            VariableType::Int32(value, coder) => *value = coder.from_raw(raw.try_into().unwrap()),
+            VariableType::UInt32(value, coder) => *value = coder.from_raw(raw.try_into().unwrap()),
+            VariableType::Int40(value, coder) => *value = coder.from_raw(raw.try_into().unwrap()),
+            VariableType::UInt40(value, coder) => *value = coder.from_raw(raw.try_into().unwrap()),
+            VariableType::Int48(value, coder) => *value = coder.from_raw(raw.try_into().unwrap()),
+            VariableType::UInt48(value, coder) => *value = coder.from_raw(raw.try_into().unwrap()),
+            VariableType::Int56(value, coder) => *value = coder.from_raw(raw.try_into().unwrap()),
+            VariableType::UInt56(value, coder) => *value = coder.from_raw(raw.try_into().unwrap()),
+            VariableType::Int64(value, coder) => *value = coder.from_raw(raw.try_into().unwrap()),
+            VariableType::UInt64(value, coder) => *value = coder.from_raw(raw.try_into().unwrap()),
+            VariableType::Float32(value, coder) => *value = coder.from_raw(raw.try_into().unwrap()),
+            VariableType::Float64(value, coder) => *value = coder.from_raw(raw.try_into().unwrap()),
+            VariableType::RawBytes(_)
+            | VariableType::VisibleString(_)
+            | VariableType::OctetString(_)
+            | VariableType::UnicodeString(_)
+            | VariableType::Domain(_) => return Err(SdoAbortCode::UnsupportedAccess),
+        }
+        Ok(())
+    }
+
+    /// Returns the value of a numeric type.
+    fn number(&self) -> Option<Number> {
+        Some(match *self {
+            VariableType::Boolean(value, _) => Number::Unsigned(value as u64),
+            VariableType::Int8(value, _) => Number::Signed(value as i64),
+            VariableType::UInt8(value, _) => Number::Unsigned(value as u64),
+            VariableType::Int16(value, _) => Number::Signed(value as i64),
+            VariableType::UInt16(value, _) => Number::Unsigned(value as u64),
+            VariableType::Int24(value, _) | VariableType::Int32(value, _) => {
+                Number::Signed(value as i64)
+            }
+            VariableType::UInt24(value, _) | VariableType::UInt32(value, _) => {
+                Number::Unsigned(value as u64)
+            }
+            VariableType::Int40(value, _)
+            | VariableType::Int48(value, _)
+            | VariableType::Int56(value, _)
+            | VariableType::Int64(value, _) => Number::Signed(value),
+            VariableType::UInt40(value, _)
+            | VariableType::UInt48(value, _)
+            | VariableType::UInt56(value, _)
+            | VariableType::UInt64(value, _) => Number::Unsigned(value),
+            VariableType::Float32(value, _) => Number::Float(value as f64),
+            VariableType::Float64(value, _) => Number::Float(value),
+            _ => return None,
+        })
+    }
+
+    /// Checks that the value fits in its type, for the integer types narrower
+    /// than the Rust type holding them.
+    fn check_range(&self) -> Result<(), SdoAbortCode> {
+        let bits = match self {
+            VariableType::Int24(_, _) | VariableType::UInt24(_, _) => 24,
+            VariableType::Int40(_, _) | VariableType::UInt40(_, _) => 40,
+            VariableType::Int48(_, _) | VariableType::UInt48(_, _) => 48,
+            VariableType::Int56(_, _) | VariableType::UInt56(_, _) => 56,
+            _ => return Ok(()),
+        };
+        match self.number() {
+            Some(Number::Signed(value)) if value >= 1 << (bits - 1) => {
+                Err(SdoAbortCode::ValueTooHigh)
+            }
+            Some(Number::Signed(value)) if value < -(1 << (bits - 1)) => {
+                Err(SdoAbortCode::ValueTooLow)
+            }
+            Some(Number::Unsigned(value)) if value >= 1 << bits => Err(SdoAbortCode::ValueTooHigh),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns a value of the same numeric type holding `number`.
+    fn with_number(self, number: Number) -> Self {
+        let (signed, unsigned, float) = match number {
+            Number::Signed(value) => (value, value as u64, value as f64),
+            Number::Unsigned(value) => (value as i64, value, value as f64),
+            Number::Float(value) => (value as i64, value as u64, value),
+        };
+        match self {
+            VariableType::Boolean(_, coder) => VariableType::Boolean(unsigned != 0, coder),
+            VariableType::Int8(_, coder) => VariableType::Int8(signed as i8, coder),
+            VariableType::UInt8(_, coder) => VariableType::UInt8(unsigned as u8, coder),
+            VariableType::Int16(_, coder) => VariableType::Int16(signed as i16, coder),
+            VariableType::UInt16(_, coder) => VariableType::UInt16(unsigned as u16, coder),
+            VariableType::Int24(_, coder) => VariableType::Int24(signed as i32, coder),
+            VariableType::UInt24(_, coder) => VariableType::UInt24(unsigned as u32, coder),
+            VariableType::Int32(_, coder) => VariableType::Int32(signed as i32, coder),
+            VariableType::UInt32(_, coder) => VariableType::UInt32(unsigned as u32, coder),
+            VariableType::Int40(_, coder) => VariableType::Int40(signed, coder),
+            VariableType::UInt40(_, coder) => VariableType::UInt40(unsigned, coder),
+            VariableType::Int48(_, coder) => VariableType::Int48(signed, coder),
+            VariableType::UInt48(_, coder) => VariableType::UInt48(unsigned, coder),
+            VariableType::Int56(_, coder) => VariableType::Int56(signed, coder),
+            VariableType::UInt56(_, coder) => VariableType::UInt56(unsigned, coder),
+            VariableType::Int64(_, coder) => VariableType::Int64(signed, coder),
+            VariableType::UInt64(_, coder) => VariableType::UInt64(unsigned, coder),
+            VariableType::Float32(_, coder) => VariableType::Float32(float as f32, coder),
+            VariableType::Float64(_, coder) => VariableType::Float64(float, coder),
+            other => other,
         }
     }
 }
 
-#[derive(Clone, Copy)]
-enum AccessType {
+/// A numeric value, the compact form in which variables keep their limits
+/// and default value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Number {
+    Signed(i64),
+    Unsigned(u64),
+    Float(f64),
+}
+
+impl Number {
+    /// Adds `offset` to an integer, leaving floats as they are.
+    fn offset(self, offset: i64) -> Self {
+        match self {
+            Number::Signed(value) => Number::Signed(value.wrapping_add(offset)),
+            Number::Unsigned(value) => Number::Unsigned(value.wrapping_add_signed(offset)),
+            Number::Float(value) => Number::Float(value),
+        }
+    }
+
+    /// Compares two numbers of the same kind.
+    fn compare(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Number::Signed(a), Number::Signed(b)) => a.partial_cmp(b),
+            (Number::Unsigned(a), Number::Unsigned(b)) => a.partial_cmp(b),
+            (Number::Float(a), Number::Float(b)) => a.partial_cmp(b),
+            _ => None,
+        }
+    }
+}
+
+/// The CiA 301 access types, spelled `ro`, `wo`, `rw`, `rwr`, `rww` and
+/// `const` in EDS files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessType {
     ReadOnly,
     WriteOnly,
     ReadWrite,
+    /// Read-write, only mappable into TPDOs (`rwr`).
+    ReadWriteTpdo,
+    /// Read-write, only mappable into RPDOs (`rww`).
+    ReadWriteRpdo,
+    /// Read-only, and never changing (`const`).
+    Const,
 }
 
 impl AccessType {
     fn allows_writing(&self) -> bool {
         match self {
-            Self::WriteOnly | Self::ReadWrite => true,
-            Self::ReadOnly => false,
+            Self::WriteOnly | Self::ReadWrite | Self::ReadWriteTpdo | Self::ReadWriteRpdo => true,
+            Self::ReadOnly | Self::Const => false,
         }
     }
 
     fn allows_reading(&self) -> bool {
         match self {
-            Self::ReadOnly | Self::ReadWrite => true,
+            Self::ReadOnly
+            | Self::Const
+            | Self::ReadWrite
+            | Self::ReadWriteTpdo
+            | Self::ReadWriteRpdo => true,
             Self::WriteOnly => false,
         }
     }
+
+    /// Returns the PDO directions that entries of this access type can be
+    /// mapped in: TPDOs read them, RPDOs write them.
+    pub fn pdo_mapability(&self) -> PdoMapability {
+        match self {
+            Self::ReadWrite => PdoMapability::All,
+            Self::ReadOnly | Self::Const | Self::ReadWriteTpdo => PdoMapability::Tpdo,
+            Self::WriteOnly | Self::ReadWriteRpdo => PdoMapability::Rpdo,
+        }
+    }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum StorageLocation {
     NonVolatile,
     Ram,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PdoMapability {
     All,
     Tpdo,
@@ -79,6 +425,31 @@ pub enum PdoMapability {
     None,
 }
 
+impl PdoMapability {
+    pub fn allows_tpdo(&self) -> bool {
+        matches!(self, Self::All | Self::Tpdo)
+    }
+
+    pub fn allows_rpdo(&self) -> bool {
+        matches!(self, Self::All | Self::Rpdo)
+    }
+
+    /// Keeps only the directions that `access_type` allows, see
+    /// [`AccessType::pdo_mapability`].
+    pub fn restricted_to(self, access_type: AccessType) -> Self {
+        let allowed = access_type.pdo_mapability();
+        match (
+            self.allows_tpdo() && allowed.allows_tpdo(),
+            self.allows_rpdo() && allowed.allows_rpdo(),
+        ) {
+            (true, true) => Self::All,
+            (true, false) => Self::Tpdo,
+            (false, true) => Self::Rpdo,
+            (false, false) => Self::None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct EntryId {
     index: u16,
@@ -86,7 +457,7 @@ pub struct EntryId {
 }
 
 impl EntryId {
-    pub fn new(index: u16, sub_index: u8) -> Self {
+    pub const fn new(index: u16, sub_index: u8) -> Self {
         Self { index, sub_index }
     }
 
@@ -97,7 +468,15 @@ impl EntryId {
         }
     }
 
-    pub(crate) fn to_le_bytes(&self) -> [u8; 3] {
+    pub fn index(&self) -> u16 {
+        self.index
+    }
+
+    pub fn sub_index(&self) -> u8 {
+        self.sub_index
+    }
+
+    pub(crate) fn to_le_bytes(self) -> [u8; 3] {
         let mut bytes = [0; 3];
         bytes[0..2].copy_from_slice(&self.index.to_le_bytes());
         bytes[2] = self.sub_index;
@@ -105,6 +484,36 @@ impl EntryId {
     }
 }
 
+/// Which values of a variable are relative to the node-ID, like
+/// `$NODEID+0x80` in an EDS, see [`VariableBuilder::node_id_relative`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NodeIdRelative {
+    pub low_limit: bool,
+    pub high_limit: bool,
+    pub default_value: bool,
+}
+
+impl NodeIdRelative {
+    const LOW_LIMIT: u8 = 0x1;
+    const HIGH_LIMIT: u8 = 0x2;
+    const DEFAULT_VALUE: u8 = 0x4;
+
+    /// Packs the flags into the bits kept by [`Variable`].
+    fn bits(&self) -> u8 {
+        let mut bits = 0;
+        for (relative, bit) in [
+            (self.low_limit, Self::LOW_LIMIT),
+            (self.high_limit, Self::HIGH_LIMIT),
+            (self.default_value, Self::DEFAULT_VALUE),
+        ] {
+            if relative {
+                bits |= bit;
+            }
+        }
+        bits
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct Variable {
     name: &'static str,
@@ -113,9 +522,275 @@ pub struct Variable {
     pdo_mapability: PdoMapability,
     access_type: AccessType,
     id: EntryId,
+    low_limit: Option<Number>,
+    high_limit: Option<Number>,
+    /// `None` for the variables that are not numeric, which have no default.
+    default_value: Option<Number>,
+    /// [`NodeIdRelative`] bits, packed to keep variables small.
+    node_id_relative: u8,
 }
 
-enum FrameId {
+impl Variable {
+    pub(crate) fn read_raw(&self) -> Result<Vec<u8, STRING_CAPACITY>, SdoAbortCode> {
+        if !self.access_type.allows_reading() {
+            return Err(SdoAbortCode::WriteOnlyError);
+        }
+        self.data_type
+            .to_raw()
+            .ok_or(SdoAbortCode::UnsupportedAccess)
+    }
+
+    pub(crate) fn raw_size(&self) -> usize {
+        self.data_type.raw_size()
+    }
+
+    pub(crate) fn write_raw(&mut self, raw: &[u8]) -> Result<(), SdoAbortCode> {
+        if !self.access_type.allows_writing() {
+            return Err(SdoAbortCode::ReadOnlyError);
+        }
+        if let VariableType::Domain(domain) = self.data_type {
+            domain.write(0, raw)?;
+            return domain.finish_write(raw.len());
+        }
+        self.data_type = self.decode_raw(raw)?;
+        Ok(())
+    }
+
+    /// Decodes a raw value of the type of the variable, checking it against
+    /// its limits.
+    fn decode_raw(&self, raw: &[u8]) -> Result<VariableType, SdoAbortCode> {
+        let mut value = self.data_type;
+        value.set_from_raw(raw)?;
+        self.check_limits(&value)?;
+        Ok(value)
+    }
+
+    fn check_limits(&self, value: &VariableType) -> Result<(), SdoAbortCode> {
+        value.check_range()?;
+        if self.low_limit.is_none() && self.high_limit.is_none() {
+            return Ok(());
+        }
+        let value = value.number().ok_or(SdoAbortCode::InvalidValue)?;
+        if let Some(low_limit) = &self.low_limit {
+            match value.compare(low_limit) {
+                Some(Ordering::Less) => return Err(SdoAbortCode::ValueTooLow),
+                Some(_) => {}
+                None => return Err(SdoAbortCode::InvalidValue),
+            }
+        }
+        if let Some(high_limit) = &self.high_limit {
+            match value.compare(high_limit) {
+                Some(Ordering::Greater) => return Err(SdoAbortCode::ValueTooHigh),
+                Some(_) => {}
+                None => return Err(SdoAbortCode::InvalidValue),
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    pub(crate) fn new(
+        id: EntryId,
+        name: &'static str,
+        data_type: VariableType,
+        access_type: AccessType,
+    ) -> Self {
+        Self {
+            name,
+            storage_location: StorageLocation::Ram,
+            data_type,
+            pdo_mapability: PdoMapability::None,
+            access_type,
+            id,
+            low_limit: None,
+            high_limit: None,
+            default_value: data_type.number(),
+            node_id_relative: 0,
+        }
+    }
+
+    /// Starts building the variable `id`: a read-write, unnamed variable held
+    /// in RAM and not mappable into PDOs, until set otherwise.
+    pub fn builder(id: EntryId) -> VariableBuilder {
+        VariableBuilder {
+            id,
+            name: "",
+            access_type: AccessType::ReadWrite,
+            pdo_mapability: PdoMapability::None,
+            storage_location: StorageLocation::Ram,
+            low_limit: None,
+            high_limit: None,
+            default_value: None,
+            node_id_relative: NodeIdRelative::default(),
+        }
+    }
+
+    pub fn id(&self) -> EntryId {
+        self.id
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn access_type(&self) -> AccessType {
+        self.access_type
+    }
+
+    pub fn pdo_mapability(&self) -> PdoMapability {
+        self.pdo_mapability
+    }
+
+    pub fn storage_location(&self) -> StorageLocation {
+        self.storage_location
+    }
+
+    pub fn value(&self) -> VariableType {
+        self.data_type
+    }
+
+    pub fn low_limit(&self) -> Option<VariableType> {
+        self.low_limit
+            .map(|limit| self.data_type.with_number(limit))
+    }
+
+    pub fn high_limit(&self) -> Option<VariableType> {
+        self.high_limit
+            .map(|limit| self.data_type.with_number(limit))
+    }
+
+    /// Returns the default value, `None` if the variable is not numeric.
+    pub fn default_value(&self) -> Option<VariableType> {
+        self.default_value
+            .map(|value| self.data_type.with_number(value))
+    }
+
+    /// Sets the value from the application, regardless of the access type.
+    /// The value must be of the same type as the variable, and within its
+    /// limits.
+    pub fn set_value(&mut self, value: VariableType) -> Result<(), SdoAbortCode> {
+        if core::mem::discriminant(&self.data_type) != core::mem::discriminant(&value) {
+            return Err(SdoAbortCode::WrongLength);
+        }
+        self.check_limits(&value)?;
+        self.data_type = value;
+        Ok(())
+    }
+
+    /// Moves the values relative to the node-ID from the node-ID `from` to
+    /// `to`.
+    fn change_node_id(&mut self, from: NodeId, to: NodeId) {
+        let offset = to.raw() as i64 - from.raw() as i64;
+        for (number, bit) in [
+            (&mut self.low_limit, NodeIdRelative::LOW_LIMIT),
+            (&mut self.high_limit, NodeIdRelative::HIGH_LIMIT),
+            (&mut self.default_value, NodeIdRelative::DEFAULT_VALUE),
+        ] {
+            if self.node_id_relative & bit != 0 {
+                *number = number.map(|number| number.offset(offset));
+            }
+        }
+    }
+
+    /// Sets the value back to its default, regardless of the access type.
+    /// Only numeric defaults are kept, so string and octet-string variables
+    /// are left as they are.
+    pub fn restore_default(&mut self) {
+        if let Some(default_value) = self.default_value {
+            self.data_type = self.data_type.with_number(default_value);
+        }
+    }
+}
+
+/// Builds a [`Variable`], see [`Variable::builder`].
+#[derive(Clone, Copy)]
+pub struct VariableBuilder {
+    id: EntryId,
+    name: &'static str,
+    access_type: AccessType,
+    pdo_mapability: PdoMapability,
+    storage_location: StorageLocation,
+    low_limit: Option<VariableType>,
+    high_limit: Option<VariableType>,
+    default_value: Option<VariableType>,
+    node_id_relative: NodeIdRelative,
+}
+
+impl VariableBuilder {
+    pub fn name(mut self, name: &'static str) -> Self {
+        self.name = name;
+        self
+    }
+
+    pub fn access(mut self, access_type: AccessType) -> Self {
+        self.access_type = access_type;
+        self
+    }
+
+    pub fn pdo(mut self, pdo_mapability: PdoMapability) -> Self {
+        self.pdo_mapability = pdo_mapability;
+        self
+    }
+
+    pub fn storage(mut self, storage_location: StorageLocation) -> Self {
+        self.storage_location = storage_location;
+        self
+    }
+
+    pub fn low_limit(mut self, low_limit: VariableType) -> Self {
+        self.low_limit = Some(low_limit);
+        self
+    }
+
+    pub fn high_limit(mut self, high_limit: VariableType) -> Self {
+        self.high_limit = Some(high_limit);
+        self
+    }
+
+    /// Sets the default value, which is the built value otherwise. Only
+    /// numeric variables have one.
+    pub fn default_value(mut self, default_value: VariableType) -> Self {
+        self.default_value = Some(default_value);
+        self
+    }
+
+    /// Marks values as relative to the node-ID. They are given for the
+    /// node-ID the object dictionary is built with, and follow it when it
+    /// changes, e.g. through LSS.
+    pub fn node_id_relative(mut self, node_id_relative: NodeIdRelative) -> Self {
+        self.node_id_relative = node_id_relative;
+        self
+    }
+
+    /// Builds the variable, holding `value` and of its type. Fails with
+    /// [`SdoAbortCode::ValueTooHigh`] or [`SdoAbortCode::ValueTooLow`] if the
+    /// value, a limit or the default value does not fit in its type.
+    pub fn value(self, value: VariableType) -> Result<Variable, SdoAbortCode> {
+        [
+            Some(value),
+            self.low_limit,
+            self.high_limit,
+            self.default_value,
+        ]
+        .iter()
+        .flatten()
+        .try_for_each(VariableType::check_range)?;
+        Ok(Variable {
+            name: self.name,
+            storage_location: self.storage_location,
+            data_type: value,
+            pdo_mapability: self.pdo_mapability.restricted_to(self.access_type),
+            access_type: self.access_type,
+            id: self.id,
+            low_limit: self.low_limit.as_ref().and_then(VariableType::number),
+            high_limit: self.high_limit.as_ref().and_then(VariableType::number),
+            default_value: self.default_value.unwrap_or(value).number(),
+            node_id_relative: self.node_id_relative.bits(),
+        })
+    }
+}
+
+pub enum FrameId {
     Standard(u16),
     Extended(u32),
 }
@@ -127,26 +802,42 @@ pub struct CobId {
 
 impl CobId {
     const DISABLED_ID: u32 = 0x8000_0000;
+    const NO_RTR: u32 = 0x4000_0000;
+    const EXTENDED_ID: u32 = 0x2000_0000;
+
     pub fn new(enabled: bool, rtr_allowed: bool, frame_id: FrameId) -> Self {
         let raw_frame_id = match frame_id {
             FrameId::Standard(id) => (id & 0x7FF) as u32,
-            FrameId::Extended(id) => (id & 0x1FFF_FFFF),
+            FrameId::Extended(id) => (id & 0x1FFF_FFFF) | Self::EXTENDED_ID,
         };
-        Self {
-            guts: ((enabled as u32) << 31) + ((rtr_allowed as u32) << 30) + raw_frame_id,
+        let mut guts = raw_frame_id;
+        if !enabled {
+            guts |= Self::DISABLED_ID;
+        }
+        if !rtr_allowed {
+            guts |= Self::NO_RTR;
         }
+        Self { guts }
+    }
+
+    pub fn from_raw(raw: u32) -> Self {
+        Self { guts: raw }
+    }
+
+    pub fn raw(&self) -> u32 {
+        self.guts
     }
 
     pub fn is_valid(&self) -> bool {
-        (self.guts >> 31) & 0x1 == 0x0
+        self.guts & Self::DISABLED_ID == 0x0
     }
 
     pub fn rtr_allowed(&self) -> bool {
-        (self.guts >> 30) & 0x1 == 0x0
+        self.guts & Self::NO_RTR == 0x0
     }
 
     fn is_extended_id(&self) -> bool {
-        (self.guts >> 29) & 0x1 == 0x1
+        self.guts & Self::EXTENDED_ID != 0x0
     }
 
     pub fn assigned_frame_id(&self) -> FrameId {
@@ -156,6 +847,14 @@ impl CobId {
             FrameId::Standard((self.guts & 0x7FF) as u16)
         }
     }
+
+    /// Returns the CAN identifier of the object.
+    pub fn can_id(&self) -> Id {
+        match self.assigned_frame_id() {
+            FrameId::Standard(id) => Id::Standard(StandardId::new(id).unwrap()),
+            FrameId::Extended(id) => Id::Extended(ExtendedId::new(id).unwrap()),
+        }
+    }
 }
 
 impl Default for CobId {
@@ -166,6 +865,19 @@ impl Default for CobId {
     }
 }
 
+/// Error register, served from [`ObjectDictionary::error_register`].
+const ERROR_REGISTER_INDEX: u16 = 0x1001;
+
+/// Pre-defined error field, served from [`ObjectDictionary::error_history`].
+const PREDEFINED_ERROR_FIELD_INDEX: u16 = 0x1003;
+
+/// Decodes a PDO parameter, whose size is checked beforehand.
+fn pdo_parameter_value(raw: &[u8]) -> u32 {
+    let mut bytes = [0; 4];
+    bytes[..raw.len()].copy_from_slice(raw);
+    u32::from_le_bytes(bytes)
+}
+
 pub struct ObjectDictionary<
     const ENTRY_COUNT: usize,
     const RPDO_COUNT: usize,
@@ -174,9 +886,12 @@ pub struct ObjectDictionary<
     error_register: u8,
     manufacturer_status_register: u32,
     predefined_errors: [u32; 8],
+    predefined_error_count: u8,
     entries: heapless::Vec<Variable, ENTRY_COUNT>,
     tpdo_mappings: [PdoConfiguration; TPDO_COUNT],
     rpdo_mappings: [PdoConfiguration; RPDO_COUNT],
+    default_tpdo_mappings: [PdoConfiguration; TPDO_COUNT],
+    default_rpdo_mappings: [PdoConfiguration; RPDO_COUNT],
     node_id: NodeId,
 }
 
@@ -194,35 +909,503 @@ impl<const ENTRY_COUNT: usize, const RPDO_COUNT: usize, const TPDO_COUNT: usize>
     ) -> Self {
         let mut e = entries;
         e.sort_by_key(|v| v.id);
+        let predefined_error_count = predefined_errors
+            .iter()
+            .take_while(|error| **error != 0)
+            .count() as u8;
 
         Self {
             error_register,
             manufacturer_status_register,
             predefined_errors,
+            predefined_error_count,
             entries: e,
             tpdo_mappings,
             rpdo_mappings,
+            default_tpdo_mappings: tpdo_mappings,
+            default_rpdo_mappings: rpdo_mappings,
             node_id,
         }
     }
 
+    pub fn node_id(&self) -> NodeId {
+        self.node_id
+    }
+
+    /// Changes the node-ID, moving the default values and limits relative to
+    /// it. The values themselves follow when restored, as on Reset
+    /// Communication.
+    pub(crate) fn set_node_id(&mut self, node_id: NodeId) {
+        for variable in self.entries.iter_mut() {
+            variable.change_node_id(self.node_id, node_id);
+        }
+        for configuration in self
+            .default_rpdo_mappings
+            .iter_mut()
+            .chain(self.default_tpdo_mappings.iter_mut())
+        {
+            configuration.change_node_id(self.node_id, node_id);
+        }
+        self.node_id = node_id;
+    }
+
+    /// Returns the variables, by increasing index and sub-index.
+    pub fn variables(&self) -> &[Variable] {
+        &self.entries
+    }
+
     pub fn get_mut_variable(&mut self, id: EntryId) -> Option<&mut Variable> {
         match self.entries.binary_search_by_key(&id, |v| v.id) {
             Ok(idx) => Some(self.entries.get_mut(idx).unwrap()),
             Err(_) => None,
         }
     }
+
+    pub fn get_variable(&self, id: EntryId) -> Option<&Variable> {
+        match self.entries.binary_search_by_key(&id, |v| v.id) {
+            Ok(idx) => self.entries.get(idx),
+            Err(_) => None,
+        }
+    }
+
+    /// Returns the first variable named `name`.
+    pub fn get_variable_by_name(&self, name: &str) -> Option<&Variable> {
+        self.entries.iter().find(|v| v.name == name)
+    }
+
+    /// Returns the first variable named `name`.
+    pub fn get_mut_variable_by_name(&mut self, name: &str) -> Option<&mut Variable> {
+        self.entries.iter_mut().find(|v| v.name == name)
+    }
+
+    /// Returns the variables of every sub-index of `index`, by increasing
+    /// sub-index.
+    pub fn get_variables_by_index(&self, index: u16) -> &[Variable] {
+        &self.entries[self.index_range(index)]
+    }
+
+    /// Returns the variables of every sub-index of `index`, by increasing
+    /// sub-index.
+    pub fn get_mut_variables_by_index(&mut self, index: u16) -> &mut [Variable] {
+        let range = self.index_range(index);
+        &mut self.entries[range]
+    }
+
+    fn index_range(&self, index: u16) -> core::ops::Range<usize> {
+        let start = self.entries.partition_point(|v| v.id.index < index);
+        let end = self.entries.partition_point(|v| v.id.index <= index);
+        start..end
+    }
+
+    /// Reads the raw, little-endian encoded value of an entry, as it would be
+    /// transferred over SDO.
+    pub(crate) fn read_raw(&self, id: EntryId) -> Result<Vec<u8, STRING_CAPACITY>, SdoAbortCode> {
+        match id.index {
+            ERROR_REGISTER_INDEX => {
+                self.check_error_register_sub_index(id)?;
+                return Ok(Vec::from_slice(&[self.error_register]).unwrap());
+            }
+            PREDEFINED_ERROR_FIELD_INDEX => return self.read_error_history(id.sub_index),
+            _ if self.is_concise_dcf(id) => return Err(SdoAbortCode::WriteOnlyError),
+            _ => {}
+        }
+        if let Some((parameter, config)) = self.pdo_parameter(id.index) {
+            let raw = config.read_parameter(parameter, id.sub_index)?;
+            return Ok(Vec::from_slice(&raw).unwrap());
+        }
+        self.get_variable(id)
+            .ok_or_else(|| self.missing_entry_code(id))?
+            .read_raw()
+    }
+
+    /// Writes a raw, little-endian encoded value into an entry, as it would be
+    /// received over SDO.
+    pub(crate) fn write_raw(&mut self, id: EntryId, raw: &[u8]) -> Result<(), SdoAbortCode> {
+        if self.is_concise_dcf(id) {
+            return concise_dcf::apply(self, raw);
+        }
+        self.check_write(id, raw)?;
+        if id.index == PREDEFINED_ERROR_FIELD_INDEX {
+            self.clear_error_history();
+            return Ok(());
+        }
+        if let Some((parameter, _)) = self.pdo_parameter(id.index) {
+            let config = match parameter.direction {
+                PdoMapability::Rpdo => self.rpdo_configuration_mut(parameter.pdo),
+                _ => self.tpdo_configuration_mut(parameter.pdo),
+            };
+            config
+                .unwrap()
+                .write_parameter(parameter, id.sub_index, pdo_parameter_value(raw));
+            return Ok(());
+        }
+        self.get_mut_variable(id).unwrap().write_raw(raw)
+    }
+
+    /// Checks that [`Self::write_raw`] would accept a value, without writing
+    /// it.
+    pub(crate) fn check_write(&self, id: EntryId, raw: &[u8]) -> Result<(), SdoAbortCode> {
+        if id.index == PREDEFINED_ERROR_FIELD_INDEX && id.sub_index == 0 {
+            // Only writing 0 is allowed, which clears the error history.
+            return match raw {
+                [0] => Ok(()),
+                [_] => Err(SdoAbortCode::InvalidValue),
+                [] => Err(SdoAbortCode::TooShort),
+                _ => Err(SdoAbortCode::TooLong),
+            };
+        }
+        self.check_download_size(id, raw.len())?;
+        if self.is_concise_dcf(id) || self.domain(id).is_some() {
+            return Ok(());
+        }
+        if let Some((parameter, config)) = self.pdo_parameter(id.index) {
+            return config.check_parameter(self, parameter, id.sub_index, pdo_parameter_value(raw));
+        }
+        let mut variable = *self.get_variable(id).unwrap();
+        variable.write_raw(raw)
+    }
+
+    /// Checks that a value of `size` bytes can be downloaded into an entry.
+    pub(crate) fn check_download_size(&self, id: EntryId, size: usize) -> Result<(), SdoAbortCode> {
+        let entry_size = self.writable_size(id)?;
+        if size > entry_size {
+            return Err(SdoAbortCode::TooLong);
+        }
+        if size < entry_size && !self.has_variable_size(id) {
+            return Err(SdoAbortCode::TooShort);
+        }
+        Ok(())
+    }
+
+    fn has_variable_size(&self, id: EntryId) -> bool {
+        self.is_concise_dcf(id)
+            || self
+                .get_variable(id)
+                .is_some_and(|v| v.data_type.has_variable_size())
+    }
+
+    /// Returns the handler of an entry holding a domain, regardless of its
+    /// access type.
+    pub(crate) fn domain(&self, id: EntryId) -> Option<&'static dyn DomainHandler> {
+        match self.get_variable(id)?.data_type {
+            VariableType::Domain(domain) => Some(domain),
+            _ => None,
+        }
+    }
+
+    /// Returns the handler of an entry holding a domain, if it can be read.
+    pub(crate) fn readable_domain(
+        &self,
+        id: EntryId,
+    ) -> Result<Option<&'static dyn DomainHandler>, SdoAbortCode> {
+        let Some(domain) = self.domain(id) else {
+            return Ok(None);
+        };
+        if !self.get_variable(id).unwrap().access_type.allows_reading() {
+            return Err(SdoAbortCode::WriteOnlyError);
+        }
+        Ok(Some(domain))
+    }
+
+    /// Returns the PDO communication or mapping parameter at `index`, from
+    /// 0x1400 to 0x1BFF, if the PDO is configured.
+    fn pdo_parameter(&self, index: u16) -> Option<(PdoParameter, &PdoConfiguration)> {
+        let parameter = PdoParameter::from_index(index)?;
+        let config = match parameter.direction {
+            PdoMapability::Rpdo => self.rpdo_configuration(parameter.pdo)?,
+            _ => self.tpdo_configuration(parameter.pdo)?,
+        };
+        Some((parameter, config))
+    }
+
+    /// Whether `index` is a PDO communication or mapping parameter served by
+    /// [`Self::rpdo_configuration`] or [`Self::tpdo_configuration`].
+    pub(crate) fn is_pdo_parameter(&self, index: u16) -> bool {
+        self.pdo_parameter(index).is_some()
+    }
+
+    /// The concise DCF of a node is written into 0x1F22, at the sub-index of
+    /// its own node-ID.
+    fn is_concise_dcf(&self, id: EntryId) -> bool {
+        id.index == CONCISE_DCF_INDEX && id.sub_index == self.node_id.raw()
+    }
+
+    /// Sets an entry back to its default value.
+    ///
+    /// Only numeric defaults are kept, so this fails with
+    /// [`SdoAbortCode::NoDataAvailable`] for string, octet-string and other
+    /// entries without one, leaving their value as it is.
+    pub fn restore_default(&mut self, id: EntryId) -> Result<(), SdoAbortCode> {
+        let code = self.missing_entry_code(id);
+        let variable = self.get_mut_variable(id).ok_or(code)?;
+        if variable.default_value.is_none() {
+            return Err(SdoAbortCode::NoDataAvailable);
+        }
+        variable.restore_default();
+        Ok(())
+    }
+
+    /// Sets every entry of the indices in `indices` back to its default value.
+    /// Entries without one, such as strings, keep their value.
+    ///
+    /// The PDOs get back the configurations the object dictionary was built
+    /// with, and the pre-defined error field (0x1003) is cleared.
+    pub fn restore_defaults(&mut self, indices: impl RangeBounds<u16>) {
+        self.entries
+            .iter_mut()
+            .filter(|v| indices.contains(&v.id.index))
+            .for_each(Variable::restore_default);
+        let pdos = [
+            (
+                RPDO_COMMUNICATION_INDEX,
+                RPDO_MAPPING_INDEX,
+                &mut self.rpdo_mappings[..],
+                &self.default_rpdo_mappings[..],
+            ),
+            (
+                TPDO_COMMUNICATION_INDEX,
+                TPDO_MAPPING_INDEX,
+                &mut self.tpdo_mappings[..],
+                &self.default_tpdo_mappings[..],
+            ),
+        ];
+        for (communication_index, mapping_index, configurations, defaults) in pdos {
+            for (pdo, (configuration, default)) in
+                (0..).zip(configurations.iter_mut().zip(defaults))
+            {
+                if indices.contains(&(communication_index + pdo))
+                    || indices.contains(&(mapping_index + pdo))
+                {
+                    *configuration = *default;
+                }
+            }
+        }
+        if indices.contains(&PREDEFINED_ERROR_FIELD_INDEX) {
+            self.clear_error_history();
+        }
+    }
+
+    /// Returns the error register (0x1001).
+    pub fn error_register(&self) -> u8 {
+        self.error_register
+    }
+
+    pub(crate) fn set_error_register(&mut self, error_register: u8) {
+        self.error_register = error_register;
+    }
+
+    /// Returns the manufacturer status register (0x1002).
+    pub fn manufacturer_status_register(&self) -> u32 {
+        self.manufacturer_status_register
+    }
+
+    /// Returns the pre-defined error field (0x1003), newest error first. Each
+    /// entry holds the error code in its lower 16 bits.
+    pub fn error_history(&self) -> &[u32] {
+        &self.predefined_errors[..self.predefined_error_count as usize]
+    }
+
+    /// Records an error in the history, dropping the oldest one when full.
+    pub(crate) fn push_error_history(&mut self, error: u32) {
+        self.predefined_errors.rotate_right(1);
+        self.predefined_errors[0] = error;
+        self.predefined_error_count =
+            (self.predefined_error_count + 1).min(self.predefined_errors.len() as u8);
+    }
+
+    pub(crate) fn clear_error_history(&mut self) {
+        self.predefined_errors = [0; 8];
+        self.predefined_error_count = 0;
+    }
+
+    fn read_error_history(&self, sub_index: u8) -> Result<Vec<u8, STRING_CAPACITY>, SdoAbortCode> {
+        match sub_index as usize {
+            0 => Ok(Vec::from_slice(&[self.predefined_error_count]).unwrap()),
+            n if n > self.predefined_errors.len() => Err(SdoAbortCode::SubindexDoesNotExist),
+            n => match self.error_history().get(n - 1) {
+                Some(error) => Ok(Vec::from_slice(&error.to_le_bytes()).unwrap()),
+                None => Err(SdoAbortCode::NoDataAvailable),
+            },
+        }
+    }
+
+    fn check_error_register_sub_index(&self, id: EntryId) -> Result<(), SdoAbortCode> {
+        if id.sub_index == 0 {
+            Ok(())
+        } else {
+            Err(SdoAbortCode::SubindexDoesNotExist)
+        }
+    }
+
+    pub fn tpdo_configuration(&self, pdo: usize) -> Option<&PdoConfiguration> {
+        self.tpdo_mappings.get(pdo)
+    }
+
+    pub fn tpdo_configuration_mut(&mut self, pdo: usize) -> Option<&mut PdoConfiguration> {
+        self.tpdo_mappings.get_mut(pdo)
+    }
+
+    pub fn rpdo_configuration(&self, pdo: usize) -> Option<&PdoConfiguration> {
+        self.rpdo_mappings.get(pdo)
+    }
+
+    pub fn rpdo_configuration_mut(&mut self, pdo: usize) -> Option<&mut PdoConfiguration> {
+        self.rpdo_mappings.get_mut(pdo)
+    }
+
+    /// Returns a copy of the RPDO and TPDO configurations, to be restored
+    /// with [`Self::restore_pdo_configurations`].
+    pub(crate) fn pdo_configurations(
+        &self,
+    ) -> (
+        [PdoConfiguration; RPDO_COUNT],
+        [PdoConfiguration; TPDO_COUNT],
+    ) {
+        (self.rpdo_mappings, self.tpdo_mappings)
+    }
+
+    pub(crate) fn restore_pdo_configurations(
+        &mut self,
+        (rpdo_mappings, tpdo_mappings): (
+            [PdoConfiguration; RPDO_COUNT],
+            [PdoConfiguration; TPDO_COUNT],
+        ),
+    ) {
+        self.rpdo_mappings = rpdo_mappings;
+        self.tpdo_mappings = tpdo_mappings;
+    }
+
+    /// Reads the raw value of an entry as a little-endian unsigned integer,
+    /// regardless of its access type. Returns `None` if the entry does not
+    /// exist or has no fixed-size value.
+    pub(crate) fn read_unsigned(&self, id: EntryId) -> Option<u64> {
+        let data_type = self.get_variable(id)?.data_type;
+        if data_type.has_variable_size() {
+            return None;
+        }
+        let raw = data_type.to_raw()?;
+        Some(
+            raw.iter()
+                .rev()
+                .fold(0u64, |value, byte| (value << 8) | *byte as u64),
+        )
+    }
+
+    /// Writes the raw value of an entry from a little-endian unsigned integer,
+    /// regardless of its access type but within its limits. Bits beyond the
+    /// size of the entry are ignored.
+    pub(crate) fn write_unsigned(&mut self, id: EntryId, value: u64) -> Result<(), SdoAbortCode> {
+        let value = self.decode_unsigned(id, value)?;
+        self.get_mut_variable(id).unwrap().data_type = value;
+        Ok(())
+    }
+
+    /// Checks that [`Self::write_unsigned`] would accept a value, without
+    /// writing it.
+    pub(crate) fn check_unsigned(&self, id: EntryId, value: u64) -> Result<(), SdoAbortCode> {
+        self.decode_unsigned(id, value).map(|_| ())
+    }
+
+    fn decode_unsigned(&self, id: EntryId, value: u64) -> Result<VariableType, SdoAbortCode> {
+        let variable = self
+            .get_variable(id)
+            .ok_or_else(|| self.missing_entry_code(id))?;
+        let size = variable.raw_size();
+        if size > 8 || variable.data_type.has_variable_size() {
+            return Err(SdoAbortCode::UnsupportedAccess);
+        }
+        variable.decode_raw(&value.to_le_bytes()[..size])
+    }
+
+    /// Checks that an entry can be mapped into a PDO of the given direction,
+    /// [`PdoMapability::Tpdo`] or [`PdoMapability::Rpdo`], as allowed by both
+    /// its access type and its PDO mapability.
+    pub(crate) fn check_pdo_mapping(
+        &self,
+        id: EntryId,
+        direction: PdoMapability,
+    ) -> Result<(), SdoAbortCode> {
+        let variable = self
+            .get_variable(id)
+            .ok_or_else(|| self.missing_entry_code(id))?;
+        let access_type = variable.access_type;
+        if direction.allows_tpdo() && !access_type.allows_reading() {
+            return Err(SdoAbortCode::WriteOnlyError);
+        }
+        if direction.allows_rpdo() && !access_type.allows_writing() {
+            return Err(SdoAbortCode::ReadOnlyError);
+        }
+        let mapability = variable.pdo_mapability;
+        if (direction.allows_tpdo() && !mapability.allows_tpdo())
+            || (direction.allows_rpdo() && !mapability.allows_rpdo())
+        {
+            return Err(SdoAbortCode::ObjectCannotBeMapped);
+        }
+        Ok(())
+    }
+
+    /// Checks that an entry exists and can be written, returning the largest
+    /// encoded size it accepts.
+    pub(crate) fn writable_size(&self, id: EntryId) -> Result<usize, SdoAbortCode> {
+        match id.index {
+            ERROR_REGISTER_INDEX => {
+                self.check_error_register_sub_index(id)?;
+                return Err(SdoAbortCode::ReadOnlyError);
+            }
+            PREDEFINED_ERROR_FIELD_INDEX => {
+                return match id.sub_index as usize {
+                    0 => Ok(1),
+                    n if n > self.predefined_errors.len() => {
+                        Err(SdoAbortCode::SubindexDoesNotExist)
+                    }
+                    _ => Err(SdoAbortCode::ReadOnlyError),
+                };
+            }
+            _ if self.is_concise_dcf(id) => return Ok(CONCISE_DCF_CAPACITY),
+            _ => {}
+        }
+        if let Some((parameter, _)) = self.pdo_parameter(id.index) {
+            return parameter.writable_size(id.sub_index);
+        }
+        let variable = self
+            .get_variable(id)
+            .ok_or_else(|| self.missing_entry_code(id))?;
+        if !variable.access_type.allows_writing() {
+            return Err(SdoAbortCode::ReadOnlyError);
+        }
+        Ok(variable.data_type.max_size())
+    }
+
+    fn missing_entry_code(&self, id: EntryId) -> SdoAbortCode {
+        if self.entries.iter().any(|v| v.id.index == id.index) {
+            SdoAbortCode::SubindexDoesNotExist
+        } else {
+            SdoAbortCode::ObjectDoesNotExist
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use heapless::Vec;
 
-    use crate::{node::NodeId, object_dictionary::ObjectDictionary};
+    use crate::{
+        node::NodeId,
+        object_dictionary::{
+            AccessType, CobId, EntryId, FixedBytes, FrameId, NodeIdRelative, ObjectDictionary,
+            PdoMapability, StorageLocation, Variable, VariableType, STRING_CAPACITY,
+        },
+        parameter_coder::{
+            DefaultF32Coder, DefaultI24Coder, DefaultI40Coder, DefaultU16Coder, DefaultU48Coder,
+            DefaultU56Coder, DefaultU8Coder,
+        },
+        pdo::{PdoConfiguration, PdoTransmissionType},
+        sdo::SdoAbortCode,
+    };
 
     #[test]
     fn it_works() {
-        let mut od = ObjectDictionary::new(
+        let _od = ObjectDictionary::new(
             0,
             0,
             [Default::default(); 8],
@@ -232,4 +1415,569 @@ mod tests {
             NodeId::default(),
         );
     }
+
+    #[test]
+    fn test_variable_builder() {
+        let variable = Variable::builder(EntryId::new(0x2000, 0x1))
+            .name("Setpoint")
+            .access(AccessType::ReadOnly)
+            .pdo(PdoMapability::Tpdo)
+            .storage(StorageLocation::NonVolatile)
+            .value(VariableType::UInt16(5, &DefaultU16Coder))
+            .unwrap();
+        assert_eq!(variable.id(), EntryId::new(0x2000, 0x1));
+        assert_eq!(variable.name(), "Setpoint");
+        assert_eq!(variable.access_type(), AccessType::ReadOnly);
+        assert_eq!(variable.pdo_mapability(), PdoMapability::Tpdo);
+        assert_eq!(variable.storage_location(), StorageLocation::NonVolatile);
+        assert!(matches!(variable.value(), VariableType::UInt16(5, _)));
+
+        let variable = Variable::builder(EntryId::new(0x2001, 0x0))
+            .value(VariableType::UInt8(1, &DefaultU8Coder))
+            .unwrap();
+        assert_eq!(variable.name(), "");
+        assert_eq!(variable.access_type(), AccessType::ReadWrite);
+        assert_eq!(variable.pdo_mapability(), PdoMapability::None);
+        assert_eq!(variable.storage_location(), StorageLocation::Ram);
+    }
+
+    #[test]
+    fn test_access_types() {
+        let variable = |index, access_type| {
+            Variable::builder(EntryId::new(index, 0))
+                .access(access_type)
+                .pdo(PdoMapability::All)
+                .value(VariableType::UInt8(0, &DefaultU8Coder))
+                .unwrap()
+        };
+        let mut od: ObjectDictionary<6, 0, 0> = ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [],
+            Vec::from_slice(&[
+                variable(0x2000, AccessType::ReadOnly),
+                variable(0x2001, AccessType::WriteOnly),
+                variable(0x2002, AccessType::ReadWrite),
+                variable(0x2003, AccessType::ReadWriteTpdo),
+                variable(0x2004, AccessType::ReadWriteRpdo),
+                variable(0x2005, AccessType::Const),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::default(),
+        );
+        let id = |index| EntryId::new(index, 0);
+
+        assert_eq!(
+            od.write_raw(id(0x2000), &[1]),
+            Err(SdoAbortCode::ReadOnlyError)
+        );
+        assert_eq!(
+            od.write_raw(id(0x2005), &[1]),
+            Err(SdoAbortCode::ReadOnlyError)
+        );
+        assert_eq!(od.read_raw(id(0x2001)), Err(SdoAbortCode::WriteOnlyError));
+        for index in [0x2002, 0x2003, 0x2004] {
+            assert_eq!(od.write_raw(id(index), &[1]), Ok(()));
+            assert_eq!(od.read_raw(id(index)).unwrap(), [1]);
+        }
+
+        let mapabilities: std::vec::Vec<_> =
+            od.variables().iter().map(|v| v.pdo_mapability()).collect();
+        assert_eq!(
+            mapabilities,
+            [
+                PdoMapability::Tpdo,
+                PdoMapability::Rpdo,
+                PdoMapability::All,
+                PdoMapability::Tpdo,
+                PdoMapability::Rpdo,
+                PdoMapability::Tpdo,
+            ]
+        );
+
+        let tpdo = |index| od.check_pdo_mapping(id(index), PdoMapability::Tpdo);
+        assert_eq!(tpdo(0x2001), Err(SdoAbortCode::WriteOnlyError));
+        assert_eq!(tpdo(0x2004), Err(SdoAbortCode::ObjectCannotBeMapped));
+        assert_eq!(tpdo(0x2003), Ok(()));
+        assert_eq!(tpdo(0x2005), Ok(()));
+        let rpdo = |index| od.check_pdo_mapping(id(index), PdoMapability::Rpdo);
+        assert_eq!(rpdo(0x2000), Err(SdoAbortCode::ReadOnlyError));
+        assert_eq!(rpdo(0x2005), Err(SdoAbortCode::ReadOnlyError));
+        assert_eq!(rpdo(0x2003), Err(SdoAbortCode::ObjectCannotBeMapped));
+        assert_eq!(rpdo(0x2004), Ok(()));
+    }
+
+    #[test]
+    fn test_pdo_mapability() {
+        let variable = |index, pdo_mapability| {
+            Variable::builder(EntryId::new(index, 0))
+                .pdo(pdo_mapability)
+                .value(VariableType::UInt8(0, &DefaultU8Coder))
+                .unwrap()
+        };
+        let od: ObjectDictionary<3, 0, 0> = ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [],
+            Vec::from_slice(&[
+                variable(0x2000, PdoMapability::None),
+                variable(0x2001, PdoMapability::Tpdo),
+                variable(0x2002, PdoMapability::Rpdo),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::default(),
+        );
+        let check = |index, direction| od.check_pdo_mapping(EntryId::new(index, 0), direction);
+
+        // Read-write variables can only be mapped in the directions they allow.
+        let cannot_be_mapped = Err(SdoAbortCode::ObjectCannotBeMapped);
+        assert_eq!(check(0x2000, PdoMapability::Tpdo), cannot_be_mapped);
+        assert_eq!(check(0x2000, PdoMapability::Rpdo), cannot_be_mapped);
+        assert_eq!(check(0x2001, PdoMapability::Tpdo), Ok(()));
+        assert_eq!(check(0x2001, PdoMapability::Rpdo), cannot_be_mapped);
+        assert_eq!(check(0x2002, PdoMapability::Tpdo), cannot_be_mapped);
+        assert_eq!(check(0x2002, PdoMapability::Rpdo), Ok(()));
+    }
+
+    #[test]
+    fn test_value_limits() {
+        let mut od: ObjectDictionary<3, 0, 0> = ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [],
+            Vec::from_slice(&[
+                Variable::builder(EntryId::new(0x2000, 0))
+                    .low_limit(VariableType::UInt16(10, &DefaultU16Coder))
+                    .high_limit(VariableType::UInt16(1000, &DefaultU16Coder))
+                    .default_value(VariableType::UInt16(100, &DefaultU16Coder))
+                    .value(VariableType::UInt16(500, &DefaultU16Coder))
+                    .unwrap(),
+                Variable::builder(EntryId::new(0x2001, 0))
+                    .name("Gain")
+                    .low_limit(VariableType::Float32(0.0, &DefaultF32Coder))
+                    .value(VariableType::Float32(1.0, &DefaultF32Coder))
+                    .unwrap(),
+                Variable::builder(EntryId::new(0x3000, 0))
+                    .value(VariableType::UInt8(7, &DefaultU8Coder))
+                    .unwrap(),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::default(),
+        );
+        let current = EntryId::new(0x2000, 0);
+        let gain = EntryId::new(0x2001, 0);
+        let other = EntryId::new(0x3000, 0);
+
+        // SDO
+        assert_eq!(
+            od.write_raw(current, &1001u16.to_le_bytes()),
+            Err(SdoAbortCode::ValueTooHigh)
+        );
+        assert_eq!(
+            od.write_raw(current, &9u16.to_le_bytes()),
+            Err(SdoAbortCode::ValueTooLow)
+        );
+        assert_eq!(
+            od.write_raw(gain, &f32::NAN.to_le_bytes()),
+            Err(SdoAbortCode::InvalidValue)
+        );
+        assert_eq!(od.write_raw(current, &1000u16.to_le_bytes()), Ok(()));
+        // RPDO
+        assert_eq!(
+            od.write_unsigned(current, 5),
+            Err(SdoAbortCode::ValueTooLow)
+        );
+        assert_eq!(od.read_unsigned(current), Some(1000));
+        // Application
+        let variable = od.get_mut_variable(current).unwrap();
+        assert_eq!(
+            variable.set_value(VariableType::UInt16(2000, &DefaultU16Coder)),
+            Err(SdoAbortCode::ValueTooHigh)
+        );
+        variable.restore_default();
+        assert_eq!(od.read_unsigned(current), Some(100));
+
+        od.write_raw(gain, &2.0f32.to_le_bytes()).unwrap();
+        od.write_raw(other, &[8]).unwrap();
+        od.restore_defaults(0x2000..0x3000);
+        assert_eq!(od.read_unsigned(gain), Some(1.0f32.to_bits() as u64));
+        assert_eq!(od.read_unsigned(other), Some(8));
+        assert_eq!(od.restore_default(other), Ok(()));
+        assert_eq!(od.read_unsigned(other), Some(7));
+        assert_eq!(
+            od.restore_default(EntryId::new(0x4000, 0)),
+            Err(SdoAbortCode::ObjectDoesNotExist)
+        );
+    }
+
+    #[test]
+    fn test_odd_width_ranges() {
+        let builder = Variable::builder(EntryId::new(0x2000, 0));
+        assert_eq!(
+            builder
+                .value(VariableType::Int24(1 << 23, &DefaultI24Coder))
+                .err(),
+            Some(SdoAbortCode::ValueTooHigh)
+        );
+        assert_eq!(
+            builder
+                .value(VariableType::Int40(-(1 << 39) - 1, &DefaultI40Coder))
+                .err(),
+            Some(SdoAbortCode::ValueTooLow)
+        );
+        assert_eq!(
+            builder
+                .default_value(VariableType::UInt56(1 << 56, &DefaultU56Coder))
+                .value(VariableType::UInt56(0, &DefaultU56Coder))
+                .err(),
+            Some(SdoAbortCode::ValueTooHigh)
+        );
+
+        let mut variable = builder
+            .value(VariableType::UInt48(0, &DefaultU48Coder))
+            .unwrap();
+        assert_eq!(
+            variable.set_value(VariableType::UInt48(1 << 48, &DefaultU48Coder)),
+            Err(SdoAbortCode::ValueTooHigh)
+        );
+        variable
+            .set_value(VariableType::UInt48((1 << 48) - 1, &DefaultU48Coder))
+            .unwrap();
+        assert!(matches!(
+            variable.value(),
+            VariableType::UInt48(value, _) if value == (1 << 48) - 1
+        ));
+    }
+
+    #[test]
+    fn test_node_id_relative() {
+        let id = EntryId::new(0x2000, 0);
+        let variable = Variable::builder(id)
+            .low_limit(VariableType::UInt16(0x105, &DefaultU16Coder))
+            .high_limit(VariableType::UInt16(0x1FF, &DefaultU16Coder))
+            .node_id_relative(NodeIdRelative {
+                low_limit: true,
+                default_value: true,
+                ..Default::default()
+            })
+            .value(VariableType::UInt16(0x185, &DefaultU16Coder))
+            .unwrap();
+        let tpdo = PdoConfiguration::new(
+            CobId::from_raw(0x185),
+            PdoTransmissionType::EventDriven,
+            0,
+            Default::default(),
+            0,
+        );
+        let mut od = ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [tpdo.with_node_id_relative_cob_id(), tpdo],
+            [],
+            Vec::<_, 1>::from_slice(&[variable]).unwrap(),
+            NodeId::new(5).unwrap(),
+        );
+        od.set_node_id(NodeId::new(9).unwrap());
+        let variable = od.get_variable(id).unwrap();
+        assert!(matches!(
+            variable.low_limit(),
+            Some(VariableType::UInt16(0x109, _))
+        ));
+        assert!(matches!(
+            variable.high_limit(),
+            Some(VariableType::UInt16(0x1FF, _))
+        ));
+        assert!(matches!(
+            variable.default_value(),
+            Some(VariableType::UInt16(0x189, _))
+        ));
+        // The values follow once restored.
+        assert_eq!(od.read_unsigned(id), Some(0x185));
+        assert_eq!(od.tpdo_configuration(0).unwrap().cob_id().raw(), 0x185);
+        od.restore_defaults(..);
+        assert_eq!(od.read_unsigned(id), Some(0x189));
+        assert_eq!(od.tpdo_configuration(0).unwrap().cob_id().raw(), 0x189);
+        assert_eq!(od.tpdo_configuration(1).unwrap().cob_id().raw(), 0x185);
+    }
+
+    #[test]
+    fn test_compact_limits() {
+        let variable = Variable::builder(EntryId::new(0x2000, 0))
+            .low_limit(VariableType::Int40(-(1 << 39), &DefaultI40Coder))
+            .default_value(VariableType::Int40(-5, &DefaultI40Coder))
+            .value(VariableType::Int40(3, &DefaultI40Coder))
+            .unwrap();
+        assert!(matches!(
+            variable.low_limit(),
+            Some(VariableType::Int40(value, _)) if value == -(1 << 39)
+        ));
+        assert!(variable.high_limit().is_none());
+        assert!(matches!(
+            variable.default_value(),
+            Some(VariableType::Int40(-5, _))
+        ));
+
+        // Limits and defaults do not take the room of a full value.
+        assert!(size_of::<Variable>() < 3 * size_of::<VariableType>());
+
+        let name = FixedBytes::from_slice(b"node").unwrap();
+        let mut variable = Variable::builder(EntryId::new(0x2001, 0))
+            .value(VariableType::VisibleString(name))
+            .unwrap();
+        assert!(variable.default_value().is_none());
+        variable
+            .set_value(VariableType::VisibleString(
+                FixedBytes::from_slice(b"renamed").unwrap(),
+            ))
+            .unwrap();
+        variable.restore_default();
+        assert!(matches!(
+            variable.value(),
+            VariableType::VisibleString(value) if value.as_slice() == b"renamed"
+        ));
+
+        let mut od = ObjectDictionary::<1, 0, 0>::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [],
+            [variable].into_iter().collect(),
+            NodeId::default(),
+        );
+        assert_eq!(
+            od.restore_default(EntryId::new(0x2001, 0)),
+            Err(SdoAbortCode::NoDataAvailable)
+        );
+    }
+
+    #[test]
+    fn test_strings() {
+        let string = |index, value| {
+            Variable::builder(EntryId::new(index, 0))
+                .value(value)
+                .unwrap()
+        };
+        let mut od: ObjectDictionary<2, 0, 0> = ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [],
+            Vec::from_slice(&[
+                string(0x2000, VariableType::VisibleString(FixedBytes::new())),
+                string(0x2001, VariableType::UnicodeString(FixedBytes::new())),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::default(),
+        );
+
+        let name = EntryId::new(0x2000, 0);
+        assert_eq!(od.check_download_size(name, 4), Ok(()));
+        assert_eq!(od.write_raw(name, b"Pump"), Ok(()));
+        assert_eq!(od.read_raw(name).unwrap().as_slice(), b"Pump");
+        assert_eq!(od.write_raw(name, b"Fan"), Ok(()));
+        assert_eq!(od.read_raw(name).unwrap().as_slice(), b"Fan");
+        assert_eq!(od.read_unsigned(name), None);
+        assert_eq!(
+            od.write_raw(name, &[b'x'; STRING_CAPACITY + 1]),
+            Err(SdoAbortCode::TooLong)
+        );
+
+        let unicode = EntryId::new(0x2001, 0);
+        assert_eq!(od.write_raw(unicode, &[b'A', 0]), Ok(()));
+        assert_eq!(
+            od.write_raw(unicode, &[b'A', 0, b'B']),
+            Err(SdoAbortCode::InvalidValue)
+        );
+    }
+
+    #[test]
+    fn test_variable_lookup() {
+        let u8_variable = |index, sub_index, name| {
+            Variable::builder(EntryId::new(index, sub_index))
+                .name(name)
+                .value(VariableType::UInt8(sub_index, &DefaultU8Coder))
+                .unwrap()
+        };
+        let mut od: ObjectDictionary<4, 0, 0> = ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [],
+            Vec::from_slice(&[
+                u8_variable(0x2001, 0, "Other"),
+                u8_variable(0x2000, 2, "Second"),
+                u8_variable(0x2000, 0, "Count"),
+                u8_variable(0x2000, 1, "First"),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::default(),
+        );
+
+        assert_eq!(
+            od.get_variable(EntryId::new(0x2000, 1)).unwrap().name(),
+            "First"
+        );
+        assert_eq!(
+            od.get_variable_by_name("Second").unwrap().id(),
+            EntryId::new(0x2000, 2)
+        );
+        assert!(od.get_variable_by_name("Missing").is_none());
+
+        let names: std::vec::Vec<_> = od
+            .get_variables_by_index(0x2000)
+            .iter()
+            .map(Variable::name)
+            .collect();
+        assert_eq!(names, ["Count", "First", "Second"]);
+        assert!(od.get_variables_by_index(0x1000).is_empty());
+
+        od.get_mut_variable_by_name("Other")
+            .unwrap()
+            .set_value(VariableType::UInt8(9, &DefaultU8Coder))
+            .unwrap();
+        assert_eq!(od.read_unsigned(EntryId::new(0x2001, 0)), Some(9));
+        assert_eq!(od.get_mut_variables_by_index(0x2001).len(), 1);
+    }
+
+    #[test]
+    fn test_cob_id_bits() {
+        let cob_id = CobId::new(true, false, FrameId::Standard(0x181));
+        assert_eq!(cob_id.raw(), 0x4000_0181);
+        assert!(cob_id.is_valid());
+        assert!(!cob_id.rtr_allowed());
+
+        let cob_id = CobId::new(false, true, FrameId::Extended(0x1234_5678));
+        assert_eq!(cob_id.raw(), 0xB234_5678);
+        assert!(!cob_id.is_valid());
+        assert!(matches!(
+            cob_id.assigned_frame_id(),
+            FrameId::Extended(0x1234_5678)
+        ));
+        assert!(!CobId::default().is_valid());
+    }
+
+    #[test]
+    fn test_error_history() {
+        let mut od = ObjectDictionary::new(
+            0x11,
+            0,
+            [0; 8],
+            [],
+            [],
+            Vec::<_, 0>::new(),
+            NodeId::default(),
+        );
+        assert_eq!(od.read_raw(EntryId::new(0x1001, 0)).unwrap(), [0x11]);
+        assert_eq!(
+            od.write_raw(EntryId::new(0x1001, 0), &[0]),
+            Err(SdoAbortCode::ReadOnlyError)
+        );
+
+        for code in 1..=9 {
+            od.push_error_history(0x1000 + code);
+        }
+        assert_eq!(od.error_history().len(), 8);
+        assert_eq!(od.error_history()[0], 0x1009);
+        assert_eq!(od.read_raw(EntryId::new(0x1003, 0)).unwrap(), [8]);
+        assert_eq!(
+            od.read_raw(EntryId::new(0x1003, 8)).unwrap(),
+            [0x02, 0x10, 0, 0]
+        );
+        assert_eq!(
+            od.read_raw(EntryId::new(0x1003, 9)),
+            Err(SdoAbortCode::SubindexDoesNotExist)
+        );
+
+        assert_eq!(
+            od.write_raw(EntryId::new(0x1003, 0), &[1]),
+            Err(SdoAbortCode::InvalidValue)
+        );
+        assert_eq!(
+            od.write_raw(EntryId::new(0x1003, 1), &[0, 0, 0, 0]),
+            Err(SdoAbortCode::ReadOnlyError)
+        );
+        od.write_raw(EntryId::new(0x1003, 0), &[0]).unwrap();
+        assert!(od.error_history().is_empty());
+        assert_eq!(
+            od.read_raw(EntryId::new(0x1003, 1)),
+            Err(SdoAbortCode::NoDataAvailable)
+        );
+    }
+
+    #[test]
+    fn test_pdo_parameters() {
+        let rpdo = PdoConfiguration::new(
+            CobId::new(true, false, FrameId::Standard(0x205)),
+            PdoTransmissionType::Synchronous(1),
+            0,
+            Default::default(),
+            0,
+        );
+        let mut od = ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [rpdo],
+            Vec::<_, 0>::new(),
+            NodeId::default(),
+        );
+        assert_eq!(od.read_raw(EntryId::new(0x1400, 0)).unwrap(), [5]);
+        assert_eq!(
+            od.read_raw(EntryId::new(0x1400, 1)).unwrap(),
+            [0x05, 0x02, 0, 0x40]
+        );
+        assert_eq!(od.read_raw(EntryId::new(0x1400, 2)).unwrap(), [1]);
+        assert_eq!(
+            od.read_raw(EntryId::new(0x1400, 4)),
+            Err(SdoAbortCode::SubindexDoesNotExist)
+        );
+        assert_eq!(
+            od.read_raw(EntryId::new(0x1401, 1)),
+            Err(SdoAbortCode::ObjectDoesNotExist)
+        );
+        assert_eq!(
+            od.write_raw(EntryId::new(0x1400, 0), &[5]),
+            Err(SdoAbortCode::ReadOnlyError)
+        );
+        assert_eq!(
+            od.write_raw(EntryId::new(0x1400, 2), &[241]),
+            Err(SdoAbortCode::InvalidValue)
+        );
+        od.write_raw(EntryId::new(0x1400, 2), &[0xFF]).unwrap();
+        assert_eq!(
+            od.rpdo_configuration(0).unwrap().transmission_type(),
+            PdoTransmissionType::EventDriven
+        );
+
+        // The CAN identifier only changes while the PDO is disabled.
+        let cob_id = |raw: u32| raw.to_le_bytes();
+        assert_eq!(
+            od.write_raw(EntryId::new(0x1400, 1), &cob_id(0x4000_0206)),
+            Err(SdoAbortCode::InvalidValue)
+        );
+        od.write_raw(EntryId::new(0x1400, 1), &cob_id(0xC000_0205))
+            .unwrap();
+        od.write_raw(EntryId::new(0x1400, 1), &cob_id(0x4000_0206))
+            .unwrap();
+        assert_eq!(
+            od.rpdo_configuration(0).unwrap().cob_id().raw(),
+            0x4000_0206
+        );
+    }
 }
@@ -1,58 +1,117 @@
 pub trait BooleanCoder {
+    #[allow(clippy::wrong_self_convention)]
     fn from_raw(&self, raw: u8) -> bool;
     fn to_raw(&self, value: bool) -> u8;
 }
 
 pub trait I8Coder {
+    #[allow(clippy::wrong_self_convention)]
     fn from_raw(&self, raw: u8) -> i8;
     fn to_raw(&self, value: i8) -> u8;
 }
 
 pub trait U8Coder {
+    #[allow(clippy::wrong_self_convention)]
     fn from_raw(&self, raw: u8) -> u8;
     fn to_raw(&self, value: u8) -> u8;
 }
 
 pub trait I16Coder {
+    #[allow(clippy::wrong_self_convention)]
     fn from_raw(&self, raw: [u8; 2]) -> i16;
     fn to_raw(&self, value: i16) -> [u8; 2];
 }
 
 pub trait U16Coder {
+    #[allow(clippy::wrong_self_convention)]
     fn from_raw(&self, raw: [u8; 2]) -> u16;
     fn to_raw(&self, value: u16) -> [u8; 2];
 }
 
 pub trait I32Coder {
+    #[allow(clippy::wrong_self_convention)]
     fn from_raw(&self, raw: [u8; 4]) -> i32;
     fn to_raw(&self, value: i32) -> [u8; 4];
 }
 
 pub trait U32Coder {
+    #[allow(clippy::wrong_self_convention)]
     fn from_raw(&self, raw: [u8; 4]) -> u32;
     fn to_raw(&self, value: u32) -> [u8; 4];
 }
 
 pub trait F32Coder {
+    #[allow(clippy::wrong_self_convention)]
     fn from_raw(&self, raw: [u8; 4]) -> f32;
     fn to_raw(&self, value: f32) -> [u8; 4];
 }
 
 pub trait F64Coder {
+    #[allow(clippy::wrong_self_convention)]
     fn from_raw(&self, raw: [u8; 8]) -> f64;
     fn to_raw(&self, value: f64) -> [u8; 8];
 }
 
 pub trait I64Coder {
+    #[allow(clippy::wrong_self_convention)]
     fn from_raw(&self, raw: [u8; 8]) -> i64;
     fn to_raw(&self, value: i64) -> [u8; 8];
 }
 
 pub trait U64Coder {
+    #[allow(clippy::wrong_self_convention)]
     fn from_raw(&self, raw: [u8; 8]) -> u64;
     fn to_raw(&self, value: u64) -> [u8; 8];
 }
 
+pub trait I24Coder {
+    #[allow(clippy::wrong_self_convention)]
+    fn from_raw(&self, raw: [u8; 3]) -> i32;
+    fn to_raw(&self, value: i32) -> [u8; 3];
+}
+
+pub trait U24Coder {
+    #[allow(clippy::wrong_self_convention)]
+    fn from_raw(&self, raw: [u8; 3]) -> u32;
+    fn to_raw(&self, value: u32) -> [u8; 3];
+}
+
+pub trait I40Coder {
+    #[allow(clippy::wrong_self_convention)]
+    fn from_raw(&self, raw: [u8; 5]) -> i64;
+    fn to_raw(&self, value: i64) -> [u8; 5];
+}
+
+pub trait U40Coder {
+    #[allow(clippy::wrong_self_convention)]
+    fn from_raw(&self, raw: [u8; 5]) -> u64;
+    fn to_raw(&self, value: u64) -> [u8; 5];
+}
+
+pub trait I48Coder {
+    #[allow(clippy::wrong_self_convention)]
+    fn from_raw(&self, raw: [u8; 6]) -> i64;
+    fn to_raw(&self, value: i64) -> [u8; 6];
+}
+
+pub trait U48Coder {
+    #[allow(clippy::wrong_self_convention)]
+    fn from_raw(&self, raw: [u8; 6]) -> u64;
+    fn to_raw(&self, value: u64) -> [u8; 6];
+}
+
+pub trait I56Coder {
+    #[allow(clippy::wrong_self_convention)]
+    fn from_raw(&self, raw: [u8; 7]) -> i64;
+    fn to_raw(&self, value: i64) -> [u8; 7];
+}
+
+pub trait U56Coder {
+    #[allow(clippy::wrong_self_convention)]
+    fn from_raw(&self, raw: [u8; 7]) -> u64;
+    fn to_raw(&self, value: u64) -> [u8; 7];
+}
+
 pub struct DefaultBooleanCoder;
 pub struct DefaultI8Coder;
 pub struct DefaultU8Coder;
@@ -64,14 +123,18 @@ pub struct DefaultF32Coder;
 pub struct DefaultI64Coder;
 pub struct DefaultU64Coder;
 pub struct DefaultF64Coder;
+pub struct DefaultI24Coder;
+pub struct DefaultU24Coder;
+pub struct DefaultI40Coder;
+pub struct DefaultU40Coder;
+pub struct DefaultI48Coder;
+pub struct DefaultU48Coder;
+pub struct DefaultI56Coder;
+pub struct DefaultU56Coder;
 
 impl BooleanCoder for DefaultBooleanCoder {
     fn from_raw(&self, raw: u8) -> bool {
-        if raw == 0x0 {
-            false
-        } else {
-            true
-        }
+        raw != 0x0
     }
 
     fn to_raw(&self, value: bool) -> u8 {
@@ -182,3 +245,123 @@ impl F64Coder for DefaultF64Coder {
         value.to_le_bytes()
     }
 }
+
+impl I24Coder for DefaultI24Coder {
+    fn from_raw(&self, raw: [u8; 3]) -> i32 {
+        let mut bytes = [0; 4];
+        bytes[..3].copy_from_slice(&raw);
+        (i32::from_le_bytes(bytes) << 8) >> 8
+    }
+
+    fn to_raw(&self, value: i32) -> [u8; 3] {
+        value.to_le_bytes()[..3].try_into().unwrap()
+    }
+}
+
+impl U24Coder for DefaultU24Coder {
+    fn from_raw(&self, raw: [u8; 3]) -> u32 {
+        let mut bytes = [0; 4];
+        bytes[..3].copy_from_slice(&raw);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn to_raw(&self, value: u32) -> [u8; 3] {
+        value.to_le_bytes()[..3].try_into().unwrap()
+    }
+}
+
+impl I40Coder for DefaultI40Coder {
+    fn from_raw(&self, raw: [u8; 5]) -> i64 {
+        let mut bytes = [0; 8];
+        bytes[..5].copy_from_slice(&raw);
+        (i64::from_le_bytes(bytes) << 24) >> 24
+    }
+
+    fn to_raw(&self, value: i64) -> [u8; 5] {
+        value.to_le_bytes()[..5].try_into().unwrap()
+    }
+}
+
+impl U40Coder for DefaultU40Coder {
+    fn from_raw(&self, raw: [u8; 5]) -> u64 {
+        let mut bytes = [0; 8];
+        bytes[..5].copy_from_slice(&raw);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn to_raw(&self, value: u64) -> [u8; 5] {
+        value.to_le_bytes()[..5].try_into().unwrap()
+    }
+}
+
+impl I48Coder for DefaultI48Coder {
+    fn from_raw(&self, raw: [u8; 6]) -> i64 {
+        let mut bytes = [0; 8];
+        bytes[..6].copy_from_slice(&raw);
+        (i64::from_le_bytes(bytes) << 16) >> 16
+    }
+
+    fn to_raw(&self, value: i64) -> [u8; 6] {
+        value.to_le_bytes()[..6].try_into().unwrap()
+    }
+}
+
+impl U48Coder for DefaultU48Coder {
+    fn from_raw(&self, raw: [u8; 6]) -> u64 {
+        let mut bytes = [0; 8];
+        bytes[..6].copy_from_slice(&raw);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn to_raw(&self, value: u64) -> [u8; 6] {
+        value.to_le_bytes()[..6].try_into().unwrap()
+    }
+}
+
+impl I56Coder for DefaultI56Coder {
+    fn from_raw(&self, raw: [u8; 7]) -> i64 {
+        let mut bytes = [0; 8];
+        bytes[..7].copy_from_slice(&raw);
+        (i64::from_le_bytes(bytes) << 8) >> 8
+    }
+
+    fn to_raw(&self, value: i64) -> [u8; 7] {
+        value.to_le_bytes()[..7].try_into().unwrap()
+    }
+}
+
+impl U56Coder for DefaultU56Coder {
+    fn from_raw(&self, raw: [u8; 7]) -> u64 {
+        let mut bytes = [0; 8];
+        bytes[..7].copy_from_slice(&raw);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn to_raw(&self, value: u64) -> [u8; 7] {
+        value.to_le_bytes()[..7].try_into().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_odd_width_coders() {
+        assert_eq!(DefaultI24Coder.to_raw(-2), [0xFE, 0xFF, 0xFF]);
+        assert_eq!(DefaultI24Coder.from_raw([0xFE, 0xFF, 0xFF]), -2);
+        assert_eq!(DefaultI24Coder.from_raw([0xFF, 0xFF, 0x7F]), 0x7F_FFFF);
+        assert_eq!(DefaultU24Coder.from_raw([0xFE, 0xFF, 0xFF]), 0xFF_FFFE);
+        assert_eq!(DefaultI40Coder.from_raw([0, 0, 0, 0, 0x80]), -(1 << 39));
+        assert_eq!(
+            DefaultI48Coder.to_raw(-0x1234),
+            [0xCC, 0xED, 0xFF, 0xFF, 0xFF, 0xFF]
+        );
+        assert_eq!(
+            DefaultU48Coder.from_raw([1, 2, 3, 4, 5, 6]),
+            0x0605_0403_0201
+        );
+        assert_eq!(DefaultI56Coder.from_raw([0xFF; 7]), -1);
+        assert_eq!(DefaultU56Coder.to_raw(u64::MAX), [0xFF; 7]);
+    }
+}
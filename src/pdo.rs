@@ -1,12 +1,84 @@
-use crate::object_dictionary::CobId;
+use heapless::Vec;
 
-#[derive(Clone, Copy)]
+use crate::{
+    node::NodeId,
+    object_dictionary::{CobId, EntryId, ObjectDictionary, PdoMapability},
+    sdo::SdoAbortCode,
+};
+
+pub mod rpdo;
+pub mod tpdo;
+
+pub use rpdo::{RpdoConsumer, RpdoError};
+pub use tpdo::{TpdoError, TpdoProducer};
+
+pub(crate) const RPDO_COMMUNICATION_INDEX: u16 = 0x1400;
+pub(crate) const RPDO_MAPPING_INDEX: u16 = 0x1600;
+pub(crate) const TPDO_COMMUNICATION_INDEX: u16 = 0x1800;
+pub(crate) const TPDO_MAPPING_INDEX: u16 = 0x1A00;
+
+/// Transmission type of event-driven PDOs in the communication parameter.
+/// 0xFF is accepted as well when written.
+pub(crate) const EVENT_DRIVEN: u8 = 0xFE;
+
+/// Highest sub-index of the communication parameters: COB-ID, transmission
+/// type, inhibit time, a reserved sub-index and event timer.
+const COMMUNICATION_SUB_INDEX_COUNT: u8 = 5;
+
+/// The communication or mapping parameter of a PDO, from 0x1400 to 0x1BFF.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct PdoParameter {
+    /// [`PdoMapability::Rpdo`] or [`PdoMapability::Tpdo`].
+    pub(crate) direction: PdoMapability,
+    pub(crate) pdo: usize,
+    pub(crate) mapping: bool,
+}
+
+impl PdoParameter {
+    pub(crate) fn from_index(index: u16) -> Option<Self> {
+        let (direction, first_index, mapping) = match index {
+            0x1400..=0x15FF => (PdoMapability::Rpdo, RPDO_COMMUNICATION_INDEX, false),
+            0x1600..=0x17FF => (PdoMapability::Rpdo, RPDO_MAPPING_INDEX, true),
+            0x1800..=0x19FF => (PdoMapability::Tpdo, TPDO_COMMUNICATION_INDEX, false),
+            0x1A00..=0x1BFF => (PdoMapability::Tpdo, TPDO_MAPPING_INDEX, true),
+            _ => return None,
+        };
+        Some(Self {
+            direction,
+            pdo: (index - first_index) as usize,
+            mapping,
+        })
+    }
+
+    /// Size in bytes of a sub-index.
+    fn size(&self, sub_index: u8) -> Result<usize, SdoAbortCode> {
+        match (self.mapping, sub_index) {
+            (_, 0) | (false, 2) => Ok(1),
+            (false, 1) | (true, 1..=8) => Ok(4),
+            (false, 3 | 5) => Ok(2),
+            _ => Err(SdoAbortCode::SubindexDoesNotExist),
+        }
+    }
+
+    /// Checks that a sub-index can be written, returning its size.
+    pub(crate) fn writable_size(&self, sub_index: u8) -> Result<usize, SdoAbortCode> {
+        let size = self.size(sub_index)?;
+        if !self.mapping && sub_index == 0 {
+            return Err(SdoAbortCode::ReadOnlyError);
+        }
+        Ok(size)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PdoTransmissionType {
-    Synchronous,
+    /// Sent on every n-th SYNC. With 0, a triggered PDO is sent on the next
+    /// SYNC.
+    Synchronous(u8),
     EventDriven,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Default)]
 #[repr(C)]
 pub struct PdoEntryMapping {
     index: u16,
@@ -14,13 +86,53 @@ pub struct PdoEntryMapping {
     length: u8,
 }
 
-impl Default for PdoEntryMapping {
-    fn default() -> Self {
+impl PdoEntryMapping {
+    /// Maps the `length` least significant bits of an entry.
+    pub fn new(index: u16, sub_index: u8, length: u8) -> Self {
         Self {
-            index: 0x0,
-            sub_index: 0x0,
-            length: 0,
+            index,
+            sub_index,
+            length,
+        }
+    }
+
+    /// Decodes a mapping as written in the mapping parameter.
+    pub fn from_raw(raw: u32) -> Self {
+        Self::new((raw >> 16) as u16, (raw >> 8) as u8, raw as u8)
+    }
+
+    /// Encodes the mapping as written in the mapping parameter.
+    pub fn raw(&self) -> u32 {
+        (self.index as u32) << 16 | (self.sub_index as u32) << 8 | self.length as u32
+    }
+
+    pub fn entry_id(&self) -> EntryId {
+        EntryId::new(self.index, self.sub_index)
+    }
+
+    /// Checks that the mapped entry can be mapped into a PDO of `direction`,
+    /// and is at least `length` bits long.
+    fn check<const ENTRY_COUNT: usize, const RPDO_COUNT: usize, const TPDO_COUNT: usize>(
+        &self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        direction: PdoMapability,
+    ) -> Result<(), SdoAbortCode> {
+        od.check_pdo_mapping(self.entry_id(), direction)
+            .map_err(|_| SdoAbortCode::ObjectCannotBeMapped)?;
+        let size = od
+            .read_unsigned(self.entry_id())
+            .and_then(|_| od.get_variable(self.entry_id()))
+            .map(|variable| variable.raw_size())
+            .ok_or(SdoAbortCode::ObjectCannotBeMapped)?;
+        if self.length == 0 || self.length as usize > size * 8 {
+            return Err(SdoAbortCode::ObjectCannotBeMapped);
         }
+        Ok(())
+    }
+
+    /// Length of the mapped value, in bits.
+    pub fn length(&self) -> u8 {
+        self.length
     }
 }
 
@@ -31,6 +143,8 @@ pub struct PdoConfiguration {
     number_of_map_values: u8,
     entry_mapping: [PdoEntryMapping; 8],
     event_timer_ms: u16,
+    inhibit_time_100us: u16,
+    cob_id_node_id_relative: bool,
 }
 
 impl PdoConfiguration {
@@ -43,12 +157,256 @@ impl PdoConfiguration {
     ) -> Self {
         Self {
             cob_id: id,
-            transmission_type: transmission_type,
+            transmission_type,
             number_of_map_values: mapped_val_count,
-            entry_mapping: entry_mapping,
-            event_timer_ms: event_timer_ms,
+            entry_mapping,
+            event_timer_ms,
+            inhibit_time_100us: 0,
+            cob_id_node_id_relative: false,
+        }
+    }
+
+    /// Sets the minimum time between two transmissions, in multiples of
+    /// 100µs.
+    pub fn with_inhibit_time(mut self, inhibit_time_100us: u16) -> Self {
+        self.inhibit_time_100us = inhibit_time_100us;
+        self
+    }
+
+    /// Marks the COB-ID as relative to the node-ID, like `$NODEID+0x180` in
+    /// an EDS. It is given for the node-ID the object dictionary is built
+    /// with, and follows it when it changes, e.g. through LSS.
+    pub fn with_node_id_relative_cob_id(mut self) -> Self {
+        self.cob_id_node_id_relative = true;
+        self
+    }
+
+    pub fn is_cob_id_node_id_relative(&self) -> bool {
+        self.cob_id_node_id_relative
+    }
+
+    /// Moves a COB-ID relative to the node-ID from the node-ID `from` to
+    /// `to`.
+    pub(crate) fn change_node_id(&mut self, from: NodeId, to: NodeId) {
+        if self.cob_id_node_id_relative {
+            let offset = to.raw() as i32 - from.raw() as i32;
+            self.cob_id = CobId::from_raw(self.cob_id.raw().wrapping_add_signed(offset));
         }
     }
+
+    pub fn cob_id(&self) -> CobId {
+        self.cob_id
+    }
+
+    pub fn transmission_type(&self) -> PdoTransmissionType {
+        self.transmission_type
+    }
+
+    pub fn mappings(&self) -> &[PdoEntryMapping] {
+        let count = (self.number_of_map_values as usize).min(self.entry_mapping.len());
+        &self.entry_mapping[..count]
+    }
+
+    pub fn event_timer_ms(&self) -> u16 {
+        self.event_timer_ms
+    }
+
+    pub fn inhibit_time_100us(&self) -> u16 {
+        self.inhibit_time_100us
+    }
+
+    /// Number of data bytes of the PDO, or `None` if the mapped values do
+    /// not fit in a frame.
+    pub fn data_length(&self) -> Option<usize> {
+        let bits: usize = self.mappings().iter().map(|m| m.length as usize).sum();
+        if bits > 64 {
+            return None;
+        }
+        Some(bits.div_ceil(8))
+    }
+
+    /// Reads a sub-index of the communication or mapping parameter.
+    pub(crate) fn read_parameter(
+        &self,
+        parameter: PdoParameter,
+        sub_index: u8,
+    ) -> Result<Vec<u8, 4>, SdoAbortCode> {
+        let size = parameter.size(sub_index)?;
+        let value = match (parameter.mapping, sub_index) {
+            (false, 0) => COMMUNICATION_SUB_INDEX_COUNT as u32,
+            (false, 1) => self.cob_id.raw(),
+            (false, 2) => match self.transmission_type {
+                PdoTransmissionType::Synchronous(every_nth_sync) => every_nth_sync as u32,
+                PdoTransmissionType::EventDriven => EVENT_DRIVEN as u32,
+            },
+            (false, 3) => self.inhibit_time_100us as u32,
+            (false, _) => self.event_timer_ms as u32,
+            (true, 0) => self.number_of_map_values as u32,
+            (true, n) => self.entry_mapping[n as usize - 1].raw(),
+        };
+        Ok(Vec::from_slice(&value.to_le_bytes()[..size]).unwrap())
+    }
+
+    /// Checks that [`Self::write_parameter`] would accept a value.
+    ///
+    /// As required by CiA 301, the COB-ID and inhibit time can only be
+    /// changed while the PDO is disabled, and the mapping while the PDO is
+    /// disabled and sub-index 0 of its mapping parameter is 0. The mapped
+    /// entries are checked when written, and again when sub-index 0 enables
+    /// them.
+    pub(crate) fn check_parameter<
+        const ENTRY_COUNT: usize,
+        const RPDO_COUNT: usize,
+        const TPDO_COUNT: usize,
+    >(
+        &self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        parameter: PdoParameter,
+        sub_index: u8,
+        value: u32,
+    ) -> Result<(), SdoAbortCode> {
+        let enabled = self.cob_id.is_valid();
+        match (parameter.mapping, sub_index) {
+            (false, 1) => {
+                // Bits 0 to 29 hold the CAN identifier.
+                let cob_id = CobId::from_raw(value);
+                let can_id_changed = (cob_id.raw() ^ self.cob_id.raw()) & 0x3FFF_FFFF != 0;
+                if enabled && cob_id.is_valid() && can_id_changed {
+                    return Err(SdoAbortCode::InvalidValue);
+                }
+            }
+            (false, 2) => {
+                if !matches!(value, 0..=240 | 0xFE | 0xFF) {
+                    return Err(SdoAbortCode::InvalidValue);
+                }
+            }
+            (false, 3) => {
+                if enabled && value != self.inhibit_time_100us as u32 {
+                    return Err(SdoAbortCode::InvalidValue);
+                }
+            }
+            (false, _) => {}
+            (true, 0) => {
+                if enabled {
+                    return Err(SdoAbortCode::UnsupportedAccess);
+                }
+                let mappings = self
+                    .entry_mapping
+                    .get(..value as usize)
+                    .ok_or(SdoAbortCode::PDOOverflow)?;
+                for mapping in mappings {
+                    mapping.check(od, parameter.direction)?;
+                }
+                let bits: u32 = mappings.iter().map(|m| m.length as u32).sum();
+                if bits > 64 {
+                    return Err(SdoAbortCode::PDOOverflow);
+                }
+            }
+            (true, _) => {
+                if enabled || self.number_of_map_values != 0 {
+                    return Err(SdoAbortCode::UnsupportedAccess);
+                }
+                // Unused mappings can be cleared.
+                if value != 0 {
+                    PdoEntryMapping::from_raw(value).check(od, parameter.direction)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a sub-index of the communication or mapping parameter, checked
+    /// with [`Self::check_parameter`] beforehand.
+    pub(crate) fn write_parameter(&mut self, parameter: PdoParameter, sub_index: u8, value: u32) {
+        match (parameter.mapping, sub_index) {
+            (false, 1) => self.cob_id = CobId::from_raw(value),
+            (false, 2) => {
+                self.transmission_type = match value {
+                    0..=240 => PdoTransmissionType::Synchronous(value as u8),
+                    _ => PdoTransmissionType::EventDriven,
+                }
+            }
+            (false, 3) => self.inhibit_time_100us = value as u16,
+            (false, _) => self.event_timer_ms = value as u16,
+            (true, 0) => self.number_of_map_values = value as u8,
+            (true, n) => self.entry_mapping[n as usize - 1] = PdoEntryMapping::from_raw(value),
+        }
+    }
+
+    /// Bit-packs the mapped values, least significant bit first. Fails with
+    /// the first entry that cannot be mapped or read.
+    pub(crate) fn pack<
+        const ENTRY_COUNT: usize,
+        const RPDO_COUNT: usize,
+        const TPDO_COUNT: usize,
+    >(
+        &self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+    ) -> Result<Vec<u8, 8>, (EntryId, SdoAbortCode)> {
+        let mut packed = 0u64;
+        let mut offset = 0;
+        for mapping in self.mappings() {
+            let id = mapping.entry_id();
+            if offset + mapping.length as u32 > 64 {
+                return Err((id, SdoAbortCode::PDOOverflow));
+            }
+            od.check_pdo_mapping(id, PdoMapability::Tpdo)
+                .map_err(|code| (id, code))?;
+            let value = od
+                .read_unsigned(id)
+                .ok_or((id, SdoAbortCode::UnsupportedAccess))?;
+            packed |= (value & bit_mask(mapping.length))
+                .checked_shl(offset)
+                .unwrap_or(0);
+            offset += mapping.length as u32;
+        }
+        let length = (offset as usize).div_ceil(8);
+        Ok(Vec::from_slice(&packed.to_le_bytes()[..length]).unwrap())
+    }
+
+    /// Unpacks bit-packed values into the mapped entries, or into none of
+    /// them if one cannot be written.
+    pub(crate) fn unpack<
+        const ENTRY_COUNT: usize,
+        const RPDO_COUNT: usize,
+        const TPDO_COUNT: usize,
+    >(
+        &self,
+        od: &mut ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        data: &[u8],
+    ) -> Result<(), (EntryId, SdoAbortCode)> {
+        for (id, value) in self.unpacked(data) {
+            od.check_pdo_mapping(id, PdoMapability::Rpdo)
+                .and_then(|()| od.check_unsigned(id, value))
+                .map_err(|code| (id, code))?;
+        }
+        for (id, value) in self.unpacked(data) {
+            od.write_unsigned(id, value)
+                .expect("mappings are checked before being written");
+        }
+        Ok(())
+    }
+
+    /// Splits bit-packed values into the mapped entries and their values.
+    fn unpacked(&self, data: &[u8]) -> impl Iterator<Item = (EntryId, u64)> + '_ {
+        let mut raw = [0u8; 8];
+        let length = data.len().min(8);
+        raw[..length].copy_from_slice(&data[..length]);
+        let packed = u64::from_le_bytes(raw);
+
+        self.mappings().iter().scan(0u32, move |offset, mapping| {
+            let value = packed.checked_shr(*offset).unwrap_or(0) & bit_mask(mapping.length);
+            *offset += mapping.length as u32;
+            Some((mapping.entry_id(), value))
+        })
+    }
+}
+
+fn bit_mask(length: u8) -> u64 {
+    match length {
+        64.. => u64::MAX,
+        length => (1 << length) - 1,
+    }
 }
 
 impl Default for PdoConfiguration {
@@ -59,6 +417,8 @@ impl Default for PdoConfiguration {
             number_of_map_values: 0,
             entry_mapping: Default::default(),
             event_timer_ms: 0,
+            inhibit_time_100us: 0,
+            cob_id_node_id_relative: false,
         }
     }
 }
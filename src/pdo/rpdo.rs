@@ -0,0 +1,223 @@
+use embedded_can::Frame;
+use heapless::Vec;
+
+use crate::{
+    object_dictionary::{EntryId, ObjectDictionary},
+    sdo::SdoAbortCode,
+};
+
+use super::PdoTransmissionType;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RpdoError {
+    /// The PDO carried fewer bytes than its mapping requires.
+    TooShort { pdo: usize },
+    /// A mapped entry could not be written.
+    Write {
+        pdo: usize,
+        entry: EntryId,
+        code: SdoAbortCode,
+    },
+}
+
+/// Writes the RPDOs configured in an [`ObjectDictionary`] into their mapped
+/// entries.
+///
+/// Event-driven RPDOs are applied on reception, synchronous ones are buffered
+/// and applied on the next SYNC. A newer RPDO received before the SYNC replaces
+/// the buffered one.
+pub struct RpdoConsumer<const RPDO_COUNT: usize> {
+    buffered: [Option<Vec<u8, 8>>; RPDO_COUNT],
+}
+
+impl<const RPDO_COUNT: usize> Default for RpdoConsumer<RPDO_COUNT> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const RPDO_COUNT: usize> RpdoConsumer<RPDO_COUNT> {
+    pub fn new() -> Self {
+        Self {
+            buffered: [const { None }; RPDO_COUNT],
+        }
+    }
+
+    /// Drops the buffered synchronous RPDOs.
+    pub fn reset(&mut self) {
+        self.buffered = [const { None }; RPDO_COUNT];
+    }
+
+    /// Handles a frame, returning `None` if it is not an RPDO.
+    pub fn process_frame<const ENTRY_COUNT: usize, const TPDO_COUNT: usize>(
+        &mut self,
+        od: &mut ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        frame: &impl Frame,
+    ) -> Option<Result<(), RpdoError>> {
+        if frame.is_remote_frame() {
+            return None;
+        }
+        let pdo = (0..RPDO_COUNT).find(|pdo| {
+            let cob_id = od.rpdo_configuration(*pdo).unwrap().cob_id();
+            cob_id.is_valid() && cob_id.can_id() == frame.id()
+        })?;
+        let config = *od.rpdo_configuration(pdo).unwrap();
+
+        match config.data_length() {
+            Some(length) if frame.dlc() >= length => {}
+            _ => return Some(Err(RpdoError::TooShort { pdo })),
+        }
+
+        Some(match config.transmission_type() {
+            PdoTransmissionType::Synchronous(_) => {
+                self.buffered[pdo] = Vec::from_slice(frame.data()).ok();
+                Ok(())
+            }
+            PdoTransmissionType::EventDriven => config
+                .unpack(od, frame.data())
+                .map_err(|(entry, code)| RpdoError::Write { pdo, entry, code }),
+        })
+    }
+
+    /// Applies the buffered synchronous RPDOs on reception of a SYNC,
+    /// returning the last error encountered.
+    pub fn process_sync<const ENTRY_COUNT: usize, const TPDO_COUNT: usize>(
+        &mut self,
+        od: &mut ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+    ) -> Result<(), RpdoError> {
+        let mut result = Ok(());
+        for (pdo, buffered) in self.buffered.iter_mut().enumerate() {
+            let Some(data) = buffered.take() else {
+                continue;
+            };
+            let config = *od.rpdo_configuration(pdo).unwrap();
+            if let Err((entry, code)) = config.unpack(od, &data) {
+                result = Err(RpdoError::Write { pdo, entry, code });
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use heapless::Vec;
+
+    use crate::frame::EncodedCANOpenFrame;
+    use crate::node::NodeId;
+    use crate::object_dictionary::{
+        AccessType, CobId, EntryId, FrameId, ObjectDictionary, PdoMapability, Variable,
+        VariableType,
+    };
+    use crate::parameter_coder::{DefaultI16Coder, DefaultU8Coder};
+    use crate::pdo::{PdoConfiguration, PdoEntryMapping, PdoTransmissionType};
+    use crate::sdo::SdoAbortCode;
+
+    use super::{RpdoConsumer, RpdoError};
+
+    fn test_od(value_access: AccessType) -> ObjectDictionary<2, 2, 0> {
+        let mut mappings = [PdoEntryMapping::default(); 8];
+        mappings[0] = PdoEntryMapping::new(0x2000, 0, 4);
+        mappings[1] = PdoEntryMapping::new(0x2001, 0, 16);
+        let rpdo = |cob_id, transmission_type| {
+            PdoConfiguration::new(
+                CobId::new(true, false, FrameId::Standard(cob_id)),
+                transmission_type,
+                2,
+                mappings,
+                0,
+            )
+        };
+        ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [
+                rpdo(0x201, PdoTransmissionType::EventDriven),
+                rpdo(0x301, PdoTransmissionType::Synchronous(1)),
+            ],
+            Vec::from_slice(&[
+                Variable::builder(EntryId::new(0x2000, 0))
+                    .name("nibble")
+                    .pdo(PdoMapability::Rpdo)
+                    .value(VariableType::UInt8(0, &DefaultU8Coder))
+                    .unwrap(),
+                Variable::builder(EntryId::new(0x2001, 0))
+                    .name("value")
+                    .access(value_access)
+                    .pdo(PdoMapability::All)
+                    .value(VariableType::Int16(0, &DefaultI16Coder))
+                    .unwrap(),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::new(1).unwrap(),
+        )
+    }
+
+    fn read(od: &ObjectDictionary<2, 2, 0>, index: u16) -> u64 {
+        od.read_unsigned(EntryId::new(index, 0)).unwrap()
+    }
+
+    #[test]
+    fn test_event_driven_rpdo() {
+        let mut od = test_od(AccessType::ReadWrite);
+        let mut consumer = RpdoConsumer::new();
+
+        // 0xA in the low nibble, then 0xFFFE.
+        let frame = EncodedCANOpenFrame::new(0x201, &[0xEA, 0xFF, 0x0F]);
+        assert_eq!(consumer.process_frame(&mut od, &frame), Some(Ok(())));
+        assert_eq!(read(&od, 0x2000), 0x0A);
+        assert_eq!(read(&od, 0x2001), 0xFFFE);
+
+        let other = EncodedCANOpenFrame::new(0x202, &[0xEA, 0xFF, 0x0F]);
+        assert_eq!(consumer.process_frame(&mut od, &other), None);
+    }
+
+    #[test]
+    fn test_synchronous_rpdo() {
+        let mut od = test_od(AccessType::ReadWrite);
+        let mut consumer = RpdoConsumer::new();
+
+        let frame = EncodedCANOpenFrame::new(0x301, &[0x13, 0x00, 0x00]);
+        assert_eq!(consumer.process_frame(&mut od, &frame), Some(Ok(())));
+        assert_eq!(read(&od, 0x2000), 0);
+
+        assert_eq!(consumer.process_sync(&mut od), Ok(()));
+        assert_eq!(read(&od, 0x2000), 0x03);
+        assert_eq!(read(&od, 0x2001), 0x0001);
+    }
+
+    #[test]
+    fn test_length_error() {
+        let mut od = test_od(AccessType::ReadWrite);
+        let mut consumer = RpdoConsumer::new();
+
+        let frame = EncodedCANOpenFrame::new(0x201, &[0xEA, 0xFF]);
+        assert_eq!(
+            consumer.process_frame(&mut od, &frame),
+            Some(Err(RpdoError::TooShort { pdo: 0 }))
+        );
+        assert_eq!(read(&od, 0x2001), 0);
+    }
+
+    #[test]
+    fn test_access_error() {
+        let mut od = test_od(AccessType::ReadWriteTpdo);
+        let mut consumer = RpdoConsumer::new();
+
+        let frame = EncodedCANOpenFrame::new(0x201, &[0xEA, 0xFF, 0x0F]);
+        assert_eq!(
+            consumer.process_frame(&mut od, &frame),
+            Some(Err(RpdoError::Write {
+                pdo: 0,
+                entry: EntryId::new(0x2001, 0),
+                code: SdoAbortCode::ObjectCannotBeMapped,
+            }))
+        );
+        // The entries mapped before the failing one are left untouched too.
+        assert_eq!(read(&od, 0x2000), 0);
+        assert_eq!(read(&od, 0x2001), 0);
+    }
+}
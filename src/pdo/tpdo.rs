@@ -0,0 +1,389 @@
+use core::time::Duration;
+
+use crate::{
+    frame::EncodedCANOpenFrame,
+    object_dictionary::{EntryId, ObjectDictionary},
+    sdo::SdoAbortCode,
+    time::Instant,
+};
+
+use super::{PdoConfiguration, PdoTransmissionType};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TpdoError {
+    /// A mapped entry could not be read, so the PDO was not sent.
+    Read {
+        pdo: usize,
+        entry: EntryId,
+        code: SdoAbortCode,
+    },
+}
+
+#[derive(Copy, Clone, Default)]
+struct TpdoState {
+    triggered: bool,
+    last_sent: Option<Instant>,
+    next_event: Option<Instant>,
+    sync_count: u8,
+}
+
+/// Produces the TPDOs configured in an [`ObjectDictionary`].
+///
+/// Event-driven TPDOs are sent when triggered by the application or when their
+/// event timer elapses, no sooner than their inhibit time after the previous
+/// transmission. Synchronous TPDOs are sent on SYNC.
+pub struct TpdoProducer<const TPDO_COUNT: usize> {
+    states: [TpdoState; TPDO_COUNT],
+}
+
+impl<const TPDO_COUNT: usize> Default for TpdoProducer<TPDO_COUNT> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const TPDO_COUNT: usize> TpdoProducer<TPDO_COUNT> {
+    pub fn new() -> Self {
+        Self {
+            states: [TpdoState::default(); TPDO_COUNT],
+        }
+    }
+
+    /// Restarts the event timers and SYNC counters.
+    pub fn reset(&mut self) {
+        self.states = [TpdoState::default(); TPDO_COUNT];
+    }
+
+    /// Requests the transmission of an event-driven or acyclic synchronous
+    /// TPDO, e.g. because one of its mapped values changed.
+    pub fn trigger(&mut self, pdo: usize) {
+        if let Some(state) = self.states.get_mut(pdo) {
+            state.triggered = true;
+        }
+    }
+
+    /// Sends the event-driven TPDOs due at `now`, returning the last error
+    /// encountered.
+    pub fn tick<const ENTRY_COUNT: usize, const RPDO_COUNT: usize>(
+        &mut self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        now: Instant,
+        mut transmit: impl FnMut(EncodedCANOpenFrame),
+    ) -> Result<(), TpdoError> {
+        let mut result = Ok(());
+        for (pdo, state) in self.states.iter_mut().enumerate() {
+            let config = od.tpdo_configuration(pdo).unwrap();
+            if !config.cob_id().is_valid()
+                || config.transmission_type() != PdoTransmissionType::EventDriven
+            {
+                continue;
+            }
+
+            let event_timer = Duration::from_millis(config.event_timer_ms() as u64);
+            if event_timer.is_zero() {
+                state.next_event = None;
+            } else {
+                match state.next_event {
+                    None => state.next_event = Some(now + event_timer),
+                    Some(next) if now >= next => state.triggered = true,
+                    Some(_) => {}
+                }
+            }
+
+            let inhibit_time = Duration::from_micros(config.inhibit_time_100us() as u64 * 100);
+            let inhibited = state
+                .last_sent
+                .is_some_and(|last_sent| now < last_sent + inhibit_time);
+            if state.triggered && !inhibited {
+                state.triggered = false;
+                state.last_sent = Some(now);
+                if !event_timer.is_zero() {
+                    state.next_event = Some(now + event_timer);
+                }
+                match Self::encode(od, pdo, config) {
+                    Ok(frame) => transmit(frame),
+                    Err(error) => result = Err(error),
+                }
+            }
+        }
+        result
+    }
+
+    /// Sends the synchronous TPDOs due on reception of a SYNC, returning the
+    /// last error encountered.
+    pub fn process_sync<const ENTRY_COUNT: usize, const RPDO_COUNT: usize>(
+        &mut self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        mut transmit: impl FnMut(EncodedCANOpenFrame),
+    ) -> Result<(), TpdoError> {
+        let mut result = Ok(());
+        for (pdo, state) in self.states.iter_mut().enumerate() {
+            let config = od.tpdo_configuration(pdo).unwrap();
+            let PdoTransmissionType::Synchronous(every_nth_sync) = config.transmission_type()
+            else {
+                continue;
+            };
+            if !config.cob_id().is_valid() {
+                continue;
+            }
+
+            let due = if every_nth_sync == 0 {
+                core::mem::take(&mut state.triggered)
+            } else {
+                state.sync_count += 1;
+                if state.sync_count >= every_nth_sync {
+                    state.sync_count = 0;
+                    true
+                } else {
+                    false
+                }
+            };
+            if due {
+                match Self::encode(od, pdo, config) {
+                    Ok(frame) => transmit(frame),
+                    Err(error) => result = Err(error),
+                }
+            }
+        }
+        result
+    }
+
+    fn encode<const ENTRY_COUNT: usize, const RPDO_COUNT: usize>(
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        pdo: usize,
+        config: &PdoConfiguration,
+    ) -> Result<EncodedCANOpenFrame, TpdoError> {
+        let data = config
+            .pack(od)
+            .map_err(|(entry, code)| TpdoError::Read { pdo, entry, code })?;
+        Ok(EncodedCANOpenFrame::from_vec_data(
+            config.cob_id().can_id(),
+            data,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_can::Frame;
+    use heapless::Vec;
+
+    use crate::frame::EncodedCANOpenFrame;
+    use crate::node::NodeId;
+    use crate::object_dictionary::{
+        CobId, EntryId, FrameId, ObjectDictionary, PdoMapability, Variable, VariableType,
+    };
+    use crate::parameter_coder::{
+        DefaultBooleanCoder, DefaultI16Coder, DefaultI40Coder, DefaultU24Coder, DefaultU8Coder,
+    };
+    use crate::pdo::{PdoConfiguration, PdoEntryMapping, PdoTransmissionType};
+    use crate::sdo::SdoAbortCode;
+    use crate::time::Instant;
+
+    use super::{TpdoError, TpdoProducer};
+
+    fn test_od(transmission_types: [PdoTransmissionType; 2]) -> ObjectDictionary<3, 0, 2> {
+        let mut mappings = [PdoEntryMapping::default(); 8];
+        mappings[0] = PdoEntryMapping::new(0x2000, 0, 1);
+        mappings[1] = PdoEntryMapping::new(0x2001, 0, 4);
+        mappings[2] = PdoEntryMapping::new(0x2002, 0, 16);
+        let tpdo = |cob_id, transmission_type| {
+            PdoConfiguration::new(
+                CobId::new(true, false, FrameId::Standard(cob_id)),
+                transmission_type,
+                3,
+                mappings,
+                100,
+            )
+            .with_inhibit_time(300)
+        };
+        ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [
+                tpdo(0x181, transmission_types[0]),
+                tpdo(0x281, transmission_types[1]),
+            ],
+            [],
+            Vec::from_slice(&[
+                Variable::builder(EntryId::new(0x2000, 0))
+                    .name("flag")
+                    .pdo(PdoMapability::Tpdo)
+                    .value(VariableType::Boolean(true, &DefaultBooleanCoder))
+                    .unwrap(),
+                Variable::builder(EntryId::new(0x2001, 0))
+                    .name("nibble")
+                    .pdo(PdoMapability::Tpdo)
+                    .value(VariableType::UInt8(0xFA, &DefaultU8Coder))
+                    .unwrap(),
+                Variable::builder(EntryId::new(0x2002, 0))
+                    .name("value")
+                    .pdo(PdoMapability::Tpdo)
+                    .value(VariableType::Int16(-2, &DefaultI16Coder))
+                    .unwrap(),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::new(1).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_bit_packing() {
+        let od = test_od([PdoTransmissionType::EventDriven; 2]);
+        let data = od.tpdo_configuration(0).unwrap().pack(&od).unwrap();
+        // 1 bit flag, 4 bits of 0xFA, then 0xFFFE.
+        assert_eq!(data, [0b1101_0101, 0xFF, 0b0001_1111]);
+    }
+
+    #[test]
+    fn test_unreadable_mapping() {
+        let mut od = test_od([PdoTransmissionType::EventDriven; 2]);
+        let mut mappings = [PdoEntryMapping::default(); 8];
+        mappings[0] = PdoEntryMapping::new(0x2000, 0, 1);
+        mappings[1] = PdoEntryMapping::new(0x2003, 0, 8);
+        *od.tpdo_configuration_mut(0).unwrap() = PdoConfiguration::new(
+            CobId::new(true, false, FrameId::Standard(0x181)),
+            PdoTransmissionType::EventDriven,
+            2,
+            mappings,
+            0,
+        );
+        let missing = (EntryId::new(0x2003, 0), SdoAbortCode::ObjectDoesNotExist);
+        assert_eq!(od.tpdo_configuration(0).unwrap().pack(&od), Err(missing));
+
+        let mut producer = TpdoProducer::new();
+        let mut frames = std::vec::Vec::new();
+        producer.trigger(0);
+        assert_eq!(
+            producer.tick(&od, Instant::from_millis(0), |f| frames.push(f)),
+            Err(TpdoError::Read {
+                pdo: 0,
+                entry: missing.0,
+                code: missing.1
+            })
+        );
+        assert!(frames.is_empty());
+    }
+
+    #[test]
+    fn test_event_timer_and_inhibit_time() {
+        let od = test_od([
+            PdoTransmissionType::EventDriven,
+            PdoTransmissionType::Synchronous(1),
+        ]);
+        let mut producer = TpdoProducer::new();
+        let mut frames = std::vec::Vec::new();
+
+        producer
+            .tick(&od, Instant::from_millis(0), |f| frames.push(f))
+            .unwrap();
+        assert!(frames.is_empty());
+        producer
+            .tick(&od, Instant::from_millis(100), |f| frames.push(f))
+            .unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].id(), EncodedCANOpenFrame::new(0x181, &[]).id());
+
+        // Triggered within the 30ms inhibit time, so delayed until it ends.
+        producer.trigger(0);
+        producer
+            .tick(&od, Instant::from_millis(110), |f| frames.push(f))
+            .unwrap();
+        assert_eq!(frames.len(), 1);
+        producer
+            .tick(&od, Instant::from_millis(130), |f| frames.push(f))
+            .unwrap();
+        assert_eq!(frames.len(), 2);
+
+        // The event timer restarts after each transmission.
+        producer
+            .tick(&od, Instant::from_millis(200), |f| frames.push(f))
+            .unwrap();
+        assert_eq!(frames.len(), 2);
+        producer
+            .tick(&od, Instant::from_millis(230), |f| frames.push(f))
+            .unwrap();
+        assert_eq!(frames.len(), 3);
+    }
+
+    #[test]
+    fn test_synchronous() {
+        let od = test_od([
+            PdoTransmissionType::Synchronous(0),
+            PdoTransmissionType::Synchronous(3),
+        ]);
+        let mut producer = TpdoProducer::new();
+        let mut frames = std::vec::Vec::new();
+
+        producer
+            .tick(&od, Instant::from_millis(1000), |f| frames.push(f))
+            .unwrap();
+        assert!(frames.is_empty());
+
+        producer.process_sync(&od, |f| frames.push(f)).unwrap();
+        producer.process_sync(&od, |f| frames.push(f)).unwrap();
+        assert!(frames.is_empty());
+
+        producer.trigger(0);
+        producer.process_sync(&od, |f| frames.push(f)).unwrap();
+        let ids: std::vec::Vec<_> = frames.iter().map(|f| f.id()).collect();
+        assert_eq!(
+            ids,
+            [
+                EncodedCANOpenFrame::new(0x181, &[]).id(),
+                EncodedCANOpenFrame::new(0x281, &[]).id()
+            ]
+        );
+
+        producer.process_sync(&od, |f| frames.push(f)).unwrap();
+        assert_eq!(frames.len(), 2);
+    }
+
+    #[test]
+    fn test_odd_width_packing() {
+        let mut od: ObjectDictionary<2, 0, 0> = ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [],
+            Vec::from_slice(&[
+                Variable::builder(EntryId::new(0x2000, 0))
+                    .name("position")
+                    .pdo(PdoMapability::All)
+                    .value(VariableType::Int40(-2, &DefaultI40Coder))
+                    .unwrap(),
+                Variable::builder(EntryId::new(0x2001, 0))
+                    .name("speed")
+                    .pdo(PdoMapability::All)
+                    .value(VariableType::UInt24(0x12_3456, &DefaultU24Coder))
+                    .unwrap(),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::new(1).unwrap(),
+        );
+        let mut mappings = [PdoEntryMapping::default(); 8];
+        mappings[0] = PdoEntryMapping::new(0x2000, 0, 40);
+        mappings[1] = PdoEntryMapping::new(0x2001, 0, 24);
+        let config = PdoConfiguration::new(
+            CobId::new(true, false, FrameId::Standard(0x181)),
+            PdoTransmissionType::EventDriven,
+            2,
+            mappings,
+            0,
+        );
+
+        let data = config.pack(&od).unwrap();
+        assert_eq!(data, [0xFE, 0xFF, 0xFF, 0xFF, 0xFF, 0x56, 0x34, 0x12]);
+
+        config
+            .unpack(&mut od, &[0x01, 0, 0, 0, 0x80, 0xAB, 0, 0])
+            .unwrap();
+        let position = od.get_variable(EntryId::new(0x2000, 0)).unwrap().value();
+        assert!(matches!(position, VariableType::Int40(value, _) if value == 1 - (1 << 39)));
+        assert_eq!(od.read_unsigned(EntryId::new(0x2001, 0)), Some(0xAB));
+    }
+}
@@ -5,6 +5,13 @@ use num_traits::FromPrimitive;
 
 use crate::{frame::EncodedCANOpenFrame, node::NodeId, object_dictionary::EntryId};
 
+pub mod client;
+mod crc;
+pub mod server;
+
+pub use client::{SdoClient, SdoError};
+pub use server::SdoServer;
+
 #[derive(FromPrimitive, ToPrimitive, Copy, Clone, Debug, PartialEq, Eq)]
 #[repr(u32)]
 pub enum SdoAbortCode {
@@ -72,6 +79,7 @@ pub enum SdoFrame {
         id: EntryId,
         payload: Vec<u8, 4>,
     },
+    /// `size` is 0 when the server did not indicate the transfer size.
     SegmentedUploadInitiateResponse {
         id: EntryId,
         size: u32,
@@ -100,6 +108,68 @@ pub enum SdoFrame {
         id: EntryId,
         code: SdoAbortCode,
     },
+    /// `size` is 0 when the client did not indicate the transfer size.
+    BlockDownloadInitiateRequest {
+        id: EntryId,
+        crc_supported: bool,
+        size: u32,
+    },
+    BlockDownloadInitiateResponse {
+        id: EntryId,
+        crc_supported: bool,
+        block_size: u8,
+    },
+    /// A segment of a sub-block, sent by the client during block download and
+    /// by the server during block upload. `payload` always holds 7 bytes, the
+    /// number of valid bytes in the last segment is carried by the end frame.
+    BlockSegment {
+        seqno: u8,
+        last: bool,
+        payload: Vec<u8, 7>,
+    },
+    BlockDownloadAck {
+        ack_seq: u8,
+        block_size: u8,
+    },
+    BlockDownloadEndRequest {
+        unused_bytes: u8,
+        crc: u16,
+    },
+    BlockDownloadEndResponse,
+    BlockUploadInitiateRequest {
+        id: EntryId,
+        crc_supported: bool,
+        block_size: u8,
+        protocol_switch_threshold: u8,
+    },
+    /// `size` is 0 when the server did not indicate the transfer size.
+    BlockUploadInitiateResponse {
+        id: EntryId,
+        crc_supported: bool,
+        size: u32,
+    },
+    BlockUploadStartRequest,
+    BlockUploadAck {
+        ack_seq: u8,
+        block_size: u8,
+    },
+    BlockUploadEndRequest {
+        unused_bytes: u8,
+        crc: u16,
+    },
+    BlockUploadEndResponse,
+}
+
+/// Largest number of segments in a single sub-block.
+pub const MAX_BLOCK_SIZE: u8 = 127;
+
+/// Number of padding bytes in the last segment of a block transfer of `len` bytes.
+pub(crate) fn unused_block_bytes(len: usize) -> u8 {
+    if len == 0 {
+        7
+    } else {
+        ((7 - len % 7) % 7) as u8
+    }
 }
 
 trait SdoCommand: Into<u8> + TryFrom<u8> {}
@@ -120,11 +190,26 @@ enum ClientCommand {
         toggle: bool,
     },
     Abort,
+    BlockUploadInitiate {
+        crc_supported: bool,
+    },
+    BlockUploadEnd,
+    BlockUploadAck,
+    BlockUploadStart,
+    BlockDownloadInitiate {
+        crc_supported: bool,
+        size_indicated: bool,
+    },
+    BlockDownloadEnd {
+        unused_bytes: u8,
+    },
 }
 
-impl Into<u8> for ClientCommand {
-    fn into(self) -> u8 {
-        match self {
+impl From<ClientCommand> for u8 {
+    // Zero fields are kept to spell out the layout of the command byte.
+    #[allow(clippy::identity_op)]
+    fn from(command: ClientCommand) -> u8 {
+        match command {
             ClientCommand::ExpeditedDownload { length } => {
                 (1 << 5) + ((4 - length) << 2) + (1 << 1) + 1
             }
@@ -137,6 +222,17 @@ impl Into<u8> for ClientCommand {
             ClientCommand::InitiateUpload => 2 << 5,
             ClientCommand::UploadSegmentRequest { toggle } => (3 << 5) + ((toggle as u8) << 4),
             ClientCommand::Abort => 4 << 5,
+            ClientCommand::BlockUploadInitiate { crc_supported } => {
+                (5 << 5) + ((crc_supported as u8) << 2) + 0
+            }
+            ClientCommand::BlockUploadEnd => (5 << 5) + 1,
+            ClientCommand::BlockUploadAck => (5 << 5) + 2,
+            ClientCommand::BlockUploadStart => (5 << 5) + 3,
+            ClientCommand::BlockDownloadInitiate {
+                crc_supported,
+                size_indicated,
+            } => (6 << 5) + ((crc_supported as u8) << 2) + ((size_indicated as u8) << 1) + 0,
+            ClientCommand::BlockDownloadEnd { unused_bytes } => (6 << 5) + (unused_bytes << 2) + 1,
         }
     }
 }
@@ -161,6 +257,21 @@ impl TryFrom<u8> for ClientCommand {
                 toggle: (value >> 4) & 0b1 == 0b1,
             }),
             4 => Ok(ClientCommand::Abort),
+            5 => match value & 0b11 {
+                0 => Ok(ClientCommand::BlockUploadInitiate {
+                    crc_supported: (value >> 2) & 0b1 == 0b1,
+                }),
+                1 => Ok(ClientCommand::BlockUploadEnd),
+                2 => Ok(ClientCommand::BlockUploadAck),
+                _ => Ok(ClientCommand::BlockUploadStart),
+            },
+            6 if value & 0b1 == 0b1 => Ok(ClientCommand::BlockDownloadEnd {
+                unused_bytes: (value >> 2) & 0b111,
+            }),
+            6 => Ok(ClientCommand::BlockDownloadInitiate {
+                crc_supported: (value >> 2) & 0b1 == 0b1,
+                size_indicated: (value >> 1) & 0b1 == 0b1,
+            }),
             _ => Err(InvalidCommandCode),
         }
     }
@@ -170,30 +281,58 @@ enum ServerCommand {
     InitiateDownloadResponse,
     DownloadSegmentResponse(bool),
     UploadInitiateExpeditedResponse(u8),
-    UploadInitiateSegmentedResponse,
+    UploadInitiateSegmentedResponse {
+        size_indicated: bool,
+    },
     UploadSegmentResponse {
         toggle: bool,
         length: u8,
         last: bool,
     },
     Abort,
+    BlockDownloadInitiateResponse {
+        crc_supported: bool,
+    },
+    BlockDownloadEndResponse,
+    BlockDownloadAck,
+    BlockUploadInitiateResponse {
+        crc_supported: bool,
+        size_indicated: bool,
+    },
+    BlockUploadEnd {
+        unused_bytes: u8,
+    },
 }
 
-impl Into<u8> for ServerCommand {
-    fn into(self) -> u8 {
-        match self {
-            Self::InitiateDownloadResponse => 3 << 5,
-            Self::DownloadSegmentResponse(toggle) => 1 << 5 | (toggle as u8) << 4,
-            Self::UploadInitiateExpeditedResponse(length) => {
+impl From<ServerCommand> for u8 {
+    // Zero fields are kept to spell out the layout of the command byte.
+    #[allow(clippy::identity_op)]
+    fn from(command: ServerCommand) -> u8 {
+        match command {
+            ServerCommand::InitiateDownloadResponse => 3 << 5,
+            ServerCommand::DownloadSegmentResponse(toggle) => 1 << 5 | (toggle as u8) << 4,
+            ServerCommand::UploadInitiateExpeditedResponse(length) => {
                 2 << 5 | (4 - length) << 2 | 0b1 << 1 | 0b1
             }
-            Self::UploadInitiateSegmentedResponse => 2 << 5 | 0b1,
-            Self::UploadSegmentResponse {
+            ServerCommand::UploadInitiateSegmentedResponse { size_indicated } => {
+                2 << 5 | (size_indicated as u8)
+            }
+            ServerCommand::UploadSegmentResponse {
                 toggle,
                 length,
                 last,
             } => 0 << 5 | (toggle as u8) << 4 | (7 - length) << 1 | (last as u8),
-            Self::Abort => 4 << 5,
+            ServerCommand::Abort => 4 << 5,
+            ServerCommand::BlockDownloadInitiateResponse { crc_supported } => {
+                5 << 5 | (crc_supported as u8) << 2 | 0
+            }
+            ServerCommand::BlockDownloadEndResponse => 5 << 5 | 1,
+            ServerCommand::BlockDownloadAck => 5 << 5 | 2,
+            ServerCommand::BlockUploadInitiateResponse {
+                crc_supported,
+                size_indicated,
+            } => 6 << 5 | (crc_supported as u8) << 2 | (size_indicated as u8) << 1 | 0,
+            ServerCommand::BlockUploadEnd { unused_bytes } => 6 << 5 | unused_bytes << 2 | 1,
         }
     }
 }
@@ -211,9 +350,26 @@ impl TryFrom<u8> for ServerCommand {
             2 if (value >> 1) & 0b1 == 0b1 => Ok(Self::UploadInitiateExpeditedResponse(
                 4 - ((value >> 2) & 0b11),
             )),
-            2 if (value >> 1) & 0b1 != 0b1 => Ok(Self::UploadInitiateSegmentedResponse),
+            2 if (value >> 1) & 0b1 != 0b1 => Ok(Self::UploadInitiateSegmentedResponse {
+                size_indicated: value & 0b1 == 0b1,
+            }),
             3 => Ok(Self::InitiateDownloadResponse),
             4 => Ok(Self::Abort),
+            5 => match value & 0b11 {
+                0 => Ok(Self::BlockDownloadInitiateResponse {
+                    crc_supported: (value >> 2) & 0b1 == 0b1,
+                }),
+                1 => Ok(Self::BlockDownloadEndResponse),
+                2 => Ok(Self::BlockDownloadAck),
+                _ => Err(InvalidCommandCode),
+            },
+            6 if value & 0b1 == 0b1 => Ok(Self::BlockUploadEnd {
+                unused_bytes: (value >> 2) & 0b111,
+            }),
+            6 => Ok(Self::BlockUploadInitiateResponse {
+                crc_supported: (value >> 2) & 0b1 == 0b1,
+                size_indicated: (value >> 1) & 0b1 == 0b1,
+            }),
             _ => Err(InvalidCommandCode),
         }
     }
@@ -237,9 +393,43 @@ impl SDOCoder {
         node_role: SDORole,
         frame: &impl Frame,
     ) -> Option<SdoFrame> {
+        let frame_data = Self::rx_frame_data(self_node_id, &node_role, frame)?;
+        match node_role {
+            SDORole::Server => Self::try_decode_rx_frame_from_client(frame_data),
+            SDORole::Client => Self::try_decode_rx_frame_from_server(frame_data),
+        }
+    }
+
+    /// Decodes a frame received while a sub-block is being transferred, where
+    /// any frame other than an abort is a block segment.
+    pub(crate) fn try_decode_rx_block_segment(
+        self_node_id: NodeId,
+        node_role: SDORole,
+        frame: &impl Frame,
+    ) -> Option<SdoFrame> {
+        let frame_data = Self::rx_frame_data(self_node_id, &node_role, frame)?;
+        if frame_data[0] & 0x7F == 0 {
+            return Self::try_decode_rx_frame(self_node_id, node_role, frame);
+        }
+        Some(SdoFrame::BlockSegment {
+            seqno: frame_data[0] & 0x7F,
+            last: frame_data[0] >> 7 == 0b1,
+            payload: Vec::from_slice(&frame_data[1..8]).unwrap(),
+        })
+    }
+
+    fn rx_frame_data<'a>(
+        self_node_id: NodeId,
+        node_role: &SDORole,
+        frame: &'a impl Frame,
+    ) -> Option<&'a [u8]> {
+        let id_offset = match node_role {
+            SDORole::Server => Self::RX_ID_OFFSET,
+            SDORole::Client => Self::TX_ID_OFFSET,
+        };
         match frame.id() {
             Id::Standard(std) => {
-                if std.as_raw() != (self_node_id.raw() as u16 + Self::RX_ID_OFFSET) {
+                if std.as_raw() != (self_node_id.raw() as u16 + id_offset) {
                     return None;
                 }
             }
@@ -250,15 +440,11 @@ impl SDOCoder {
             return None;
         }
 
-        let frame_data = frame.data();
-        match node_role {
-            SDORole::Server => Self::try_decode_rx_frame_from_client(frame_data),
-            SDORole::Client => Self::try_decode_rx_frame_from_server(frame_data),
-        }
+        Some(frame.data())
     }
 
     fn try_decode_rx_frame_from_client(frame_data: &[u8]) -> Option<SdoFrame> {
-        return match ClientCommand::try_from(frame_data[0]) {
+        match ClientCommand::try_from(frame_data[0]) {
             Err(_) => None,
             Ok(ClientCommand::ExpeditedDownload { length }) => {
                 Some(SdoFrame::ExpeditedDownloadRequest {
@@ -278,7 +464,7 @@ impl SDOCoder {
                 length,
                 last_seg,
             }) => Some(SdoFrame::SegmentedDownloadRequest {
-                toggle: toggle,
+                toggle,
                 last: last_seg,
                 payload: Vec::<u8, 7>::from_slice(
                     frame_data[1..(1 + length as usize)].try_into().unwrap(),
@@ -289,30 +475,60 @@ impl SDOCoder {
                 id: EntryId::from_bytes(frame_data[1..4].try_into().unwrap()),
             }),
             Ok(ClientCommand::UploadSegmentRequest { toggle }) => {
-                Some(SdoFrame::SegmentedUploadRequest { toggle: toggle })
+                Some(SdoFrame::SegmentedUploadRequest { toggle })
             }
-            Ok(ClientCommand::Abort) => match SdoAbortCode::from_le_bytes(&frame_data[4..8]) {
-                None => None,
-                Some(code) => Some(SdoFrame::Abort {
+            Ok(ClientCommand::Abort) => {
+                SdoAbortCode::from_le_bytes(&frame_data[4..8]).map(|code| SdoFrame::Abort {
                     id: EntryId::from_bytes(frame_data[1..4].try_into().unwrap()),
-                    code: code,
-                }),
-            },
-        };
+                    code,
+                })
+            }
+            Ok(ClientCommand::BlockUploadInitiate { crc_supported }) => {
+                Some(SdoFrame::BlockUploadInitiateRequest {
+                    id: EntryId::from_bytes(frame_data[1..4].try_into().unwrap()),
+                    crc_supported,
+                    block_size: frame_data[4],
+                    protocol_switch_threshold: frame_data[5],
+                })
+            }
+            Ok(ClientCommand::BlockUploadEnd) => Some(SdoFrame::BlockUploadEndResponse),
+            Ok(ClientCommand::BlockUploadAck) => Some(SdoFrame::BlockUploadAck {
+                ack_seq: frame_data[1],
+                block_size: frame_data[2],
+            }),
+            Ok(ClientCommand::BlockUploadStart) => Some(SdoFrame::BlockUploadStartRequest),
+            Ok(ClientCommand::BlockDownloadInitiate {
+                crc_supported,
+                size_indicated,
+            }) => Some(SdoFrame::BlockDownloadInitiateRequest {
+                id: EntryId::from_bytes(frame_data[1..4].try_into().unwrap()),
+                crc_supported,
+                size: if size_indicated {
+                    u32::from_le_bytes(frame_data[4..8].try_into().unwrap())
+                } else {
+                    0
+                },
+            }),
+            Ok(ClientCommand::BlockDownloadEnd { unused_bytes }) => {
+                Some(SdoFrame::BlockDownloadEndRequest {
+                    unused_bytes,
+                    crc: u16::from_le_bytes(frame_data[1..3].try_into().unwrap()),
+                })
+            }
+        }
     }
 
     fn try_decode_rx_frame_from_server(frame_data: &[u8]) -> Option<SdoFrame> {
-        return match ServerCommand::try_from(frame_data[0]) {
+        match ServerCommand::try_from(frame_data[0]) {
             Err(_) => None,
-            Ok(ServerCommand::Abort) => match SdoAbortCode::from_le_bytes(&frame_data[4..8]) {
-                None => None,
-                Some(code) => Some(SdoFrame::Abort {
+            Ok(ServerCommand::Abort) => {
+                SdoAbortCode::from_le_bytes(&frame_data[4..8]).map(|code| SdoFrame::Abort {
                     id: EntryId::from_bytes(frame_data[1..4].try_into().unwrap()),
-                    code: code,
-                }),
-            },
+                    code,
+                })
+            }
             Ok(ServerCommand::DownloadSegmentResponse(toggle)) => {
-                Some(SdoFrame::SegmentedDownloadResponse { toggle: toggle })
+                Some(SdoFrame::SegmentedDownloadResponse { toggle })
             }
             Ok(ServerCommand::InitiateDownloadResponse) => {
                 Some(SdoFrame::DownloadInitiateResponse {
@@ -325,10 +541,14 @@ impl SDOCoder {
                     payload: Vec::<u8, 4>::from_slice(&frame_data[4..(4 + size as usize)]).unwrap(),
                 })
             }
-            Ok(ServerCommand::UploadInitiateSegmentedResponse) => {
+            Ok(ServerCommand::UploadInitiateSegmentedResponse { size_indicated }) => {
                 Some(SdoFrame::SegmentedUploadInitiateResponse {
                     id: EntryId::from_bytes(frame_data[1..4].try_into().unwrap()),
-                    size: u32::from_le_bytes(frame_data[4..8].try_into().unwrap()),
+                    size: if size_indicated {
+                        u32::from_le_bytes(frame_data[4..8].try_into().unwrap())
+                    } else {
+                        0
+                    },
                 })
             }
             Ok(ServerCommand::UploadSegmentResponse {
@@ -336,11 +556,41 @@ impl SDOCoder {
                 length,
                 last,
             }) => Some(SdoFrame::SegmentedUploadResponse {
-                toggle: toggle,
-                last: last,
+                toggle,
+                last,
                 payload: Vec::<u8, 7>::from_slice(&frame_data[1..(1 + length as usize)]).unwrap(),
             }),
-        };
+            Ok(ServerCommand::BlockDownloadInitiateResponse { crc_supported }) => {
+                Some(SdoFrame::BlockDownloadInitiateResponse {
+                    id: EntryId::from_bytes(frame_data[1..4].try_into().unwrap()),
+                    crc_supported,
+                    block_size: frame_data[4],
+                })
+            }
+            Ok(ServerCommand::BlockDownloadEndResponse) => Some(SdoFrame::BlockDownloadEndResponse),
+            Ok(ServerCommand::BlockDownloadAck) => Some(SdoFrame::BlockDownloadAck {
+                ack_seq: frame_data[1],
+                block_size: frame_data[2],
+            }),
+            Ok(ServerCommand::BlockUploadInitiateResponse {
+                crc_supported,
+                size_indicated,
+            }) => Some(SdoFrame::BlockUploadInitiateResponse {
+                id: EntryId::from_bytes(frame_data[1..4].try_into().unwrap()),
+                crc_supported,
+                size: if size_indicated {
+                    u32::from_le_bytes(frame_data[4..8].try_into().unwrap())
+                } else {
+                    0
+                },
+            }),
+            Ok(ServerCommand::BlockUploadEnd { unused_bytes }) => {
+                Some(SdoFrame::BlockUploadEndRequest {
+                    unused_bytes,
+                    crc: u16::from_le_bytes(frame_data[1..3].try_into().unwrap()),
+                })
+            }
+        }
     }
 
     pub(crate) fn encode_tx_frame(tx_id: Id, sdo_frame: SdoFrame) -> EncodedCANOpenFrame {
@@ -370,13 +620,15 @@ impl SDOCoder {
             ),
             SdoFrame::SegmentedUploadInitiateResponse { id, size } => Self::build_tx_sdo_frame(
                 tx_id,
-                ServerCommand::UploadInitiateSegmentedResponse,
+                ServerCommand::UploadInitiateSegmentedResponse {
+                    size_indicated: true,
+                },
                 Some(id),
                 Vec::<u8, 4>::from_slice(&size.to_le_bytes()).ok(),
             ),
             SdoFrame::SegmentedUploadRequest { toggle } => Self::build_tx_sdo_frame::<0>(
                 tx_id,
-                ClientCommand::UploadSegmentRequest { toggle: toggle },
+                ClientCommand::UploadSegmentRequest { toggle },
                 None,
                 None,
             ),
@@ -387,9 +639,9 @@ impl SDOCoder {
             } => Self::build_tx_sdo_frame(
                 tx_id,
                 ServerCommand::UploadSegmentResponse {
-                    toggle: toggle,
+                    toggle,
                     length: payload.len() as u8,
-                    last: last,
+                    last,
                 },
                 None,
                 Some(payload),
@@ -407,7 +659,7 @@ impl SDOCoder {
             } => Self::build_tx_sdo_frame(
                 tx_id,
                 ClientCommand::DownloadSegmentRequest {
-                    toggle: toggle,
+                    toggle,
                     length: payload.len() as u8,
                     last_seg: last,
                 },
@@ -426,6 +678,106 @@ impl SDOCoder {
                 Some(id),
                 Vec::<u8, 4>::from_slice(&code.to_le_bytes()).ok(),
             ),
+            SdoFrame::BlockDownloadInitiateRequest {
+                id,
+                crc_supported,
+                size,
+            } => Self::build_tx_sdo_frame(
+                tx_id,
+                ClientCommand::BlockDownloadInitiate {
+                    crc_supported,
+                    size_indicated: true,
+                },
+                Some(id),
+                Vec::<u8, 4>::from_slice(&size.to_le_bytes()).ok(),
+            ),
+            SdoFrame::BlockDownloadInitiateResponse {
+                id,
+                crc_supported,
+                block_size,
+            } => Self::build_tx_sdo_frame(
+                tx_id,
+                ServerCommand::BlockDownloadInitiateResponse { crc_supported },
+                Some(id),
+                Vec::<u8, 1>::from_slice(&[block_size]).ok(),
+            ),
+            SdoFrame::BlockSegment {
+                seqno,
+                last,
+                payload,
+            } => {
+                let mut data = Vec::<u8, 8>::new();
+                data.push((last as u8) << 7 | seqno).unwrap();
+                data.extend_from_slice(&payload).unwrap();
+                data.resize(8, 0).unwrap();
+                EncodedCANOpenFrame::from_vec_data(tx_id, data)
+            }
+            SdoFrame::BlockDownloadAck {
+                ack_seq,
+                block_size,
+            } => Self::build_tx_sdo_frame(
+                tx_id,
+                ServerCommand::BlockDownloadAck,
+                None,
+                Vec::<u8, 2>::from_slice(&[ack_seq, block_size]).ok(),
+            ),
+            SdoFrame::BlockDownloadEndRequest { unused_bytes, crc } => Self::build_tx_sdo_frame(
+                tx_id,
+                ClientCommand::BlockDownloadEnd { unused_bytes },
+                None,
+                Vec::<u8, 2>::from_slice(&crc.to_le_bytes()).ok(),
+            ),
+            SdoFrame::BlockDownloadEndResponse => Self::build_tx_sdo_frame::<0>(
+                tx_id,
+                ServerCommand::BlockDownloadEndResponse,
+                None,
+                None,
+            ),
+            SdoFrame::BlockUploadInitiateRequest {
+                id,
+                crc_supported,
+                block_size,
+                protocol_switch_threshold,
+            } => Self::build_tx_sdo_frame(
+                tx_id,
+                ClientCommand::BlockUploadInitiate { crc_supported },
+                Some(id),
+                Vec::<u8, 2>::from_slice(&[block_size, protocol_switch_threshold]).ok(),
+            ),
+            SdoFrame::BlockUploadInitiateResponse {
+                id,
+                crc_supported,
+                size,
+            } => Self::build_tx_sdo_frame(
+                tx_id,
+                ServerCommand::BlockUploadInitiateResponse {
+                    crc_supported,
+                    size_indicated: true,
+                },
+                Some(id),
+                Vec::<u8, 4>::from_slice(&size.to_le_bytes()).ok(),
+            ),
+            SdoFrame::BlockUploadStartRequest => {
+                Self::build_tx_sdo_frame::<0>(tx_id, ClientCommand::BlockUploadStart, None, None)
+            }
+            SdoFrame::BlockUploadAck {
+                ack_seq,
+                block_size,
+            } => Self::build_tx_sdo_frame(
+                tx_id,
+                ClientCommand::BlockUploadAck,
+                None,
+                Vec::<u8, 2>::from_slice(&[ack_seq, block_size]).ok(),
+            ),
+            SdoFrame::BlockUploadEndRequest { unused_bytes, crc } => Self::build_tx_sdo_frame(
+                tx_id,
+                ServerCommand::BlockUploadEnd { unused_bytes },
+                None,
+                Vec::<u8, 2>::from_slice(&crc.to_le_bytes()).ok(),
+            ),
+            SdoFrame::BlockUploadEndResponse => {
+                Self::build_tx_sdo_frame::<0>(tx_id, ClientCommand::BlockUploadEnd, None, None)
+            }
         }
     }
 
@@ -460,6 +812,7 @@ impl SDOCoder {
 }
 
 #[cfg(test)]
+#[allow(clippy::identity_op)]
 mod tests {
     use embedded_can::{Frame, Id, StandardId};
     use heapless::Vec;
@@ -534,7 +887,7 @@ mod tests {
     #[test]
     fn test_rx_decode_exp_dl_resp() {
         let frame =
-            EncodedCANOpenFrame::new(0x605, &[(3 << 5), 0x00, 0x20, 0x1, 0x0, 0x0, 0x0, 0x0]);
+            EncodedCANOpenFrame::new(0x585, &[(3 << 5), 0x00, 0x20, 0x1, 0x0, 0x0, 0x0, 0x0]);
         let decoded =
             SDOCoder::try_decode_rx_frame(NodeId::new(5).unwrap(), SDORole::Client, &frame);
 
@@ -605,8 +958,8 @@ mod tests {
 
     #[test]
     fn test_rx_decode_dl_seg_resp() {
-        let mut frame = EncodedCANOpenFrame::new(
-            0x605,
+        let frame = EncodedCANOpenFrame::new(
+            0x585,
             &[
                 (1 << 5) + (1 << 4),
                 0x00,
@@ -618,16 +971,11 @@ mod tests {
                 0x00,
             ],
         );
-        let mut decoded =
+        let decoded =
             SDOCoder::try_decode_rx_frame(NodeId::new(5).unwrap(), SDORole::Client, &frame);
         assert!(decoded.is_some());
-        let mut sdo = decoded.unwrap();
-        assert_eq!(
-            sdo,
-            SdoFrame::SegmentedDownloadResponse {
-                toggle: true
-            }
-        );
+        let sdo = decoded.unwrap();
+        assert_eq!(sdo, SdoFrame::SegmentedDownloadResponse { toggle: true });
     }
 
     #[test]
@@ -647,8 +995,8 @@ mod tests {
 
     #[test]
     fn test_rx_upload_init_exp_resp() {
-        let mut frame = EncodedCANOpenFrame::new(
-            0x605,
+        let frame = EncodedCANOpenFrame::new(
+            0x585,
             &[
                 (2 << 5) + (1 << 2) + (1 << 1) + 1,
                 0x00,
@@ -660,10 +1008,10 @@ mod tests {
                 0x77,
             ],
         );
-        let mut decoded =
+        let decoded =
             SDOCoder::try_decode_rx_frame(NodeId::new(5).unwrap(), SDORole::Client, &frame);
         assert!(decoded.is_some());
-        let mut sdo = decoded.unwrap();
+        let sdo = decoded.unwrap();
         assert_eq!(
             sdo,
             SdoFrame::ExpeditedUploadResponse {
@@ -675,8 +1023,8 @@ mod tests {
 
     #[test]
     fn test_rx_upload_init_seg_resp() {
-        let mut frame = EncodedCANOpenFrame::new(
-            0x605,
+        let frame = EncodedCANOpenFrame::new(
+            0x585,
             &[
                 (2 << 5) + (1 << 2) + (0 << 1) + 1,
                 0x00,
@@ -688,10 +1036,10 @@ mod tests {
                 0x77,
             ],
         );
-        let mut decoded =
+        let decoded =
             SDOCoder::try_decode_rx_frame(NodeId::new(5).unwrap(), SDORole::Client, &frame);
         assert!(decoded.is_some());
-        let mut sdo = decoded.unwrap();
+        let sdo = decoded.unwrap();
         assert_eq!(
             sdo,
             SdoFrame::SegmentedUploadInitiateResponse {
@@ -699,6 +1047,16 @@ mod tests {
                 size: 0x77889955
             }
         );
+
+        // The size is ignored when not indicated.
+        let frame = EncodedCANOpenFrame::new(0x585, &[2 << 5, 0x00, 0x20, 0x01, 0x55, 0, 0, 0]);
+        assert_eq!(
+            SDOCoder::try_decode_rx_frame(NodeId::new(5).unwrap(), SDORole::Client, &frame),
+            Some(SdoFrame::SegmentedUploadInitiateResponse {
+                id: EntryId::new(0x2000, 0x01),
+                size: 0
+            })
+        );
     }
 
     #[test]
@@ -747,8 +1105,8 @@ mod tests {
 
     #[test]
     fn test_rx_decode_upload_seg_resp() {
-        let mut frame = EncodedCANOpenFrame::new(
-            0x605,
+        let frame = EncodedCANOpenFrame::new(
+            0x585,
             &[
                 (0 << 5) + (1 << 4) + (1 << 1) + 0,
                 0x00,
@@ -760,10 +1118,10 @@ mod tests {
                 0x77,
             ],
         );
-        let mut decoded =
+        let decoded =
             SDOCoder::try_decode_rx_frame(NodeId::new(5).unwrap(), SDORole::Client, &frame);
         assert!(decoded.is_some());
-        let mut sdo = decoded.unwrap();
+        let sdo = decoded.unwrap();
         assert_eq!(
             sdo,
             SdoFrame::SegmentedUploadResponse {
@@ -790,6 +1148,8 @@ mod tests {
             }
         );
 
+        let frame =
+            EncodedCANOpenFrame::new(0x585, &[(4 << 5), 0x00, 0x20, 0x05, 0x05, 0x00, 0x04, 0x05]);
         decoded = SDOCoder::try_decode_rx_frame(NodeId::new(5).unwrap(), SDORole::Client, &frame);
         assert!(decoded.is_some());
         let sdo = decoded.unwrap();
@@ -802,6 +1162,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rx_decode_client_ignores_requests() {
+        let frame = EncodedCANOpenFrame::new(0x605, &[(3 << 5), 0x00, 0x20, 0x1, 0, 0, 0, 0]);
+        let decoded =
+            SDOCoder::try_decode_rx_frame(NodeId::new(5).unwrap(), SDORole::Client, &frame);
+        assert!(decoded.is_none());
+    }
+
     // Transmit Encoding tests
     #[test]
     fn test_tx_exp_dl_req() {
@@ -1008,4 +1376,154 @@ mod tests {
             [(4 << 5), 0x00, 0x20, 0x50, 0x23, 0x00, 0x0A, 0x06]
         );
     }
+
+    #[test]
+    fn test_rx_decode_block_download_initiate() {
+        let frame = EncodedCANOpenFrame::new(
+            0x605,
+            &[
+                (6 << 5) + (1 << 2) + (1 << 1),
+                0x00,
+                0x20,
+                0x01,
+                0x10,
+                0,
+                0,
+                0,
+            ],
+        );
+        let decoded =
+            SDOCoder::try_decode_rx_frame(NodeId::new(5).unwrap(), SDORole::Server, &frame);
+        assert_eq!(
+            decoded,
+            Some(SdoFrame::BlockDownloadInitiateRequest {
+                id: EntryId::new(0x2000, 0x1),
+                crc_supported: true,
+                size: 0x10
+            })
+        );
+
+        let frame =
+            EncodedCANOpenFrame::new(0x605, &[(6 << 5) + (3 << 2) + 1, 0x34, 0x12, 0, 0, 0, 0, 0]);
+        let decoded =
+            SDOCoder::try_decode_rx_frame(NodeId::new(5).unwrap(), SDORole::Server, &frame);
+        assert_eq!(
+            decoded,
+            Some(SdoFrame::BlockDownloadEndRequest {
+                unused_bytes: 3,
+                crc: 0x1234
+            })
+        );
+    }
+
+    #[test]
+    fn test_rx_decode_block_upload_responses() {
+        let frame = EncodedCANOpenFrame::new(
+            0x585,
+            &[
+                (6 << 5) + (1 << 2) + (1 << 1),
+                0x00,
+                0x20,
+                0x01,
+                0x10,
+                0,
+                0,
+                0,
+            ],
+        );
+        let decoded =
+            SDOCoder::try_decode_rx_frame(NodeId::new(5).unwrap(), SDORole::Client, &frame);
+        assert_eq!(
+            decoded,
+            Some(SdoFrame::BlockUploadInitiateResponse {
+                id: EntryId::new(0x2000, 0x1),
+                crc_supported: true,
+                size: 0x10
+            })
+        );
+
+        let frame = EncodedCANOpenFrame::new(0x585, &[(5 << 5) + 2, 0x05, 0x7F, 0, 0, 0, 0, 0]);
+        let decoded =
+            SDOCoder::try_decode_rx_frame(NodeId::new(5).unwrap(), SDORole::Client, &frame);
+        assert_eq!(
+            decoded,
+            Some(SdoFrame::BlockDownloadAck {
+                ack_seq: 5,
+                block_size: 127
+            })
+        );
+    }
+
+    #[test]
+    fn test_rx_decode_block_segment() {
+        let frame = EncodedCANOpenFrame::new(0x605, &[0x80 + 3, 1, 2, 3, 4, 5, 6, 7]);
+        let decoded =
+            SDOCoder::try_decode_rx_block_segment(NodeId::new(5).unwrap(), SDORole::Server, &frame);
+        assert_eq!(
+            decoded,
+            Some(SdoFrame::BlockSegment {
+                seqno: 3,
+                last: true,
+                payload: Vec::from_slice(&[1, 2, 3, 4, 5, 6, 7]).unwrap()
+            })
+        );
+
+        let abort =
+            EncodedCANOpenFrame::new(0x605, &[(4 << 5), 0x00, 0x20, 0x01, 0x04, 0x00, 0x04, 0x05]);
+        let decoded =
+            SDOCoder::try_decode_rx_block_segment(NodeId::new(5).unwrap(), SDORole::Server, &abort);
+        assert_eq!(
+            decoded,
+            Some(SdoFrame::Abort {
+                id: EntryId::new(0x2000, 0x1),
+                code: SdoAbortCode::CRCError
+            })
+        );
+    }
+
+    #[test]
+    fn test_tx_encode_block_upload_initiate() {
+        let tx_id = Id::Standard(StandardId::new(0x605).unwrap());
+        let encoded = SDOCoder::encode_tx_frame(
+            tx_id,
+            SdoFrame::BlockUploadInitiateRequest {
+                id: EntryId::new(0x2000, 0x1),
+                crc_supported: true,
+                block_size: 127,
+                protocol_switch_threshold: 21,
+            },
+        );
+
+        assert_eq!(encoded.id(), tx_id);
+        assert_eq!(
+            encoded.data(),
+            [(5 << 5) + (1 << 2), 0x00, 0x20, 0x01, 127, 21, 0x0, 0x0]
+        );
+    }
+
+    #[test]
+    fn test_tx_encode_block_segment_and_end() {
+        let tx_id = Id::Standard(StandardId::new(0x585).unwrap());
+        let encoded = SDOCoder::encode_tx_frame(
+            tx_id,
+            SdoFrame::BlockSegment {
+                seqno: 2,
+                last: false,
+                payload: Vec::from_slice(&[1, 2, 3, 4, 5, 6, 7]).unwrap(),
+            },
+        );
+        assert_eq!(encoded.data(), [2, 1, 2, 3, 4, 5, 6, 7]);
+
+        let encoded = SDOCoder::encode_tx_frame(
+            tx_id,
+            SdoFrame::BlockUploadEndRequest {
+                unused_bytes: 6,
+                crc: 0xABCD,
+            },
+        );
+        assert_eq!(
+            encoded.data(),
+            [(6 << 5) + (6 << 2) + 1, 0xCD, 0xAB, 0, 0, 0, 0, 0]
+        );
+    }
 }
@@ -0,0 +1,1256 @@
+use core::time::Duration;
+
+use embedded_can::{nb::Can, Error, ErrorKind, Frame, Id, StandardId};
+
+use crate::{
+    frame::EncodedCANOpenFrame,
+    node::NodeId,
+    object_dictionary::EntryId,
+    time::{Clock, Instant},
+};
+
+use super::{
+    crc::crc16_ccitt, unused_block_bytes, SDOCoder, SDORole, SdoAbortCode, SdoFrame, MAX_BLOCK_SIZE,
+};
+
+/// Largest number of bytes reserved up front for an upload, whatever the size
+/// indicated by the server.
+const MAX_RESERVED_SIZE: usize = 4096;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SdoError {
+    /// Another transfer is still in progress on this client.
+    Busy,
+    /// No transfer has been started.
+    Idle,
+    /// The server aborted the transfer.
+    ServerAbort(SdoAbortCode),
+    /// The client aborted the transfer because of a protocol violation.
+    LocalAbort(SdoAbortCode),
+    /// The underlying CAN interface reported an error.
+    Bus(ErrorKind),
+}
+
+enum ClientState {
+    Idle,
+    ExpeditedDownload {
+        id: EntryId,
+    },
+    SegmentedDownloadInitiate {
+        id: EntryId,
+        data: Vec<u8>,
+    },
+    SegmentedDownload {
+        id: EntryId,
+        toggle: bool,
+        data: Vec<u8>,
+        offset: usize,
+    },
+    UploadInitiate {
+        id: EntryId,
+    },
+    /// `size` is 0 when the server did not indicate it.
+    SegmentedUpload {
+        id: EntryId,
+        toggle: bool,
+        size: usize,
+        data: Vec<u8>,
+    },
+    BlockDownloadInitiate {
+        id: EntryId,
+        data: Vec<u8>,
+    },
+    BlockDownload {
+        id: EntryId,
+        crc_enabled: bool,
+        data: Vec<u8>,
+        block_size: u8,
+        block_start: usize,
+        seqno: u8,
+        sending: bool,
+    },
+    BlockDownloadEnd {
+        id: EntryId,
+    },
+    BlockUploadInitiate {
+        id: EntryId,
+    },
+    BlockUpload {
+        id: EntryId,
+        crc_enabled: bool,
+        size: usize,
+        data: Vec<u8>,
+        expected_seqno: u8,
+        complete: bool,
+    },
+    BlockUploadEnd {
+        id: EntryId,
+        crc_enabled: bool,
+        size: usize,
+        data: Vec<u8>,
+    },
+    Done(Result<Vec<u8>, SdoError>),
+}
+
+/// An SDO client talking to one server at a time.
+///
+/// Transfers are started with [`SdoClient::start_upload`] or
+/// [`SdoClient::start_download`], which return the first request to send.
+/// Each frame received from the bus is then fed to
+/// [`SdoClient::process_frame`], which returns the next request to send, until
+/// [`SdoClient::poll_upload`] or [`SdoClient::poll_download`] stop returning
+/// `WouldBlock`. Expedited or segmented transfer is selected automatically.
+///
+/// Block transfers are started with [`SdoClient::start_block_upload`] and
+/// [`SdoClient::start_block_download`]. As a sub-block consists of several
+/// frames sent back to back, [`SdoClient::poll_frame`] must be drained after
+/// sending each request.
+///
+/// [`SdoClient::tick`] is called with the current time while a transfer is in
+/// progress. If the server does not answer within the timeout, the transfer is
+/// aborted and the abort request it returns is sent.
+///
+/// [`SdoClient::upload`], [`SdoClient::download`], [`SdoClient::block_upload`]
+/// and [`SdoClient::block_download`] drive the same state machine over a
+/// non-blocking CAN interface.
+pub struct SdoClient {
+    node_id: NodeId,
+    state: ClientState,
+    block_size: u8,
+    protocol_switch_threshold: u8,
+    timeout: Duration,
+    /// Time by which the server must answer, set by the first tick after a
+    /// request.
+    deadline: Option<Instant>,
+}
+
+impl Default for SdoClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SdoClient {
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(1000);
+
+    pub fn new() -> Self {
+        Self {
+            node_id: NodeId::default(),
+            state: ClientState::Idle,
+            block_size: MAX_BLOCK_SIZE,
+            protocol_switch_threshold: 0,
+            timeout: Self::DEFAULT_TIMEOUT,
+            deadline: None,
+        }
+    }
+
+    /// Sets how long to wait for each response of the server.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Sets the number of segments per sub-block requested in block uploads.
+    pub fn set_block_size(&mut self, block_size: u8) {
+        self.block_size = block_size.clamp(1, MAX_BLOCK_SIZE);
+    }
+
+    /// Sets the size up to which the server may answer a block upload with an
+    /// expedited or segmented upload instead. 0 disables the protocol switch.
+    pub fn set_protocol_switch_threshold(&mut self, threshold: u8) {
+        self.protocol_switch_threshold = threshold;
+    }
+
+    /// Returns `true` while a transfer is waiting for a response.
+    pub fn is_busy(&self) -> bool {
+        !matches!(self.state, ClientState::Idle | ClientState::Done(_))
+    }
+
+    pub fn tx_id(node_id: NodeId) -> Id {
+        Id::Standard(StandardId::new(SDOCoder::RX_ID_OFFSET + node_id.raw() as u16).unwrap())
+    }
+
+    /// Starts reading `id` from `node`, returning the request to send.
+    pub fn start_upload(
+        &mut self,
+        node: NodeId,
+        id: EntryId,
+    ) -> Result<EncodedCANOpenFrame, SdoError> {
+        self.start(node)?;
+        self.state = ClientState::UploadInitiate { id };
+        Ok(self.encode(SdoFrame::UploadRequest { id }))
+    }
+
+    /// Starts writing `data` to `id` on `node`, returning the request to send.
+    pub fn start_download(
+        &mut self,
+        node: NodeId,
+        id: EntryId,
+        data: &[u8],
+    ) -> Result<EncodedCANOpenFrame, SdoError> {
+        self.start(node)?;
+
+        if data.len() <= 4 {
+            self.state = ClientState::ExpeditedDownload { id };
+            return Ok(self.encode(SdoFrame::ExpeditedDownloadRequest {
+                id,
+                payload: heapless::Vec::from_slice(data).unwrap(),
+            }));
+        }
+
+        self.state = ClientState::SegmentedDownloadInitiate {
+            id,
+            data: data.to_vec(),
+        };
+        Ok(self.encode(SdoFrame::SegmentedDownloadInitiateRequest {
+            id,
+            size: data.len() as u32,
+        }))
+    }
+
+    /// Starts reading `id` from `node` with a block upload, returning the
+    /// request to send.
+    pub fn start_block_upload(
+        &mut self,
+        node: NodeId,
+        id: EntryId,
+    ) -> Result<EncodedCANOpenFrame, SdoError> {
+        self.start(node)?;
+        self.state = ClientState::BlockUploadInitiate { id };
+        Ok(self.encode(SdoFrame::BlockUploadInitiateRequest {
+            id,
+            crc_supported: true,
+            block_size: self.block_size,
+            protocol_switch_threshold: self.protocol_switch_threshold,
+        }))
+    }
+
+    /// Starts writing `data` to `id` on `node` with a block download,
+    /// returning the request to send.
+    pub fn start_block_download(
+        &mut self,
+        node: NodeId,
+        id: EntryId,
+        data: &[u8],
+    ) -> Result<EncodedCANOpenFrame, SdoError> {
+        self.start(node)?;
+        self.state = ClientState::BlockDownloadInitiate {
+            id,
+            data: data.to_vec(),
+        };
+        Ok(self.encode(SdoFrame::BlockDownloadInitiateRequest {
+            id,
+            crc_supported: true,
+            size: data.len() as u32,
+        }))
+    }
+
+    /// Returns the next segment of the sub-block being downloaded, if any.
+    pub fn poll_frame(&mut self) -> Option<EncodedCANOpenFrame> {
+        self.next_block_segment()
+            .map(|segment| self.encode(segment))
+    }
+
+    /// Aborts the transfer in progress, returning the abort request to send.
+    pub fn abort(&mut self, code: SdoAbortCode) -> Option<EncodedCANOpenFrame> {
+        if !self.is_busy() {
+            return None;
+        }
+        let id = self.current_id();
+        self.state = ClientState::Done(Err(SdoError::LocalAbort(code)));
+        Some(self.encode(SdoFrame::Abort { id, code }))
+    }
+
+    /// Feeds a received frame to the transfer in progress.
+    ///
+    /// Frames that are not SDO responses from the server being talked to are
+    /// ignored. Returns the next request to send, if any.
+    pub fn process_frame(&mut self, frame: &impl Frame) -> Option<EncodedCANOpenFrame> {
+        if !self.is_busy() {
+            return None;
+        }
+        let response = if matches!(self.state, ClientState::BlockUpload { .. }) {
+            SDOCoder::try_decode_rx_block_segment(self.node_id, SDORole::Client, frame)?
+        } else {
+            SDOCoder::try_decode_rx_frame(self.node_id, SDORole::Client, frame)?
+        };
+
+        self.deadline = None;
+        match self.handle_response(response) {
+            Ok(request) => request.map(|request| self.encode(request)),
+            Err(code) => self.abort(code),
+        }
+    }
+
+    /// Aborts the transfer in progress if the server has not answered within
+    /// the timeout at `now`, returning the abort request to send.
+    pub fn tick(&mut self, now: Instant) -> Option<EncodedCANOpenFrame> {
+        if !self.is_busy() {
+            return None;
+        }
+        match self.deadline {
+            Some(deadline) if now >= deadline => self.abort(SdoAbortCode::SDOProtocolTimedOut),
+            Some(_) => None,
+            None => {
+                self.deadline = Some(now + self.timeout);
+                None
+            }
+        }
+    }
+
+    /// Returns the uploaded data once the transfer has completed.
+    pub fn poll_upload(&mut self) -> nb::Result<Vec<u8>, SdoError> {
+        self.take_result()
+    }
+
+    /// Returns once the download has completed.
+    pub fn poll_download(&mut self) -> nb::Result<(), SdoError> {
+        self.take_result().map(|_| ())
+    }
+
+    /// Reads `id` from `node`, blocking until the transfer has completed.
+    pub fn upload<B: Can>(
+        &mut self,
+        bus: &mut B,
+        clock: &impl Clock,
+        node: NodeId,
+        id: EntryId,
+    ) -> Result<Vec<u8>, SdoError> {
+        let request = self.start_upload(node, id)?;
+        self.run_blocking(bus, clock, request)?;
+        nb::block!(self.poll_upload())
+    }
+
+    /// Writes `data` to `id` on `node`, blocking until the transfer has completed.
+    pub fn download<B: Can>(
+        &mut self,
+        bus: &mut B,
+        clock: &impl Clock,
+        node: NodeId,
+        id: EntryId,
+        data: &[u8],
+    ) -> Result<(), SdoError> {
+        let request = self.start_download(node, id, data)?;
+        self.run_blocking(bus, clock, request)?;
+        nb::block!(self.poll_download())
+    }
+
+    /// Reads `id` from `node` with a block upload, blocking until the transfer
+    /// has completed.
+    pub fn block_upload<B: Can>(
+        &mut self,
+        bus: &mut B,
+        clock: &impl Clock,
+        node: NodeId,
+        id: EntryId,
+    ) -> Result<Vec<u8>, SdoError> {
+        let request = self.start_block_upload(node, id)?;
+        self.run_blocking(bus, clock, request)?;
+        nb::block!(self.poll_upload())
+    }
+
+    /// Writes `data` to `id` on `node` with a block download, blocking until
+    /// the transfer has completed.
+    pub fn block_download<B: Can>(
+        &mut self,
+        bus: &mut B,
+        clock: &impl Clock,
+        node: NodeId,
+        id: EntryId,
+        data: &[u8],
+    ) -> Result<(), SdoError> {
+        let request = self.start_block_download(node, id, data)?;
+        self.run_blocking(bus, clock, request)?;
+        nb::block!(self.poll_download())
+    }
+
+    fn run_blocking<B: Can>(
+        &mut self,
+        bus: &mut B,
+        clock: &impl Clock,
+        request: EncodedCANOpenFrame,
+    ) -> Result<(), SdoError> {
+        let mut request = Some(request);
+        while request.is_some() || self.is_busy() {
+            let result = match request.take() {
+                Some(frame) => Self::transmit(bus, &frame).map(|_| request = self.poll_frame()),
+                None => match bus.receive() {
+                    Ok(frame) => {
+                        request = self.process_frame(&frame);
+                        Ok(())
+                    }
+                    Err(nb::Error::WouldBlock) => {
+                        request = self.tick(clock.now());
+                        Ok(())
+                    }
+                    Err(nb::Error::Other(err)) => Err(SdoError::Bus(err.kind())),
+                },
+            };
+            if result.is_err() {
+                self.state = ClientState::Idle;
+                return result;
+            }
+        }
+        Ok(())
+    }
+
+    fn transmit<B: Can>(bus: &mut B, frame: &EncodedCANOpenFrame) -> Result<(), SdoError> {
+        let frame = B::Frame::new(frame.id(), frame.data()).unwrap();
+        nb::block!(bus.transmit(&frame))
+            .map(|_| ())
+            .map_err(|err| SdoError::Bus(err.kind()))
+    }
+
+    fn start(&mut self, node: NodeId) -> Result<(), SdoError> {
+        if self.is_busy() {
+            return Err(SdoError::Busy);
+        }
+        self.node_id = node;
+        self.deadline = None;
+        Ok(())
+    }
+
+    fn take_result(&mut self) -> nb::Result<Vec<u8>, SdoError> {
+        match core::mem::replace(&mut self.state, ClientState::Idle) {
+            ClientState::Done(result) => result.map_err(nb::Error::Other),
+            ClientState::Idle => Err(nb::Error::Other(SdoError::Idle)),
+            state => {
+                self.state = state;
+                Err(nb::Error::WouldBlock)
+            }
+        }
+    }
+
+    fn encode(&self, frame: SdoFrame) -> EncodedCANOpenFrame {
+        SDOCoder::encode_tx_frame(Self::tx_id(self.node_id), frame)
+    }
+
+    fn current_id(&self) -> EntryId {
+        match &self.state {
+            ClientState::ExpeditedDownload { id }
+            | ClientState::SegmentedDownloadInitiate { id, .. }
+            | ClientState::SegmentedDownload { id, .. }
+            | ClientState::UploadInitiate { id }
+            | ClientState::SegmentedUpload { id, .. }
+            | ClientState::BlockDownloadInitiate { id, .. }
+            | ClientState::BlockDownload { id, .. }
+            | ClientState::BlockDownloadEnd { id }
+            | ClientState::BlockUploadInitiate { id }
+            | ClientState::BlockUpload { id, .. }
+            | ClientState::BlockUploadEnd { id, .. } => *id,
+            ClientState::Idle | ClientState::Done(_) => EntryId::new(0, 0),
+        }
+    }
+
+    fn handle_response(&mut self, response: SdoFrame) -> Result<Option<SdoFrame>, SdoAbortCode> {
+        if let SdoFrame::Abort { code, .. } = response {
+            self.state = ClientState::Done(Err(SdoError::ServerAbort(code)));
+            return Ok(None);
+        }
+        if self.is_block_transfer() {
+            return self.handle_block_response(response);
+        }
+
+        match (&mut self.state, response) {
+            (
+                ClientState::ExpeditedDownload { id },
+                SdoFrame::DownloadInitiateResponse { id: response_id },
+            ) if *id == response_id => {
+                self.state = ClientState::Done(Ok(Vec::new()));
+                Ok(None)
+            }
+            (
+                ClientState::SegmentedDownloadInitiate { id, data },
+                SdoFrame::DownloadInitiateResponse { id: response_id },
+            ) if *id == response_id => {
+                self.state = ClientState::SegmentedDownload {
+                    id: *id,
+                    toggle: false,
+                    data: core::mem::take(data),
+                    offset: 0,
+                };
+                Ok(self.next_download_segment())
+            }
+            (
+                ClientState::SegmentedDownload {
+                    toggle,
+                    offset,
+                    data,
+                    ..
+                },
+                SdoFrame::SegmentedDownloadResponse {
+                    toggle: response_toggle,
+                },
+            ) => {
+                if *toggle != response_toggle {
+                    return Err(SdoAbortCode::ToggleBitNotAlternated);
+                }
+                *toggle = !*toggle;
+                if *offset == data.len() {
+                    self.state = ClientState::Done(Ok(Vec::new()));
+                    return Ok(None);
+                }
+                Ok(self.next_download_segment())
+            }
+            (
+                ClientState::UploadInitiate { id },
+                SdoFrame::ExpeditedUploadResponse {
+                    id: response_id,
+                    payload,
+                },
+            ) if *id == response_id => {
+                self.state = ClientState::Done(Ok(payload.to_vec()));
+                Ok(None)
+            }
+            (
+                ClientState::UploadInitiate { id },
+                SdoFrame::SegmentedUploadInitiateResponse {
+                    id: response_id,
+                    size,
+                },
+            ) if *id == response_id => {
+                self.state = ClientState::SegmentedUpload {
+                    id: *id,
+                    toggle: false,
+                    size: size as usize,
+                    data: Vec::with_capacity((size as usize).min(MAX_RESERVED_SIZE)),
+                };
+                Ok(Some(SdoFrame::SegmentedUploadRequest { toggle: false }))
+            }
+            (
+                ClientState::SegmentedUpload {
+                    toggle, size, data, ..
+                },
+                SdoFrame::SegmentedUploadResponse {
+                    toggle: response_toggle,
+                    last,
+                    payload,
+                },
+            ) => {
+                if *toggle != response_toggle {
+                    return Err(SdoAbortCode::ToggleBitNotAlternated);
+                }
+                data.extend_from_slice(&payload);
+                if *size != 0 && data.len() > *size {
+                    return Err(SdoAbortCode::TooLong);
+                }
+                if !last {
+                    *toggle = !*toggle;
+                    return Ok(Some(SdoFrame::SegmentedUploadRequest { toggle: *toggle }));
+                }
+                if data.len() < *size {
+                    return Err(SdoAbortCode::TooShort);
+                }
+                self.state = ClientState::Done(Ok(core::mem::take(data)));
+                Ok(None)
+            }
+            _ => Err(SdoAbortCode::CommandSpecifierError),
+        }
+    }
+
+    fn is_block_transfer(&self) -> bool {
+        matches!(
+            self.state,
+            ClientState::BlockDownloadInitiate { .. }
+                | ClientState::BlockDownload { .. }
+                | ClientState::BlockDownloadEnd { .. }
+                | ClientState::BlockUploadInitiate { .. }
+                | ClientState::BlockUpload { .. }
+                | ClientState::BlockUploadEnd { .. }
+        )
+    }
+
+    fn handle_block_response(
+        &mut self,
+        response: SdoFrame,
+    ) -> Result<Option<SdoFrame>, SdoAbortCode> {
+        match (&mut self.state, response) {
+            (
+                ClientState::BlockDownloadInitiate { id, data },
+                SdoFrame::BlockDownloadInitiateResponse {
+                    id: response_id,
+                    crc_supported,
+                    block_size,
+                },
+            ) if *id == response_id => {
+                if block_size == 0 || block_size > MAX_BLOCK_SIZE {
+                    return Err(SdoAbortCode::InvalidBlockSize);
+                }
+                self.state = ClientState::BlockDownload {
+                    id: *id,
+                    crc_enabled: crc_supported,
+                    data: core::mem::take(data),
+                    block_size,
+                    block_start: 0,
+                    seqno: 0,
+                    sending: true,
+                };
+                Ok(self.next_block_segment())
+            }
+            (
+                ClientState::BlockDownload {
+                    id,
+                    crc_enabled,
+                    data,
+                    block_size,
+                    block_start,
+                    seqno,
+                    sending: sending @ false,
+                },
+                SdoFrame::BlockDownloadAck {
+                    ack_seq,
+                    block_size: new_block_size,
+                },
+            ) => {
+                if ack_seq > *seqno {
+                    return Err(SdoAbortCode::InvalidSequenceNumber);
+                }
+                if new_block_size == 0 || new_block_size > MAX_BLOCK_SIZE {
+                    return Err(SdoAbortCode::InvalidBlockSize);
+                }
+
+                let sent_all = *block_start + *seqno as usize * 7 >= data.len();
+                if sent_all && ack_seq == *seqno {
+                    let request = SdoFrame::BlockDownloadEndRequest {
+                        unused_bytes: unused_block_bytes(data.len()),
+                        crc: if *crc_enabled {
+                            crc16_ccitt(0, data)
+                        } else {
+                            0
+                        },
+                    };
+                    self.state = ClientState::BlockDownloadEnd { id: *id };
+                    return Ok(Some(request));
+                }
+
+                *block_start += ack_seq as usize * 7;
+                *block_size = new_block_size;
+                *seqno = 0;
+                *sending = true;
+                Ok(self.next_block_segment())
+            }
+            (ClientState::BlockDownloadEnd { .. }, SdoFrame::BlockDownloadEndResponse) => {
+                self.state = ClientState::Done(Ok(Vec::new()));
+                Ok(None)
+            }
+            (
+                ClientState::BlockUploadInitiate { id },
+                SdoFrame::BlockUploadInitiateResponse {
+                    id: response_id,
+                    crc_supported,
+                    size,
+                },
+            ) if *id == response_id => {
+                self.state = ClientState::BlockUpload {
+                    id: *id,
+                    crc_enabled: crc_supported,
+                    size: size as usize,
+                    data: Vec::with_capacity((size as usize).min(MAX_RESERVED_SIZE)),
+                    expected_seqno: 1,
+                    complete: false,
+                };
+                Ok(Some(SdoFrame::BlockUploadStartRequest))
+            }
+            (
+                ClientState::BlockUploadInitiate { id },
+                response @ (SdoFrame::ExpeditedUploadResponse { .. }
+                | SdoFrame::SegmentedUploadInitiateResponse { .. }),
+            ) => {
+                // The server switched to the regular upload protocol as the
+                // value is below the protocol switch threshold.
+                self.state = ClientState::UploadInitiate { id: *id };
+                self.handle_response(response)
+            }
+            (
+                ClientState::BlockUpload {
+                    id,
+                    crc_enabled,
+                    size,
+                    data,
+                    expected_seqno,
+                    complete,
+                },
+                SdoFrame::BlockSegment {
+                    seqno,
+                    last,
+                    payload,
+                },
+            ) => {
+                // Segments received out of order are dropped, the server
+                // repeats them after the acknowledgement of the sub-block.
+                if seqno == *expected_seqno {
+                    data.extend_from_slice(&payload);
+                    *expected_seqno += 1;
+                    *complete = last;
+                }
+
+                if !last && seqno < self.block_size {
+                    return Ok(None);
+                }
+
+                let ack_seq = *expected_seqno - 1;
+                *expected_seqno = 1;
+                if *complete {
+                    self.state = ClientState::BlockUploadEnd {
+                        id: *id,
+                        crc_enabled: *crc_enabled,
+                        size: *size,
+                        data: core::mem::take(data),
+                    };
+                }
+                Ok(Some(SdoFrame::BlockUploadAck {
+                    ack_seq,
+                    block_size: self.block_size,
+                }))
+            }
+            (
+                ClientState::BlockUploadEnd {
+                    crc_enabled,
+                    size,
+                    data,
+                    ..
+                },
+                SdoFrame::BlockUploadEndRequest { unused_bytes, crc },
+            ) => {
+                let data_len = data
+                    .len()
+                    .checked_sub(unused_bytes as usize)
+                    .ok_or(SdoAbortCode::TooShort)?;
+                data.truncate(data_len);
+
+                if *crc_enabled && crc16_ccitt(0, data) != crc {
+                    return Err(SdoAbortCode::CRCError);
+                }
+                if *size != 0 && data.len() > *size {
+                    return Err(SdoAbortCode::TooLong);
+                }
+                if data.len() < *size {
+                    return Err(SdoAbortCode::TooShort);
+                }
+
+                self.state = ClientState::Done(Ok(core::mem::take(data)));
+                Ok(Some(SdoFrame::BlockUploadEndResponse))
+            }
+            _ => Err(SdoAbortCode::CommandSpecifierError),
+        }
+    }
+
+    fn next_block_segment(&mut self) -> Option<SdoFrame> {
+        let ClientState::BlockDownload {
+            data,
+            block_size,
+            block_start,
+            seqno,
+            sending: sending @ true,
+            ..
+        } = &mut self.state
+        else {
+            return None;
+        };
+
+        let start = *block_start + *seqno as usize * 7;
+        let end = (start + 7).min(data.len());
+        let mut payload = heapless::Vec::from_slice(&data[start..end]).unwrap();
+        payload.resize(7, 0).unwrap();
+        let last = end == data.len();
+        *seqno += 1;
+
+        if last || *seqno == *block_size {
+            *sending = false;
+        }
+
+        Some(SdoFrame::BlockSegment {
+            seqno: *seqno,
+            last,
+            payload,
+        })
+    }
+
+    fn next_download_segment(&mut self) -> Option<SdoFrame> {
+        let ClientState::SegmentedDownload {
+            toggle,
+            data,
+            offset,
+            ..
+        } = &mut self.state
+        else {
+            return None;
+        };
+
+        let end = (*offset + 7).min(data.len());
+        let payload = heapless::Vec::from_slice(&data[*offset..end]).unwrap();
+        *offset = end;
+
+        Some(SdoFrame::SegmentedDownloadRequest {
+            toggle: *toggle,
+            last: end == data.len(),
+            payload,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+    use std::cell::RefCell;
+
+    use embedded_can::Frame;
+    use heapless::Vec;
+
+    use crate::frame::EncodedCANOpenFrame;
+    use crate::node::NodeId;
+    use crate::object_dictionary::{
+        AccessType, DomainHandler, EntryId, FixedBytes, ObjectDictionary, Variable, VariableType,
+    };
+    use crate::parameter_coder::*;
+    use crate::sdo::crc::crc16_ccitt;
+    use crate::sdo::{SdoAbortCode, SdoServer};
+    use crate::test_utils::{TestClock, VirtualBus};
+    use crate::time::Instant;
+
+    use super::{SdoClient, SdoError};
+
+    fn test_od() -> ObjectDictionary<5, 0, 0> {
+        ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [],
+            Vec::from_slice(&[
+                Variable::new(
+                    EntryId::new(0x2000, 0x1),
+                    "rw_u16",
+                    VariableType::UInt16(0x1234, &DefaultU16Coder),
+                    AccessType::ReadWrite,
+                ),
+                Variable::new(
+                    EntryId::new(0x2000, 0x2),
+                    "rw_f64",
+                    VariableType::Float64(1.5, &DefaultF64Coder),
+                    AccessType::ReadWrite,
+                ),
+                Variable::new(
+                    EntryId::new(0x2001, 0x0),
+                    "ro_u8",
+                    VariableType::UInt8(7, &DefaultU8Coder),
+                    AccessType::ReadOnly,
+                ),
+                Variable::new(
+                    EntryId::new(0x2002, 0x0),
+                    "rw_string",
+                    VariableType::VisibleString(
+                        FixedBytes::from_slice(b"Pump controller").unwrap(),
+                    ),
+                    AccessType::ReadWrite,
+                ),
+                Variable::new(
+                    EntryId::new(0x2003, 0x0),
+                    "rw_domain",
+                    VariableType::Domain(Box::leak(Box::new(TestDomain::default()))),
+                    AccessType::ReadWrite,
+                ),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::new(5).unwrap(),
+        )
+    }
+
+    /// A domain larger than any SDO server buffer.
+    #[derive(Default)]
+    struct TestDomain {
+        data: RefCell<std::vec::Vec<u8>>,
+    }
+
+    impl DomainHandler for TestDomain {
+        fn size(&self) -> usize {
+            self.data.borrow().len()
+        }
+
+        fn capacity(&self) -> usize {
+            1000
+        }
+
+        fn read(&self, offset: usize, buf: &mut [u8]) -> Result<(), SdoAbortCode> {
+            buf.copy_from_slice(&self.data.borrow()[offset..offset + buf.len()]);
+            Ok(())
+        }
+
+        fn write(&self, offset: usize, data: &[u8]) -> Result<(), SdoAbortCode> {
+            let mut buffer = self.data.borrow_mut();
+            buffer.truncate(offset);
+            buffer.extend_from_slice(data);
+            Ok(())
+        }
+
+        fn finish_write(&self, size: usize) -> Result<(), SdoAbortCode> {
+            match self.data.borrow().len() == size {
+                true => Ok(()),
+                false => Err(SdoAbortCode::GeneralError),
+            }
+        }
+    }
+
+    fn clock() -> TestClock {
+        TestClock::default()
+    }
+
+    fn virtual_bus() -> VirtualBus<5, 0, 0> {
+        VirtualBus::new(test_od())
+    }
+
+    #[test]
+    fn test_expedited_upload() {
+        let mut od = test_od();
+        let mut server = SdoServer::new();
+        let mut client = SdoClient::new();
+
+        let request = client
+            .start_upload(NodeId::new(5).unwrap(), EntryId::new(0x2000, 0x1))
+            .unwrap();
+        assert_eq!(request.data(), [2 << 5, 0x00, 0x20, 0x01, 0, 0, 0, 0]);
+        assert_eq!(client.poll_upload(), Err(nb::Error::WouldBlock));
+
+        let response = server.process_frame(&mut od, &request).unwrap();
+        assert!(client.process_frame(&response).is_none());
+        assert_eq!(client.poll_upload(), Ok(std::vec![0x34, 0x12]));
+        assert_eq!(client.poll_upload(), Err(nb::Error::Other(SdoError::Idle)));
+    }
+
+    #[test]
+    fn test_blocking_segmented_transfers() {
+        let mut bus = virtual_bus();
+        let mut client = SdoClient::new();
+        let node = NodeId::new(5).unwrap();
+        let id = EntryId::new(0x2000, 0x2);
+
+        assert_eq!(
+            client.upload(&mut bus, &clock(), node, id),
+            Ok(1.5f64.to_le_bytes().to_vec())
+        );
+        assert_eq!(
+            client.download(&mut bus, &clock(), node, id, &(-8.25f64).to_le_bytes()),
+            Ok(())
+        );
+        assert_eq!(
+            client.upload(&mut bus, &clock(), node, id),
+            Ok((-8.25f64).to_le_bytes().to_vec())
+        );
+    }
+
+    #[test]
+    fn test_blocking_expedited_download() {
+        let mut bus = virtual_bus();
+        let mut client = SdoClient::new();
+        let node = NodeId::new(5).unwrap();
+        let id = EntryId::new(0x2000, 0x1);
+
+        assert_eq!(
+            client.download(&mut bus, &clock(), node, id, &[0xCD, 0xAB]),
+            Ok(())
+        );
+        assert_eq!(
+            client.upload(&mut bus, &clock(), node, id),
+            Ok(std::vec![0xCD, 0xAB])
+        );
+    }
+
+    #[test]
+    fn test_server_abort() {
+        let mut bus = virtual_bus();
+        let mut client = SdoClient::new();
+
+        assert_eq!(
+            client.download(
+                &mut bus,
+                &clock(),
+                NodeId::new(5).unwrap(),
+                EntryId::new(0x2001, 0x0),
+                &[0x1]
+            ),
+            Err(SdoError::ServerAbort(SdoAbortCode::ReadOnlyError))
+        );
+        assert!(!client.is_busy());
+    }
+
+    #[test]
+    fn test_silent_server_times_out() {
+        let mut bus = virtual_bus();
+        let mut client = SdoClient::new();
+        client.set_timeout(Duration::from_millis(5));
+
+        assert_eq!(
+            client.upload(
+                &mut bus,
+                &clock(),
+                NodeId::new(6).unwrap(),
+                EntryId::new(0x2000, 0x1)
+            ),
+            Err(SdoError::LocalAbort(SdoAbortCode::SDOProtocolTimedOut))
+        );
+        assert!(!client.is_busy());
+        assert_eq!(
+            bus.tx.last().unwrap(),
+            &[4 << 5, 0x00, 0x20, 0x01, 0x00, 0x00, 0x04, 0x05]
+        );
+    }
+
+    #[test]
+    fn test_tick_timeout() {
+        let mut client = SdoClient::new();
+        client.set_timeout(Duration::from_millis(10));
+        client
+            .start_upload(NodeId::new(5).unwrap(), EntryId::new(0x2000, 0x2))
+            .unwrap();
+        assert!(client.tick(Instant::from_millis(0)).is_none());
+
+        // A response restarts the timeout.
+        let initiate =
+            EncodedCANOpenFrame::new(0x585, &[(2 << 5) + 1, 0x00, 0x20, 0x02, 8, 0, 0, 0]);
+        client.process_frame(&initiate).unwrap();
+        assert!(client.tick(Instant::from_millis(8)).is_none());
+        assert!(client.tick(Instant::from_millis(17)).is_none());
+
+        let abort = client.tick(Instant::from_millis(18)).unwrap();
+        assert_eq!(
+            abort.data(),
+            [4 << 5, 0x00, 0x20, 0x02, 0x00, 0x00, 0x04, 0x05]
+        );
+        assert_eq!(
+            client.poll_upload(),
+            Err(nb::Error::Other(SdoError::LocalAbort(
+                SdoAbortCode::SDOProtocolTimedOut
+            )))
+        );
+    }
+
+    #[test]
+    fn test_upload_without_indicated_size() {
+        let mut client = SdoClient::new();
+        client
+            .start_upload(NodeId::new(5).unwrap(), EntryId::new(0x2000, 0x2))
+            .unwrap();
+
+        let initiate = EncodedCANOpenFrame::new(0x585, &[2 << 5, 0x00, 0x20, 0x02, 0, 0, 0, 0]);
+        let request = client.process_frame(&initiate).unwrap();
+        assert_eq!(request.data(), [3 << 5, 0, 0, 0, 0, 0, 0, 0]);
+
+        let segment = EncodedCANOpenFrame::new(0x585, &[0, 1, 2, 3, 4, 5, 6, 7]);
+        client.process_frame(&segment).unwrap();
+        let segment =
+            EncodedCANOpenFrame::new(0x585, &[(1 << 4) + (5 << 1) + 1, 8, 9, 0, 0, 0, 0, 0]);
+        assert!(client.process_frame(&segment).is_none());
+        assert_eq!(
+            client.poll_upload(),
+            Ok(std::vec![1, 2, 3, 4, 5, 6, 7, 8, 9])
+        );
+    }
+
+    #[test]
+    fn test_huge_indicated_size() {
+        let mut client = SdoClient::new();
+        client
+            .start_upload(NodeId::new(5).unwrap(), EntryId::new(0x2000, 0x2))
+            .unwrap();
+
+        // Nothing is reserved beyond a bounded size up front.
+        let initiate = EncodedCANOpenFrame::new(
+            0x585,
+            &[(2 << 5) + 1, 0x00, 0x20, 0x02, 0xFF, 0xFF, 0xFF, 0xFF],
+        );
+        let request = client.process_frame(&initiate).unwrap();
+        assert_eq!(request.data(), [3 << 5, 0, 0, 0, 0, 0, 0, 0]);
+        let segment = EncodedCANOpenFrame::new(0x585, &[(6 << 1) + 1, 1, 0, 0, 0, 0, 0, 0]);
+        let request = client.process_frame(&segment).unwrap();
+        assert_eq!(
+            request.data(),
+            [4 << 5, 0x00, 0x20, 0x02, 0x13, 0x00, 0x07, 0x06]
+        );
+        assert_eq!(
+            client.poll_upload(),
+            Err(nb::Error::Other(SdoError::LocalAbort(
+                SdoAbortCode::TooShort
+            )))
+        );
+    }
+
+    #[test]
+    fn test_toggle_error_aborts() {
+        let mut client = SdoClient::new();
+        client
+            .start_upload(NodeId::new(5).unwrap(), EntryId::new(0x2000, 0x2))
+            .unwrap();
+
+        let initiate =
+            EncodedCANOpenFrame::new(0x585, &[(2 << 5) + 1, 0x00, 0x20, 0x02, 8, 0, 0, 0]);
+        let request = client.process_frame(&initiate).unwrap();
+        assert_eq!(request.data(), [3 << 5, 0, 0, 0, 0, 0, 0, 0]);
+
+        let segment = EncodedCANOpenFrame::new(0x585, &[1 << 4, 1, 2, 3, 4, 5, 6, 7]);
+        let request = client.process_frame(&segment).unwrap();
+        assert_eq!(
+            request.data(),
+            [4 << 5, 0x00, 0x20, 0x02, 0x00, 0x00, 0x03, 0x05]
+        );
+        assert_eq!(
+            client.poll_upload(),
+            Err(nb::Error::Other(SdoError::LocalAbort(
+                SdoAbortCode::ToggleBitNotAlternated
+            )))
+        );
+    }
+
+    #[test]
+    fn test_busy() {
+        let mut client = SdoClient::new();
+        client
+            .start_upload(NodeId::new(5).unwrap(), EntryId::new(0x2000, 0x1))
+            .unwrap();
+        assert_eq!(
+            client
+                .start_upload(NodeId::new(5).unwrap(), EntryId::new(0x2000, 0x1))
+                .err(),
+            Some(SdoError::Busy)
+        );
+    }
+
+    #[test]
+    fn test_blocking_block_transfers() {
+        let mut bus = virtual_bus();
+        let mut client = SdoClient::new();
+        let node = NodeId::new(5).unwrap();
+        let id = EntryId::new(0x2000, 0x2);
+
+        assert_eq!(
+            client.block_upload(&mut bus, &clock(), node, id),
+            Ok(1.5f64.to_le_bytes().to_vec())
+        );
+        assert_eq!(
+            client.block_download(&mut bus, &clock(), node, id, &(-8.25f64).to_le_bytes()),
+            Ok(())
+        );
+
+        client.set_block_size(1);
+        assert_eq!(
+            client.block_upload(&mut bus, &clock(), node, id),
+            Ok((-8.25f64).to_le_bytes().to_vec())
+        );
+        assert!(bus.server.is_idle());
+    }
+
+    #[test]
+    fn test_block_upload_protocol_switch() {
+        let mut bus = virtual_bus();
+        let mut client = SdoClient::new();
+        client.set_protocol_switch_threshold(8);
+
+        assert_eq!(
+            client.block_upload(
+                &mut bus,
+                &clock(),
+                NodeId::new(5).unwrap(),
+                EntryId::new(0x2000, 0x2)
+            ),
+            Ok(1.5f64.to_le_bytes().to_vec())
+        );
+    }
+
+    #[test]
+    fn test_string_transfers() {
+        let mut bus = virtual_bus();
+        let mut client = SdoClient::new();
+        let node = NodeId::new(5).unwrap();
+        let id = EntryId::new(0x2002, 0x0);
+
+        assert_eq!(
+            client.upload(&mut bus, &clock(), node, id),
+            Ok(b"Pump controller".to_vec())
+        );
+        assert_eq!(
+            client.download(&mut bus, &clock(), node, id, b"Fan"),
+            Ok(())
+        );
+        assert_eq!(
+            client.block_upload(&mut bus, &clock(), node, id),
+            Ok(b"Fan".to_vec())
+        );
+        assert_eq!(
+            client.block_download(&mut bus, &clock(), node, id, b"Cooling fan, left side"),
+            Ok(())
+        );
+        assert_eq!(
+            client.upload(&mut bus, &clock(), node, id),
+            Ok(b"Cooling fan, left side".to_vec())
+        );
+        assert_eq!(
+            client.download(&mut bus, &clock(), node, id, &[b'x'; 33]),
+            Err(SdoError::ServerAbort(SdoAbortCode::TooLong))
+        );
+    }
+
+    #[test]
+    fn test_domain_transfers() {
+        let mut bus = virtual_bus();
+        let mut client = SdoClient::new();
+        let node = NodeId::new(5).unwrap();
+        let id = EntryId::new(0x2003, 0x0);
+        let image: std::vec::Vec<u8> = (0..900).map(|i| (i * 7) as u8).collect();
+
+        assert_eq!(client.upload(&mut bus, &clock(), node, id), Ok(std::vec![]));
+        assert_eq!(
+            client.block_download(&mut bus, &clock(), node, id, &image),
+            Ok(())
+        );
+        assert_eq!(
+            client.upload(&mut bus, &clock(), node, id),
+            Ok(image.clone())
+        );
+        assert_eq!(
+            client.download(&mut bus, &clock(), node, id, &image[..100]),
+            Ok(())
+        );
+        assert_eq!(
+            client.block_upload(&mut bus, &clock(), node, id),
+            Ok(image[..100].to_vec())
+        );
+        assert_eq!(
+            client.block_download(&mut bus, &clock(), node, id, &[0; 1001]),
+            Err(SdoError::ServerAbort(SdoAbortCode::TooLong))
+        );
+        assert!(bus.server.is_idle());
+    }
+
+    #[test]
+    fn test_block_download_segments() {
+        let mut client = SdoClient::new();
+        let data = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        client
+            .start_block_download(NodeId::new(5).unwrap(), EntryId::new(0x2000, 0x2), &data)
+            .unwrap();
+
+        let response =
+            EncodedCANOpenFrame::new(0x585, &[(5 << 5) + (1 << 2), 0x00, 0x20, 0x02, 2, 0, 0, 0]);
+        let first = client.process_frame(&response).unwrap();
+        assert_eq!(first.data(), [1, 1, 2, 3, 4, 5, 6, 7]);
+        let second = client.poll_frame().unwrap();
+        assert_eq!(second.data(), [0x80 + 2, 8, 9, 0, 0, 0, 0, 0]);
+        assert!(client.poll_frame().is_none());
+
+        // The server only received the first segment, the second is repeated.
+        let ack = EncodedCANOpenFrame::new(0x585, &[(5 << 5) + 2, 1, 127, 0, 0, 0, 0, 0]);
+        let repeated = client.process_frame(&ack).unwrap();
+        assert_eq!(repeated.data(), [0x80 + 1, 8, 9, 0, 0, 0, 0, 0]);
+
+        let ack = EncodedCANOpenFrame::new(0x585, &[(5 << 5) + 2, 1, 127, 0, 0, 0, 0, 0]);
+        let end = client.process_frame(&ack).unwrap();
+        let crc = crc16_ccitt(0, &data).to_le_bytes();
+        assert_eq!(
+            end.data(),
+            [(6 << 5) + (5 << 2) + 1, crc[0], crc[1], 0, 0, 0, 0, 0]
+        );
+
+        let end_response = EncodedCANOpenFrame::new(0x585, &[(5 << 5) + 1, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(client.process_frame(&end_response).is_none());
+        assert_eq!(client.poll_download(), Ok(()));
+    }
+}
@@ -0,0 +1,32 @@
+/// Updates a CRC-16 (CCITT polynomial x^16 + x^12 + x^5 + 1, initial value 0)
+/// with `data`, as used to protect SDO block transfers.
+pub(crate) fn crc16_ccitt(mut crc: u16, data: &[u8]) -> u16 {
+    for byte in data {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::crc16_ccitt;
+
+    #[test]
+    fn test_check_value() {
+        assert_eq!(crc16_ccitt(0, b"123456789"), 0x31C3);
+    }
+
+    #[test]
+    fn test_incremental() {
+        let crc = crc16_ccitt(0, b"1234");
+        assert_eq!(crc16_ccitt(crc, b"56789"), 0x31C3);
+        assert_eq!(crc16_ccitt(0, &[]), 0);
+    }
+}
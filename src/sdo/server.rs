@@ -0,0 +1,1422 @@
+use embedded_can::{Frame, Id, StandardId};
+use heapless::Vec;
+
+use crate::{
+    concise_dcf::CONCISE_DCF_CAPACITY,
+    frame::EncodedCANOpenFrame,
+    node::NodeId,
+    object_dictionary::{DomainHandler, EntryId, ObjectDictionary, STRING_CAPACITY},
+};
+
+use super::{
+    crc::crc16_ccitt, unused_block_bytes, SDOCoder, SDORole, SdoAbortCode, SdoFrame, MAX_BLOCK_SIZE,
+};
+
+/// Largest value the server will buffer for an upload. Domains are streamed
+/// instead.
+const SDO_BUFFER_SIZE: usize = STRING_CAPACITY;
+/// Largest value the server will buffer for a download, which may be a
+/// concise DCF. Domains are streamed instead.
+const DOWNLOAD_BUFFER_SIZE: usize = CONCISE_DCF_CAPACITY;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BlockUploadPhase {
+    Initiated,
+    Sending,
+    WaitingForAck,
+    Ending,
+}
+
+/// A value being uploaded: buffered when the transfer starts, or read from a
+/// domain segment by segment.
+enum UploadData {
+    Buffered(Vec<u8, SDO_BUFFER_SIZE>),
+    Domain {
+        domain: &'static dyn DomainHandler,
+        size: usize,
+    },
+}
+
+impl UploadData {
+    fn new<const ENTRY_COUNT: usize, const RPDO_COUNT: usize, const TPDO_COUNT: usize>(
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        id: EntryId,
+    ) -> Result<Self, SdoAbortCode> {
+        Ok(match od.readable_domain(id)? {
+            Some(domain) => Self::Domain {
+                domain,
+                size: domain.size(),
+            },
+            None => Self::Buffered(od.read_raw(id)?),
+        })
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Buffered(data) => data.len(),
+            Self::Domain { size, .. } => *size,
+        }
+    }
+
+    /// Reads the bytes of the segment starting at `offset`.
+    fn segment(&self, offset: usize) -> Result<Vec<u8, 7>, SdoAbortCode> {
+        let end = (offset + 7).min(self.len());
+        match self {
+            Self::Buffered(data) => Ok(Vec::from_slice(&data[offset..end]).unwrap()),
+            Self::Domain { domain, .. } => {
+                let mut segment = Vec::new();
+                segment.resize(end - offset, 0).unwrap();
+                domain.read(offset, &mut segment)?;
+                Ok(segment)
+            }
+        }
+    }
+
+    fn crc(&self) -> Result<u16, SdoAbortCode> {
+        (0..self.len()).step_by(7).try_fold(0, |crc, offset| {
+            Ok(crc16_ccitt(crc, &self.segment(offset)?))
+        })
+    }
+}
+
+/// A value being downloaded: buffered and written once complete, or written
+/// into a domain as segments are received.
+///
+/// The buffer is kept inline so that the server does not allocate.
+#[allow(clippy::large_enum_variant)]
+enum DownloadData {
+    Buffered(Vec<u8, DOWNLOAD_BUFFER_SIZE>),
+    Domain {
+        domain: &'static dyn DomainHandler,
+        len: usize,
+    },
+}
+
+impl DownloadData {
+    fn new<const ENTRY_COUNT: usize, const RPDO_COUNT: usize, const TPDO_COUNT: usize>(
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        id: EntryId,
+    ) -> Self {
+        match od.domain(id) {
+            Some(domain) => Self::Domain { domain, len: 0 },
+            None => Self::Buffered(Vec::new()),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Self::Buffered(buffer) => buffer.len(),
+            Self::Domain { len, .. } => *len,
+        }
+    }
+
+    fn push(&mut self, data: &[u8]) -> Result<(), SdoAbortCode> {
+        match self {
+            Self::Buffered(buffer) => buffer
+                .extend_from_slice(data)
+                .map_err(|_| SdoAbortCode::TooLong),
+            Self::Domain { .. } if data.is_empty() => Ok(()),
+            Self::Domain { domain, len } => {
+                if *len + data.len() > domain.capacity() {
+                    return Err(SdoAbortCode::TooLong);
+                }
+                domain.write(*len, data)?;
+                *len += data.len();
+                Ok(())
+            }
+        }
+    }
+
+    fn finish<const ENTRY_COUNT: usize, const RPDO_COUNT: usize, const TPDO_COUNT: usize>(
+        &self,
+        od: &mut ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        id: EntryId,
+    ) -> Result<(), SdoAbortCode> {
+        match self {
+            Self::Buffered(buffer) => od.write_raw(id, buffer),
+            Self::Domain { domain, len } => domain.finish_write(*len),
+        }
+    }
+}
+
+enum ServerState {
+    Idle,
+    SegmentedDownload {
+        id: EntryId,
+        toggle: bool,
+        size: usize,
+        data: DownloadData,
+    },
+    SegmentedUpload {
+        id: EntryId,
+        toggle: bool,
+        data: UploadData,
+        offset: usize,
+    },
+    BlockDownload {
+        id: EntryId,
+        crc_enabled: bool,
+        size: usize,
+        expected_seqno: u8,
+        complete: bool,
+        data: DownloadData,
+        /// The last segment received, which may end with padding bytes.
+        pending: Vec<u8, 7>,
+        crc: u16,
+    },
+    BlockUpload {
+        id: EntryId,
+        crc_enabled: bool,
+        data: UploadData,
+        block_size: u8,
+        block_start: usize,
+        seqno: u8,
+        phase: BlockUploadPhase,
+    },
+}
+
+/// Serves SDO requests from a single client against an [`ObjectDictionary`].
+///
+/// Expedited transfers are answered immediately, segmented and block transfers
+/// are tracked across requests until the last segment has been exchanged.
+///
+/// During a block upload a single request is answered by a whole sub-block:
+/// after sending the response, the remaining segments are retrieved with
+/// [`SdoServer::poll_frame`] until it returns `None`.
+pub struct SdoServer {
+    state: ServerState,
+}
+
+impl Default for SdoServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SdoServer {
+    pub fn new() -> Self {
+        Self {
+            state: ServerState::Idle,
+        }
+    }
+
+    /// Returns `true` when no segmented or block transfer is in progress.
+    pub fn is_idle(&self) -> bool {
+        matches!(self.state, ServerState::Idle)
+    }
+
+    /// Drops any transfer in progress without notifying the client.
+    pub fn reset(&mut self) {
+        self.state = ServerState::Idle;
+    }
+
+    pub fn tx_id(node_id: NodeId) -> Id {
+        Id::Standard(StandardId::new(SDOCoder::TX_ID_OFFSET + node_id.raw() as u16).unwrap())
+    }
+
+    /// Decodes a received CAN frame addressed to this node's SDO server and
+    /// returns the encoded response, if any.
+    pub fn process_frame<
+        const ENTRY_COUNT: usize,
+        const RPDO_COUNT: usize,
+        const TPDO_COUNT: usize,
+    >(
+        &mut self,
+        od: &mut ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        frame: &impl Frame,
+    ) -> Option<EncodedCANOpenFrame> {
+        let request = if self.expects_block_segment() {
+            SDOCoder::try_decode_rx_block_segment(od.node_id(), SDORole::Server, frame)?
+        } else {
+            SDOCoder::try_decode_rx_frame(od.node_id(), SDORole::Server, frame)?
+        };
+        let response = self.handle_request(od, request)?;
+        Some(SDOCoder::encode_tx_frame(
+            Self::tx_id(od.node_id()),
+            response,
+        ))
+    }
+
+    /// Returns the next segment of the sub-block being uploaded, if any.
+    pub fn poll_frame(&mut self, node_id: NodeId) -> Option<EncodedCANOpenFrame> {
+        self.next_block_segment()
+            .map(|segment| SDOCoder::encode_tx_frame(Self::tx_id(node_id), segment))
+    }
+
+    /// Returns the next decoded segment of the sub-block being uploaded, if any.
+    ///
+    /// The transfer is aborted if the segment cannot be read from a domain.
+    pub fn next_block_segment(&mut self) -> Option<SdoFrame> {
+        let ServerState::BlockUpload {
+            id,
+            data,
+            block_size,
+            block_start,
+            seqno,
+            phase: phase @ BlockUploadPhase::Sending,
+            ..
+        } = &mut self.state
+        else {
+            return None;
+        };
+
+        let start = *block_start + *seqno as usize * 7;
+        let mut payload = match data.segment(start) {
+            Ok(payload) => payload,
+            Err(code) => {
+                let id = *id;
+                self.state = ServerState::Idle;
+                return Some(SdoFrame::Abort { id, code });
+            }
+        };
+        let last = start + payload.len() == data.len();
+        payload.resize(7, 0).unwrap();
+        *seqno += 1;
+
+        if last || *seqno == *block_size {
+            *phase = BlockUploadPhase::WaitingForAck;
+        }
+
+        Some(SdoFrame::BlockSegment {
+            seqno: *seqno,
+            last,
+            payload,
+        })
+    }
+
+    fn expects_block_segment(&self) -> bool {
+        matches!(
+            self.state,
+            ServerState::BlockDownload {
+                complete: false,
+                ..
+            }
+        )
+    }
+
+    /// Executes a decoded client request and returns the server's response.
+    ///
+    /// `None` is returned when the request does not warrant a response, i.e.
+    /// when the client aborts the transfer.
+    pub fn handle_request<
+        const ENTRY_COUNT: usize,
+        const RPDO_COUNT: usize,
+        const TPDO_COUNT: usize,
+    >(
+        &mut self,
+        od: &mut ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        request: SdoFrame,
+    ) -> Option<SdoFrame> {
+        let result = match request {
+            SdoFrame::Abort { .. } => {
+                self.state = ServerState::Idle;
+                return None;
+            }
+            SdoFrame::UploadRequest { id } => self.initiate_upload(od, id).map(Some),
+            SdoFrame::SegmentedUploadRequest { toggle } => self.upload_segment(toggle).map(Some),
+            SdoFrame::ExpeditedDownloadRequest { id, payload } => od
+                .write_raw(id, &payload)
+                .map(|_| Some(SdoFrame::DownloadInitiateResponse { id }))
+                .map_err(|code| (id, code)),
+            SdoFrame::SegmentedDownloadInitiateRequest { id, size } => {
+                self.initiate_download(od, id, size as usize).map(Some)
+            }
+            SdoFrame::SegmentedDownloadRequest {
+                toggle,
+                last,
+                payload,
+            } => self.download_segment(od, toggle, last, &payload).map(Some),
+            SdoFrame::BlockDownloadInitiateRequest {
+                id,
+                crc_supported,
+                size,
+            } => self
+                .initiate_block_download(od, id, crc_supported, size as usize)
+                .map(Some),
+            SdoFrame::BlockSegment {
+                seqno,
+                last,
+                payload,
+            } => self.block_download_segment(seqno, last, &payload),
+            SdoFrame::BlockDownloadEndRequest { unused_bytes, crc } => {
+                self.end_block_download(od, unused_bytes, crc).map(Some)
+            }
+            SdoFrame::BlockUploadInitiateRequest {
+                id,
+                crc_supported,
+                block_size,
+                protocol_switch_threshold,
+            } => self
+                .initiate_block_upload(od, id, crc_supported, block_size, protocol_switch_threshold)
+                .map(Some),
+            SdoFrame::BlockUploadStartRequest => self.start_block_upload(),
+            SdoFrame::BlockUploadAck {
+                ack_seq,
+                block_size,
+            } => self.block_upload_ack(ack_seq, block_size),
+            SdoFrame::BlockUploadEndResponse => self.end_block_upload(),
+            _ => Err((self.current_id(), SdoAbortCode::CommandSpecifierError)),
+        };
+
+        match result {
+            Ok(response) => response,
+            Err((id, code)) => {
+                self.state = ServerState::Idle;
+                Some(SdoFrame::Abort { id, code })
+            }
+        }
+    }
+
+    fn current_id(&self) -> EntryId {
+        match &self.state {
+            ServerState::Idle => EntryId::new(0, 0),
+            ServerState::SegmentedDownload { id, .. }
+            | ServerState::SegmentedUpload { id, .. }
+            | ServerState::BlockDownload { id, .. }
+            | ServerState::BlockUpload { id, .. } => *id,
+        }
+    }
+
+    fn initiate_upload<
+        const ENTRY_COUNT: usize,
+        const RPDO_COUNT: usize,
+        const TPDO_COUNT: usize,
+    >(
+        &mut self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        id: EntryId,
+    ) -> Result<SdoFrame, (EntryId, SdoAbortCode)> {
+        let data = UploadData::new(od, id).map_err(|code| (id, code))?;
+
+        // Empty values are uploaded in a single, empty segment.
+        if (1..=4).contains(&data.len()) {
+            let payload = data.segment(0).map_err(|code| (id, code))?;
+            self.state = ServerState::Idle;
+            return Ok(SdoFrame::ExpeditedUploadResponse {
+                id,
+                payload: Vec::from_slice(&payload).unwrap(),
+            });
+        }
+
+        let size = data.len() as u32;
+        self.state = ServerState::SegmentedUpload {
+            id,
+            toggle: false,
+            data,
+            offset: 0,
+        };
+        Ok(SdoFrame::SegmentedUploadInitiateResponse { id, size })
+    }
+
+    fn upload_segment(&mut self, toggle: bool) -> Result<SdoFrame, (EntryId, SdoAbortCode)> {
+        let ServerState::SegmentedUpload {
+            id,
+            toggle: expected_toggle,
+            data,
+            offset,
+        } = &mut self.state
+        else {
+            return Err((self.current_id(), SdoAbortCode::CommandSpecifierError));
+        };
+
+        if toggle != *expected_toggle {
+            return Err((*id, SdoAbortCode::ToggleBitNotAlternated));
+        }
+
+        let payload = data.segment(*offset).map_err(|code| (*id, code))?;
+        *offset += payload.len();
+        let last = *offset == data.len();
+        *expected_toggle = !*expected_toggle;
+
+        if last {
+            self.state = ServerState::Idle;
+        }
+
+        Ok(SdoFrame::SegmentedUploadResponse {
+            toggle,
+            last,
+            payload,
+        })
+    }
+
+    fn initiate_download<
+        const ENTRY_COUNT: usize,
+        const RPDO_COUNT: usize,
+        const TPDO_COUNT: usize,
+    >(
+        &mut self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        id: EntryId,
+        size: usize,
+    ) -> Result<SdoFrame, (EntryId, SdoAbortCode)> {
+        od.check_download_size(id, size)
+            .map_err(|code| (id, code))?;
+        if size > DOWNLOAD_BUFFER_SIZE && od.domain(id).is_none() {
+            return Err((id, SdoAbortCode::OutOfMemory));
+        }
+
+        self.state = ServerState::SegmentedDownload {
+            id,
+            toggle: false,
+            size,
+            data: DownloadData::new(od, id),
+        };
+        Ok(SdoFrame::DownloadInitiateResponse { id })
+    }
+
+    fn download_segment<
+        const ENTRY_COUNT: usize,
+        const RPDO_COUNT: usize,
+        const TPDO_COUNT: usize,
+    >(
+        &mut self,
+        od: &mut ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        toggle: bool,
+        last: bool,
+        payload: &[u8],
+    ) -> Result<SdoFrame, (EntryId, SdoAbortCode)> {
+        let ServerState::SegmentedDownload {
+            id,
+            toggle: expected_toggle,
+            size,
+            data,
+        } = &mut self.state
+        else {
+            return Err((self.current_id(), SdoAbortCode::CommandSpecifierError));
+        };
+        let id = *id;
+
+        if toggle != *expected_toggle {
+            return Err((id, SdoAbortCode::ToggleBitNotAlternated));
+        }
+        if data.len() + payload.len() > *size {
+            return Err((id, SdoAbortCode::TooLong));
+        }
+        data.push(payload).map_err(|code| (id, code))?;
+        *expected_toggle = !*expected_toggle;
+
+        if last {
+            if data.len() < *size {
+                return Err((id, SdoAbortCode::TooShort));
+            }
+            data.finish(od, id).map_err(|code| (id, code))?;
+            self.state = ServerState::Idle;
+        }
+
+        Ok(SdoFrame::SegmentedDownloadResponse { toggle })
+    }
+
+    fn initiate_block_download<
+        const ENTRY_COUNT: usize,
+        const RPDO_COUNT: usize,
+        const TPDO_COUNT: usize,
+    >(
+        &mut self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        id: EntryId,
+        crc_supported: bool,
+        size: usize,
+    ) -> Result<SdoFrame, (EntryId, SdoAbortCode)> {
+        // The size is optional in block downloads.
+        if size == 0 {
+            od.writable_size(id).map_err(|code| (id, code))?;
+        } else {
+            od.check_download_size(id, size)
+                .map_err(|code| (id, code))?;
+        }
+        if size > DOWNLOAD_BUFFER_SIZE && od.domain(id).is_none() {
+            return Err((id, SdoAbortCode::OutOfMemory));
+        }
+
+        self.state = ServerState::BlockDownload {
+            id,
+            crc_enabled: crc_supported,
+            size,
+            expected_seqno: 1,
+            complete: false,
+            data: DownloadData::new(od, id),
+            pending: Vec::new(),
+            crc: 0,
+        };
+        Ok(SdoFrame::BlockDownloadInitiateResponse {
+            id,
+            crc_supported: true,
+            block_size: MAX_BLOCK_SIZE,
+        })
+    }
+
+    fn block_download_segment(
+        &mut self,
+        seqno: u8,
+        last: bool,
+        payload: &[u8],
+    ) -> Result<Option<SdoFrame>, (EntryId, SdoAbortCode)> {
+        let ServerState::BlockDownload {
+            id,
+            expected_seqno,
+            complete: complete @ false,
+            data,
+            pending,
+            crc,
+            ..
+        } = &mut self.state
+        else {
+            return Err((self.current_id(), SdoAbortCode::CommandSpecifierError));
+        };
+
+        // Segments received out of order are dropped, the client repeats
+        // them after the acknowledgement of the sub-block. The last segment
+        // received is held back until the padding bytes are known.
+        if seqno == *expected_seqno {
+            data.push(pending).map_err(|code| (*id, code))?;
+            *crc = crc16_ccitt(*crc, pending);
+            *pending = Vec::from_slice(payload).map_err(|_| (*id, SdoAbortCode::TooLong))?;
+            *expected_seqno += 1;
+            *complete = last;
+        }
+
+        if !last && seqno < MAX_BLOCK_SIZE {
+            return Ok(None);
+        }
+
+        let ack_seq = *expected_seqno - 1;
+        *expected_seqno = 1;
+        Ok(Some(SdoFrame::BlockDownloadAck {
+            ack_seq,
+            block_size: MAX_BLOCK_SIZE,
+        }))
+    }
+
+    fn end_block_download<
+        const ENTRY_COUNT: usize,
+        const RPDO_COUNT: usize,
+        const TPDO_COUNT: usize,
+    >(
+        &mut self,
+        od: &mut ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        unused_bytes: u8,
+        crc: u16,
+    ) -> Result<SdoFrame, (EntryId, SdoAbortCode)> {
+        let ServerState::BlockDownload {
+            id,
+            crc_enabled,
+            size,
+            complete: true,
+            data,
+            pending,
+            crc: data_crc,
+            ..
+        } = &mut self.state
+        else {
+            return Err((self.current_id(), SdoAbortCode::CommandSpecifierError));
+        };
+        let id = *id;
+
+        let pending_len = pending
+            .len()
+            .checked_sub(unused_bytes as usize)
+            .ok_or((id, SdoAbortCode::TooShort))?;
+        pending.truncate(pending_len);
+        data.push(pending).map_err(|code| (id, code))?;
+        *data_crc = crc16_ccitt(*data_crc, pending);
+
+        if *crc_enabled && *data_crc != crc {
+            return Err((id, SdoAbortCode::CRCError));
+        }
+        if *size != 0 && data.len() > *size {
+            return Err((id, SdoAbortCode::TooLong));
+        }
+        if *size != 0 && data.len() < *size {
+            return Err((id, SdoAbortCode::TooShort));
+        }
+        od.check_download_size(id, data.len())
+            .map_err(|code| (id, code))?;
+
+        data.finish(od, id).map_err(|code| (id, code))?;
+        self.state = ServerState::Idle;
+        Ok(SdoFrame::BlockDownloadEndResponse)
+    }
+
+    fn initiate_block_upload<
+        const ENTRY_COUNT: usize,
+        const RPDO_COUNT: usize,
+        const TPDO_COUNT: usize,
+    >(
+        &mut self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        id: EntryId,
+        crc_supported: bool,
+        block_size: u8,
+        protocol_switch_threshold: u8,
+    ) -> Result<SdoFrame, (EntryId, SdoAbortCode)> {
+        if block_size == 0 || block_size > MAX_BLOCK_SIZE {
+            return Err((id, SdoAbortCode::InvalidBlockSize));
+        }
+
+        let data = UploadData::new(od, id).map_err(|code| (id, code))?;
+        if data.len() <= protocol_switch_threshold as usize {
+            return self.initiate_upload(od, id);
+        }
+
+        let size = data.len() as u32;
+        self.state = ServerState::BlockUpload {
+            id,
+            crc_enabled: crc_supported,
+            data,
+            block_size,
+            block_start: 0,
+            seqno: 0,
+            phase: BlockUploadPhase::Initiated,
+        };
+        Ok(SdoFrame::BlockUploadInitiateResponse {
+            id,
+            crc_supported: true,
+            size,
+        })
+    }
+
+    fn start_block_upload(&mut self) -> Result<Option<SdoFrame>, (EntryId, SdoAbortCode)> {
+        let ServerState::BlockUpload {
+            phase: phase @ BlockUploadPhase::Initiated,
+            ..
+        } = &mut self.state
+        else {
+            return Err((self.current_id(), SdoAbortCode::CommandSpecifierError));
+        };
+
+        *phase = BlockUploadPhase::Sending;
+        Ok(self.next_block_segment())
+    }
+
+    fn block_upload_ack(
+        &mut self,
+        ack_seq: u8,
+        new_block_size: u8,
+    ) -> Result<Option<SdoFrame>, (EntryId, SdoAbortCode)> {
+        let ServerState::BlockUpload {
+            id,
+            crc_enabled,
+            data,
+            block_size,
+            block_start,
+            seqno,
+            phase: phase @ BlockUploadPhase::WaitingForAck,
+        } = &mut self.state
+        else {
+            return Err((self.current_id(), SdoAbortCode::CommandSpecifierError));
+        };
+
+        if ack_seq > *seqno {
+            return Err((*id, SdoAbortCode::InvalidSequenceNumber));
+        }
+        if new_block_size == 0 || new_block_size > MAX_BLOCK_SIZE {
+            return Err((*id, SdoAbortCode::InvalidBlockSize));
+        }
+
+        let sent_all = *block_start + *seqno as usize * 7 >= data.len();
+        if sent_all && ack_seq == *seqno {
+            let crc = if *crc_enabled {
+                data.crc().map_err(|code| (*id, code))?
+            } else {
+                0
+            };
+            *phase = BlockUploadPhase::Ending;
+            return Ok(Some(SdoFrame::BlockUploadEndRequest {
+                unused_bytes: unused_block_bytes(data.len()),
+                crc,
+            }));
+        }
+
+        *block_start += ack_seq as usize * 7;
+        *block_size = new_block_size;
+        *seqno = 0;
+        *phase = BlockUploadPhase::Sending;
+        Ok(self.next_block_segment())
+    }
+
+    fn end_block_upload(&mut self) -> Result<Option<SdoFrame>, (EntryId, SdoAbortCode)> {
+        let ServerState::BlockUpload {
+            phase: BlockUploadPhase::Ending,
+            ..
+        } = self.state
+        else {
+            return Err((self.current_id(), SdoAbortCode::CommandSpecifierError));
+        };
+
+        self.state = ServerState::Idle;
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_can::Frame;
+    use heapless::Vec;
+
+    use crate::frame::EncodedCANOpenFrame;
+    use crate::node::NodeId;
+    use crate::object_dictionary::{AccessType, EntryId, ObjectDictionary, Variable, VariableType};
+    use crate::parameter_coder::*;
+    use crate::sdo::crc::crc16_ccitt;
+    use crate::sdo::{SdoAbortCode, SdoFrame};
+
+    use super::SdoServer;
+
+    fn test_od() -> ObjectDictionary<4, 0, 0> {
+        ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [],
+            Vec::from_slice(&[
+                Variable::new(
+                    EntryId::new(0x2000, 0x1),
+                    "rw_u32",
+                    VariableType::UInt32(0x1234_5678, &DefaultU32Coder),
+                    AccessType::ReadWrite,
+                ),
+                Variable::new(
+                    EntryId::new(0x2000, 0x2),
+                    "rw_u64",
+                    VariableType::UInt64(0x0102_0304_0506_0708, &DefaultU64Coder),
+                    AccessType::ReadWrite,
+                ),
+                Variable::new(
+                    EntryId::new(0x2001, 0x0),
+                    "ro_u8",
+                    VariableType::UInt8(7, &DefaultU8Coder),
+                    AccessType::ReadOnly,
+                ),
+                Variable::new(
+                    EntryId::new(0x2002, 0x0),
+                    "wo_i16",
+                    VariableType::Int16(-2, &DefaultI16Coder),
+                    AccessType::WriteOnly,
+                ),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::new(5).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_expedited_upload() {
+        let mut od = test_od();
+        let mut server = SdoServer::new();
+
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::UploadRequest {
+                id: EntryId::new(0x2000, 0x1),
+            },
+        );
+        assert_eq!(
+            response,
+            Some(SdoFrame::ExpeditedUploadResponse {
+                id: EntryId::new(0x2000, 0x1),
+                payload: Vec::from_slice(&[0x78, 0x56, 0x34, 0x12]).unwrap()
+            })
+        );
+        assert!(server.is_idle());
+    }
+
+    #[test]
+    fn test_expedited_download() {
+        let mut od = test_od();
+        let mut server = SdoServer::new();
+
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::ExpeditedDownloadRequest {
+                id: EntryId::new(0x2000, 0x1),
+                payload: Vec::from_slice(&[0x1, 0x2, 0x3, 0x4]).unwrap(),
+            },
+        );
+        assert_eq!(
+            response,
+            Some(SdoFrame::DownloadInitiateResponse {
+                id: EntryId::new(0x2000, 0x1)
+            })
+        );
+        assert_eq!(
+            od.read_raw(EntryId::new(0x2000, 0x1)).unwrap(),
+            [0x1, 0x2, 0x3, 0x4]
+        );
+    }
+
+    #[test]
+    fn test_segmented_upload() {
+        let mut od = test_od();
+        let mut server = SdoServer::new();
+        let id = EntryId::new(0x2000, 0x2);
+
+        let response = server.handle_request(&mut od, SdoFrame::UploadRequest { id });
+        assert_eq!(
+            response,
+            Some(SdoFrame::SegmentedUploadInitiateResponse { id, size: 8 })
+        );
+
+        let response =
+            server.handle_request(&mut od, SdoFrame::SegmentedUploadRequest { toggle: false });
+        assert_eq!(
+            response,
+            Some(SdoFrame::SegmentedUploadResponse {
+                toggle: false,
+                last: false,
+                payload: Vec::from_slice(&[0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02]).unwrap()
+            })
+        );
+
+        let response =
+            server.handle_request(&mut od, SdoFrame::SegmentedUploadRequest { toggle: true });
+        assert_eq!(
+            response,
+            Some(SdoFrame::SegmentedUploadResponse {
+                toggle: true,
+                last: true,
+                payload: Vec::from_slice(&[0x01]).unwrap()
+            })
+        );
+        assert!(server.is_idle());
+    }
+
+    #[test]
+    fn test_segmented_download() {
+        let mut od = test_od();
+        let mut server = SdoServer::new();
+        let id = EntryId::new(0x2000, 0x2);
+
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::SegmentedDownloadInitiateRequest { id, size: 8 },
+        );
+        assert_eq!(response, Some(SdoFrame::DownloadInitiateResponse { id }));
+
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::SegmentedDownloadRequest {
+                toggle: false,
+                last: false,
+                payload: Vec::from_slice(&[0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77]).unwrap(),
+            },
+        );
+        assert_eq!(
+            response,
+            Some(SdoFrame::SegmentedDownloadResponse { toggle: false })
+        );
+
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::SegmentedDownloadRequest {
+                toggle: true,
+                last: true,
+                payload: Vec::from_slice(&[0x88]).unwrap(),
+            },
+        );
+        assert_eq!(
+            response,
+            Some(SdoFrame::SegmentedDownloadResponse { toggle: true })
+        );
+        assert!(server.is_idle());
+        assert_eq!(
+            od.read_raw(id).unwrap(),
+            [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88]
+        );
+    }
+
+    #[test]
+    fn test_odd_width_transfers() {
+        let mut od: ObjectDictionary<2, 0, 0> = ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [],
+            Vec::from_slice(&[
+                Variable::new(
+                    EntryId::new(0x2010, 0x0),
+                    "rw_i24",
+                    VariableType::Int24(-2, &DefaultI24Coder),
+                    AccessType::ReadWrite,
+                ),
+                Variable::new(
+                    EntryId::new(0x2011, 0x0),
+                    "rw_u48",
+                    VariableType::UInt48(0x0605_0403_0201, &DefaultU48Coder),
+                    AccessType::ReadWrite,
+                ),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::new(5).unwrap(),
+        );
+        let mut server = SdoServer::new();
+        let i24 = EntryId::new(0x2010, 0x0);
+        let u48 = EntryId::new(0x2011, 0x0);
+
+        let response = server.handle_request(&mut od, SdoFrame::UploadRequest { id: i24 });
+        assert_eq!(
+            response,
+            Some(SdoFrame::ExpeditedUploadResponse {
+                id: i24,
+                payload: Vec::from_slice(&[0xFE, 0xFF, 0xFF]).unwrap()
+            })
+        );
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::ExpeditedDownloadRequest {
+                id: i24,
+                payload: Vec::from_slice(&[0x1, 0x2, 0x3, 0x4]).unwrap(),
+            },
+        );
+        assert_eq!(
+            response,
+            Some(SdoFrame::Abort {
+                id: i24,
+                code: SdoAbortCode::TooLong
+            })
+        );
+
+        let response = server.handle_request(&mut od, SdoFrame::UploadRequest { id: u48 });
+        assert_eq!(
+            response,
+            Some(SdoFrame::SegmentedUploadInitiateResponse { id: u48, size: 6 })
+        );
+        server.handle_request(&mut od, SdoFrame::SegmentedUploadRequest { toggle: false });
+        assert!(server.is_idle());
+
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::SegmentedDownloadInitiateRequest { id: u48, size: 6 },
+        );
+        assert_eq!(
+            response,
+            Some(SdoFrame::DownloadInitiateResponse { id: u48 })
+        );
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::SegmentedDownloadRequest {
+                toggle: false,
+                last: true,
+                payload: Vec::from_slice(&[0xFF, 0xEE, 0xDD, 0xCC, 0xBB, 0xAA]).unwrap(),
+            },
+        );
+        assert_eq!(
+            response,
+            Some(SdoFrame::SegmentedDownloadResponse { toggle: false })
+        );
+        assert_eq!(od.read_unsigned(u48), Some(0xAABB_CCDD_EEFF));
+    }
+
+    #[test]
+    fn test_segmented_download_toggle_error() {
+        let mut od = test_od();
+        let mut server = SdoServer::new();
+        let id = EntryId::new(0x2000, 0x2);
+
+        server.handle_request(
+            &mut od,
+            SdoFrame::SegmentedDownloadInitiateRequest { id, size: 8 },
+        );
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::SegmentedDownloadRequest {
+                toggle: true,
+                last: false,
+                payload: Vec::from_slice(&[0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77]).unwrap(),
+            },
+        );
+        assert_eq!(
+            response,
+            Some(SdoFrame::Abort {
+                id,
+                code: SdoAbortCode::ToggleBitNotAlternated
+            })
+        );
+        assert!(server.is_idle());
+        assert_eq!(
+            od.read_raw(id).unwrap(),
+            0x0102_0304_0506_0708u64.to_le_bytes()
+        );
+    }
+
+    #[test]
+    fn test_segmented_download_wrong_size() {
+        let mut od = test_od();
+        let mut server = SdoServer::new();
+        let id = EntryId::new(0x2000, 0x2);
+
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::SegmentedDownloadInitiateRequest { id, size: 9 },
+        );
+        assert_eq!(
+            response,
+            Some(SdoFrame::Abort {
+                id,
+                code: SdoAbortCode::TooLong
+            })
+        );
+    }
+
+    #[test]
+    fn test_access_errors() {
+        let mut od = test_od();
+        let mut server = SdoServer::new();
+
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::ExpeditedDownloadRequest {
+                id: EntryId::new(0x2001, 0x0),
+                payload: Vec::from_slice(&[0x1]).unwrap(),
+            },
+        );
+        assert_eq!(
+            response,
+            Some(SdoFrame::Abort {
+                id: EntryId::new(0x2001, 0x0),
+                code: SdoAbortCode::ReadOnlyError
+            })
+        );
+
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::UploadRequest {
+                id: EntryId::new(0x2002, 0x0),
+            },
+        );
+        assert_eq!(
+            response,
+            Some(SdoFrame::Abort {
+                id: EntryId::new(0x2002, 0x0),
+                code: SdoAbortCode::WriteOnlyError
+            })
+        );
+
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::ExpeditedDownloadRequest {
+                id: EntryId::new(0x2000, 0x1),
+                payload: Vec::from_slice(&[0x1, 0x2]).unwrap(),
+            },
+        );
+        assert_eq!(
+            response,
+            Some(SdoFrame::Abort {
+                id: EntryId::new(0x2000, 0x1),
+                code: SdoAbortCode::TooShort
+            })
+        );
+    }
+
+    #[test]
+    fn test_missing_entries() {
+        let mut od = test_od();
+        let mut server = SdoServer::new();
+
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::UploadRequest {
+                id: EntryId::new(0x3000, 0x0),
+            },
+        );
+        assert_eq!(
+            response,
+            Some(SdoFrame::Abort {
+                id: EntryId::new(0x3000, 0x0),
+                code: SdoAbortCode::ObjectDoesNotExist
+            })
+        );
+
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::UploadRequest {
+                id: EntryId::new(0x2000, 0x9),
+            },
+        );
+        assert_eq!(
+            response,
+            Some(SdoFrame::Abort {
+                id: EntryId::new(0x2000, 0x9),
+                code: SdoAbortCode::SubindexDoesNotExist
+            })
+        );
+    }
+
+    #[test]
+    fn test_unexpected_segment() {
+        let mut od = test_od();
+        let mut server = SdoServer::new();
+
+        let response =
+            server.handle_request(&mut od, SdoFrame::SegmentedUploadRequest { toggle: false });
+        assert_eq!(
+            response,
+            Some(SdoFrame::Abort {
+                id: EntryId::new(0, 0),
+                code: SdoAbortCode::CommandSpecifierError
+            })
+        );
+    }
+
+    #[test]
+    fn test_client_abort_resets_transfer() {
+        let mut od = test_od();
+        let mut server = SdoServer::new();
+        let id = EntryId::new(0x2000, 0x2);
+
+        server.handle_request(&mut od, SdoFrame::UploadRequest { id });
+        assert!(!server.is_idle());
+
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::Abort {
+                id,
+                code: SdoAbortCode::GeneralError,
+            },
+        );
+        assert_eq!(response, None);
+        assert!(server.is_idle());
+    }
+
+    #[test]
+    fn test_process_frame() {
+        let mut od = test_od();
+        let mut server = SdoServer::new();
+
+        let request = EncodedCANOpenFrame::new(0x605, &[2 << 5, 0x01, 0x20, 0x00, 0, 0, 0, 0]);
+        let response = server.process_frame(&mut od, &request).unwrap();
+        assert_eq!(response.id(), SdoServer::tx_id(NodeId::new(5).unwrap()));
+        assert_eq!(
+            response.data(),
+            [
+                (2 << 5) + (3 << 2) + (1 << 1) + 1,
+                0x01,
+                0x20,
+                0x00,
+                7,
+                0,
+                0,
+                0
+            ]
+        );
+
+        let other_node = EncodedCANOpenFrame::new(0x606, &[2 << 5, 0x01, 0x20, 0x00, 0, 0, 0, 0]);
+        assert!(server.process_frame(&mut od, &other_node).is_none());
+    }
+
+    #[test]
+    fn test_block_download() {
+        let mut od = test_od();
+        let mut server = SdoServer::new();
+        let id = EntryId::new(0x2000, 0x2);
+        let data = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88];
+
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::BlockDownloadInitiateRequest {
+                id,
+                crc_supported: true,
+                size: 8,
+            },
+        );
+        assert_eq!(
+            response,
+            Some(SdoFrame::BlockDownloadInitiateResponse {
+                id,
+                crc_supported: true,
+                block_size: 127
+            })
+        );
+
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::BlockSegment {
+                seqno: 1,
+                last: false,
+                payload: Vec::from_slice(&data[0..7]).unwrap(),
+            },
+        );
+        assert_eq!(response, None);
+
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::BlockSegment {
+                seqno: 2,
+                last: true,
+                payload: Vec::from_slice(&[0x88, 0, 0, 0, 0, 0, 0]).unwrap(),
+            },
+        );
+        assert_eq!(
+            response,
+            Some(SdoFrame::BlockDownloadAck {
+                ack_seq: 2,
+                block_size: 127
+            })
+        );
+
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::BlockDownloadEndRequest {
+                unused_bytes: 6,
+                crc: crc16_ccitt(0, &data),
+            },
+        );
+        assert_eq!(response, Some(SdoFrame::BlockDownloadEndResponse));
+        assert!(server.is_idle());
+        assert_eq!(od.read_raw(id).unwrap(), data);
+    }
+
+    #[test]
+    fn test_block_download_crc_error() {
+        let mut od = test_od();
+        let mut server = SdoServer::new();
+        let id = EntryId::new(0x2000, 0x1);
+
+        server.handle_request(
+            &mut od,
+            SdoFrame::BlockDownloadInitiateRequest {
+                id,
+                crc_supported: true,
+                size: 4,
+            },
+        );
+        server.handle_request(
+            &mut od,
+            SdoFrame::BlockSegment {
+                seqno: 1,
+                last: true,
+                payload: Vec::from_slice(&[1, 2, 3, 4, 0, 0, 0]).unwrap(),
+            },
+        );
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::BlockDownloadEndRequest {
+                unused_bytes: 3,
+                crc: 0xDEAD,
+            },
+        );
+        assert_eq!(
+            response,
+            Some(SdoFrame::Abort {
+                id,
+                code: SdoAbortCode::CRCError
+            })
+        );
+        assert_eq!(od.read_raw(id).unwrap(), 0x1234_5678u32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_block_upload() {
+        let mut od = test_od();
+        let mut server = SdoServer::new();
+        let id = EntryId::new(0x2000, 0x2);
+
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::BlockUploadInitiateRequest {
+                id,
+                crc_supported: true,
+                block_size: 1,
+                protocol_switch_threshold: 0,
+            },
+        );
+        assert_eq!(
+            response,
+            Some(SdoFrame::BlockUploadInitiateResponse {
+                id,
+                crc_supported: true,
+                size: 8
+            })
+        );
+
+        let response = server.handle_request(&mut od, SdoFrame::BlockUploadStartRequest);
+        assert_eq!(
+            response,
+            Some(SdoFrame::BlockSegment {
+                seqno: 1,
+                last: false,
+                payload: Vec::from_slice(&[0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02]).unwrap()
+            })
+        );
+        assert_eq!(server.next_block_segment(), None);
+
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::BlockUploadAck {
+                ack_seq: 1,
+                block_size: 127,
+            },
+        );
+        assert_eq!(
+            response,
+            Some(SdoFrame::BlockSegment {
+                seqno: 1,
+                last: true,
+                payload: Vec::from_slice(&[0x01, 0, 0, 0, 0, 0, 0]).unwrap()
+            })
+        );
+
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::BlockUploadAck {
+                ack_seq: 1,
+                block_size: 127,
+            },
+        );
+        assert_eq!(
+            response,
+            Some(SdoFrame::BlockUploadEndRequest {
+                unused_bytes: 6,
+                crc: crc16_ccitt(0, &0x0102_0304_0506_0708u64.to_le_bytes())
+            })
+        );
+
+        let response = server.handle_request(&mut od, SdoFrame::BlockUploadEndResponse);
+        assert_eq!(response, None);
+        assert!(server.is_idle());
+    }
+
+    #[test]
+    fn test_block_upload_protocol_switch() {
+        let mut od = test_od();
+        let mut server = SdoServer::new();
+        let id = EntryId::new(0x2000, 0x1);
+
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::BlockUploadInitiateRequest {
+                id,
+                crc_supported: true,
+                block_size: 127,
+                protocol_switch_threshold: 4,
+            },
+        );
+        assert_eq!(
+            response,
+            Some(SdoFrame::ExpeditedUploadResponse {
+                id,
+                payload: Vec::from_slice(&[0x78, 0x56, 0x34, 0x12]).unwrap()
+            })
+        );
+
+        let response = server.handle_request(
+            &mut od,
+            SdoFrame::BlockUploadInitiateRequest {
+                id,
+                crc_supported: true,
+                block_size: 0,
+                protocol_switch_threshold: 0,
+            },
+        );
+        assert_eq!(
+            response,
+            Some(SdoFrame::Abort {
+                id,
+                code: SdoAbortCode::InvalidBlockSize
+            })
+        );
+    }
+}
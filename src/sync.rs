@@ -0,0 +1,276 @@
+use core::time::Duration;
+
+use embedded_can::{Frame, Id, StandardId};
+use heapless::Vec;
+
+use crate::{
+    frame::EncodedCANOpenFrame,
+    object_dictionary::{EntryId, ObjectDictionary},
+    time::Instant,
+};
+
+/// COB-ID SYNC. Bit 30 set makes the node the SYNC producer.
+pub const COB_ID_SYNC: EntryId = EntryId::new(0x1005, 0x0);
+
+/// Communication cycle period, in microseconds. 0 disables SYNC production.
+pub const COMMUNICATION_CYCLE_PERIOD: EntryId = EntryId::new(0x1006, 0x0);
+
+/// Synchronous window length, in microseconds. 0 disables the window.
+pub const SYNCHRONOUS_WINDOW_LENGTH: EntryId = EntryId::new(0x1007, 0x0);
+
+/// Synchronous counter overflow value. 0 sends SYNC without a counter byte.
+pub const SYNCHRONOUS_COUNTER_OVERFLOW: EntryId = EntryId::new(0x1019, 0x0);
+
+/// Default COB-ID of the SYNC object.
+pub const DEFAULT_SYNC_ID: u16 = 0x080;
+
+const GENERATE_SYNC: u32 = 0x4000_0000;
+
+/// Reads the SYNC COB-ID and whether the node produces SYNC.
+fn sync_configuration<
+    const ENTRY_COUNT: usize,
+    const RPDO_COUNT: usize,
+    const TPDO_COUNT: usize,
+>(
+    od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+) -> (StandardId, bool) {
+    let raw = od
+        .read_unsigned(COB_ID_SYNC)
+        .unwrap_or(DEFAULT_SYNC_ID as u64) as u32;
+    let id = StandardId::new((raw & 0x7FF) as u16).unwrap();
+    (id, raw & GENERATE_SYNC != 0)
+}
+
+/// Receives SYNC frames and tracks the synchronous window.
+pub struct SyncConsumer {
+    last_sync: Option<Instant>,
+}
+
+impl Default for SyncConsumer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SyncConsumer {
+    pub fn new() -> Self {
+        Self { last_sync: None }
+    }
+
+    pub fn reset(&mut self) {
+        self.last_sync = None;
+    }
+
+    /// Handles a frame received at `now`. Returns `None` if it is not a
+    /// SYNC, else the SYNC counter, if the frame carried one.
+    pub fn process_frame<
+        const ENTRY_COUNT: usize,
+        const RPDO_COUNT: usize,
+        const TPDO_COUNT: usize,
+    >(
+        &mut self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        frame: &impl Frame,
+        now: Instant,
+    ) -> Option<Option<u8>> {
+        let (id, _) = sync_configuration(od);
+        if frame.is_remote_frame() || frame.id() != Id::Standard(id) || frame.dlc() > 1 {
+            return None;
+        }
+        self.last_sync = Some(now);
+        Some(frame.data().first().copied())
+    }
+
+    /// Notes a SYNC produced by this node at `now`.
+    pub fn produced(&mut self, now: Instant) {
+        self.last_sync = Some(now);
+    }
+
+    /// Returns whether `now` is within the synchronous window following the
+    /// last SYNC. Always true when no window is configured.
+    pub fn in_window<const ENTRY_COUNT: usize, const RPDO_COUNT: usize, const TPDO_COUNT: usize>(
+        &self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        now: Instant,
+    ) -> bool {
+        let window_us = od.read_unsigned(SYNCHRONOUS_WINDOW_LENGTH).unwrap_or(0);
+        if window_us == 0 {
+            return true;
+        }
+        self.last_sync.is_some_and(|last_sync| {
+            now.saturating_duration_since(last_sync) <= Duration::from_micros(window_us)
+        })
+    }
+}
+
+/// Produces SYNC frames every communication cycle period when enabled in
+/// [`COB_ID_SYNC`].
+pub struct SyncProducer {
+    period_us: u64,
+    next: Option<Instant>,
+    counter: u8,
+}
+
+impl Default for SyncProducer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SyncProducer {
+    pub fn new() -> Self {
+        Self {
+            period_us: 0,
+            next: None,
+            counter: 1,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Returns the SYNC frame to send at `now`, if one is due.
+    pub fn tick<const ENTRY_COUNT: usize, const RPDO_COUNT: usize, const TPDO_COUNT: usize>(
+        &mut self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        now: Instant,
+    ) -> Option<EncodedCANOpenFrame> {
+        let (id, generate) = sync_configuration(od);
+        let period_us = od.read_unsigned(COMMUNICATION_CYCLE_PERIOD).unwrap_or(0);
+        if period_us != self.period_us {
+            self.period_us = period_us;
+            self.next = None;
+        }
+        if !generate || period_us == 0 {
+            self.next = None;
+            return None;
+        }
+
+        let period = Duration::from_micros(period_us);
+        match self.next {
+            Some(next) if now < next => return None,
+            Some(next) => {
+                let following = next + period;
+                self.next = Some(if following > now {
+                    following
+                } else {
+                    now + period
+                });
+            }
+            None => self.next = Some(now + period),
+        }
+
+        let overflow = od.read_unsigned(SYNCHRONOUS_COUNTER_OVERFLOW).unwrap_or(0) as u8;
+        let data = if overflow > 1 {
+            if self.counter > overflow {
+                self.counter = 1;
+            }
+            let counter = self.counter;
+            self.counter = if counter >= overflow { 1 } else { counter + 1 };
+            Vec::from_slice(&[counter]).unwrap()
+        } else {
+            Vec::new()
+        };
+        Some(EncodedCANOpenFrame::from_vec_data(id, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_can::Frame;
+    use heapless::Vec;
+
+    use crate::frame::EncodedCANOpenFrame;
+    use crate::node::NodeId;
+    use crate::object_dictionary::{AccessType, ObjectDictionary, Variable, VariableType};
+    use crate::parameter_coder::{DefaultU32Coder, DefaultU8Coder};
+    use crate::time::Instant;
+
+    use super::{
+        SyncConsumer, SyncProducer, COB_ID_SYNC, COMMUNICATION_CYCLE_PERIOD,
+        SYNCHRONOUS_COUNTER_OVERFLOW, SYNCHRONOUS_WINDOW_LENGTH,
+    };
+
+    fn test_od(cob_id: u32, overflow: u8) -> ObjectDictionary<4, 0, 0> {
+        let u32_entry = |id, name, value| {
+            Variable::new(
+                id,
+                name,
+                VariableType::UInt32(value, &DefaultU32Coder),
+                AccessType::ReadWrite,
+            )
+        };
+        ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [],
+            Vec::from_slice(&[
+                u32_entry(COB_ID_SYNC, "COB-ID SYNC", cob_id),
+                u32_entry(
+                    COMMUNICATION_CYCLE_PERIOD,
+                    "Communication cycle period",
+                    10_000,
+                ),
+                u32_entry(
+                    SYNCHRONOUS_WINDOW_LENGTH,
+                    "Synchronous window length",
+                    2_000,
+                ),
+                Variable::new(
+                    SYNCHRONOUS_COUNTER_OVERFLOW,
+                    "Synchronous counter overflow value",
+                    VariableType::UInt8(overflow, &DefaultU8Coder),
+                    AccessType::ReadWrite,
+                ),
+            ])
+            .ok()
+            .unwrap(),
+            NodeId::new(1).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_consumer_and_window() {
+        let od = test_od(0x0000_0090, 0);
+        let mut consumer = SyncConsumer::new();
+        assert!(!consumer.in_window(&od, Instant::from_millis(0)));
+
+        let default_sync = EncodedCANOpenFrame::new(0x080, &[]);
+        assert_eq!(
+            consumer.process_frame(&od, &default_sync, Instant::from_millis(0)),
+            None
+        );
+        let sync = EncodedCANOpenFrame::new(0x090, &[7]);
+        assert_eq!(
+            consumer.process_frame(&od, &sync, Instant::from_millis(10)),
+            Some(Some(7))
+        );
+        assert!(consumer.in_window(&od, Instant::from_millis(12)));
+        assert!(!consumer.in_window(&od, Instant::from_micros(12_001)));
+    }
+
+    #[test]
+    fn test_producer_with_counter() {
+        let od = test_od(0x4000_0080, 3);
+        let mut producer = SyncProducer::new();
+        let mut counters = std::vec::Vec::new();
+        for ms in (0..=60).step_by(5) {
+            if let Some(frame) = producer.tick(&od, Instant::from_millis(ms)) {
+                assert_eq!(frame.id(), EncodedCANOpenFrame::new(0x080, &[]).id());
+                counters.push(frame.data()[0]);
+            }
+        }
+        assert_eq!(counters, [1, 2, 3, 1, 2, 3, 1]);
+    }
+
+    #[test]
+    fn test_producer_disabled() {
+        let od = test_od(0x0000_0080, 0);
+        let mut producer = SyncProducer::new();
+        assert!(producer.tick(&od, Instant::from_millis(0)).is_none());
+        assert!(producer.tick(&od, Instant::from_millis(100)).is_none());
+    }
+}
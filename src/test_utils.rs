@@ -0,0 +1,79 @@
+//! Fixtures shared by the tests of the services talking over a CAN bus.
+
+use core::cell::Cell;
+use std::collections::VecDeque;
+
+use embedded_can::{nb::Can, ErrorKind, Frame};
+
+use crate::frame::EncodedCANOpenFrame;
+use crate::object_dictionary::ObjectDictionary;
+use crate::sdo::SdoServer;
+use crate::time::{Clock, Instant};
+
+#[derive(Debug)]
+pub(crate) struct BusError;
+
+impl embedded_can::Error for BusError {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+/// A clock advancing by 1ms each time it is read.
+#[derive(Default)]
+pub(crate) struct TestClock(Cell<u64>);
+
+impl Clock for TestClock {
+    fn now(&self) -> Instant {
+        self.0.set(self.0.get() + 1);
+        Instant::from_millis(self.0.get())
+    }
+}
+
+/// A bus with a single SDO server attached to it, serving `od`.
+pub(crate) struct VirtualBus<
+    const ENTRY_COUNT: usize,
+    const RPDO_COUNT: usize,
+    const TPDO_COUNT: usize,
+> {
+    pub od: ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+    pub server: SdoServer,
+    pub rx: VecDeque<EncodedCANOpenFrame>,
+    /// Data of the frames transmitted to the server.
+    pub tx: Vec<Vec<u8>>,
+}
+
+impl<const ENTRY_COUNT: usize, const RPDO_COUNT: usize, const TPDO_COUNT: usize>
+    VirtualBus<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>
+{
+    pub fn new(od: ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>) -> Self {
+        Self {
+            od,
+            server: SdoServer::new(),
+            rx: VecDeque::new(),
+            tx: Vec::new(),
+        }
+    }
+}
+
+impl<const ENTRY_COUNT: usize, const RPDO_COUNT: usize, const TPDO_COUNT: usize> Can
+    for VirtualBus<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>
+{
+    type Frame = EncodedCANOpenFrame;
+    type Error = BusError;
+
+    fn transmit(&mut self, frame: &Self::Frame) -> nb::Result<Option<Self::Frame>, BusError> {
+        self.tx.push(frame.data().to_vec());
+        if let Some(response) = self.server.process_frame(&mut self.od, frame) {
+            self.rx.push_back(response);
+        }
+        while let Some(segment) = self.server.poll_frame(self.od.node_id()) {
+            self.rx.push_back(segment);
+        }
+        Ok(None)
+    }
+
+    fn receive(&mut self) -> nb::Result<Self::Frame, BusError> {
+        self.rx.pop_front().ok_or(nb::Error::WouldBlock)
+    }
+}
@@ -0,0 +1,66 @@
+use core::ops::Add;
+use core::time::Duration;
+
+/// A point in time of a monotonic clock, counted in microseconds from an
+/// arbitrary, fixed epoch such as system start.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant(u64);
+
+impl Instant {
+    pub const fn from_micros(micros: u64) -> Self {
+        Self(micros)
+    }
+
+    pub const fn from_millis(millis: u64) -> Self {
+        Self(millis * 1000)
+    }
+
+    pub const fn as_micros(&self) -> u64 {
+        self.0
+    }
+
+    /// Returns the time elapsed since `earlier`, or zero if `earlier` is later
+    /// than `self`.
+    pub fn saturating_duration_since(&self, earlier: Instant) -> Duration {
+        Duration::from_micros(self.0.saturating_sub(earlier.0))
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, rhs: Duration) -> Instant {
+        Instant(self.0.saturating_add(rhs.as_micros() as u64))
+    }
+}
+
+/// A source of monotonic time, implemented on top of a hardware timer on bare
+/// metal, or of the operating system clock on a host.
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// A [`Clock`] backed by [`std::time::Instant`].
+pub struct StdClock {
+    epoch: std::time::Instant,
+}
+
+impl Default for StdClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StdClock {
+    pub fn new() -> Self {
+        Self {
+            epoch: std::time::Instant::now(),
+        }
+    }
+}
+
+impl Clock for StdClock {
+    fn now(&self) -> Instant {
+        Instant::from_micros(self.epoch.elapsed().as_micros() as u64)
+    }
+}
@@ -0,0 +1,271 @@
+use embedded_can::{ExtendedId, Frame, Id, StandardId};
+use heapless::Vec;
+
+use crate::{
+    frame::EncodedCANOpenFrame,
+    object_dictionary::{EntryId, ObjectDictionary},
+    time::Instant,
+};
+
+/// COB-ID TIME. Bit 31 enables the consumer, bit 30 the producer.
+pub const COB_ID_TIME: EntryId = EntryId::new(0x1012, 0x0);
+
+/// Default value of [`COB_ID_TIME`]: consumer on 0x100.
+pub const DEFAULT_COB_ID_TIME: u32 = 0x8000_0100;
+
+const CONSUME: u32 = 0x8000_0000;
+const PRODUCE: u32 = 0x4000_0000;
+const EXTENDED_ID: u32 = 0x2000_0000;
+
+const MILLIS_PER_DAY: u64 = 86_400_000;
+
+/// Milliseconds between the Unix epoch and the CANopen epoch, 1984-01-01.
+const CANOPEN_EPOCH_UNIX_MILLIS: u64 = 441_763_200_000;
+
+/// The TIME_OF_DAY data type: milliseconds after midnight and days since
+/// 1984-01-01.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimeOfDay {
+    days: u16,
+    millis: u32,
+}
+
+impl TimeOfDay {
+    /// Returns `None` if `millis` is not within a day.
+    pub fn new(days: u16, millis: u32) -> Option<Self> {
+        if millis as u64 >= MILLIS_PER_DAY {
+            return None;
+        }
+        Some(Self { days, millis })
+    }
+
+    pub fn days(&self) -> u16 {
+        self.days
+    }
+
+    pub fn millis(&self) -> u32 {
+        self.millis
+    }
+
+    /// Returns `None` for times before 1984-01-01 or beyond the range of the
+    /// day counter.
+    pub fn from_unix_millis(unix_millis: u64) -> Option<Self> {
+        let since_epoch = unix_millis.checked_sub(CANOPEN_EPOCH_UNIX_MILLIS)?;
+        Self::from_millis_since_epoch(since_epoch)
+    }
+
+    pub fn to_unix_millis(&self) -> u64 {
+        CANOPEN_EPOCH_UNIX_MILLIS + self.millis_since_epoch()
+    }
+
+    /// Returns the time `millis` later, or `None` if it overflows the day
+    /// counter.
+    pub fn checked_add_millis(&self, millis: u64) -> Option<Self> {
+        Self::from_millis_since_epoch(self.millis_since_epoch().checked_add(millis)?)
+    }
+
+    /// Decodes the 6 byte encoding. The 4 reserved bits are ignored.
+    pub fn from_bytes(bytes: [u8; 6]) -> Option<Self> {
+        let millis = u32::from_le_bytes(bytes[0..4].try_into().unwrap()) & 0x0FFF_FFFF;
+        let days = u16::from_le_bytes(bytes[4..6].try_into().unwrap());
+        Self::new(days, millis)
+    }
+
+    pub fn to_bytes(&self) -> [u8; 6] {
+        let mut bytes = [0; 6];
+        bytes[0..4].copy_from_slice(&self.millis.to_le_bytes());
+        bytes[4..6].copy_from_slice(&self.days.to_le_bytes());
+        bytes
+    }
+
+    fn millis_since_epoch(&self) -> u64 {
+        self.days as u64 * MILLIS_PER_DAY + self.millis as u64
+    }
+
+    fn from_millis_since_epoch(millis: u64) -> Option<Self> {
+        let days = u16::try_from(millis / MILLIS_PER_DAY).ok()?;
+        Self::new(days, (millis % MILLIS_PER_DAY) as u32)
+    }
+}
+
+/// Reads [`COB_ID_TIME`], falling back to [`DEFAULT_COB_ID_TIME`].
+fn time_configuration<
+    const ENTRY_COUNT: usize,
+    const RPDO_COUNT: usize,
+    const TPDO_COUNT: usize,
+>(
+    od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+) -> (Id, u32) {
+    let raw = od
+        .read_unsigned(COB_ID_TIME)
+        .unwrap_or(DEFAULT_COB_ID_TIME as u64) as u32;
+    let id = if raw & EXTENDED_ID != 0 {
+        Id::Extended(ExtendedId::new(raw & 0x1FFF_FFFF).unwrap())
+    } else {
+        Id::Standard(StandardId::new((raw & 0x7FF) as u16).unwrap())
+    };
+    (id, raw)
+}
+
+/// Keeps a local time of day synchronised with the TIME frames received on
+/// the COB-ID configured in [`COB_ID_TIME`].
+pub struct TimeConsumer {
+    reference: Option<(TimeOfDay, Instant)>,
+}
+
+impl Default for TimeConsumer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeConsumer {
+    pub fn new() -> Self {
+        Self { reference: None }
+    }
+
+    /// Handles a frame received at `now`, returning the received time if it
+    /// is a TIME frame.
+    pub fn process_frame<
+        const ENTRY_COUNT: usize,
+        const RPDO_COUNT: usize,
+        const TPDO_COUNT: usize,
+    >(
+        &mut self,
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        frame: &impl Frame,
+        now: Instant,
+    ) -> Option<TimeOfDay> {
+        let (id, config) = time_configuration(od);
+        if config & CONSUME == 0 || frame.is_remote_frame() || frame.id() != id {
+            return None;
+        }
+        let time = TimeOfDay::from_bytes(frame.data().try_into().ok()?)?;
+        self.reference = Some((time, now));
+        Some(time)
+    }
+
+    /// Returns the current time of day, extrapolated from the last TIME frame
+    /// received.
+    pub fn time_of_day(&self, now: Instant) -> Option<TimeOfDay> {
+        let (time, received) = self.reference?;
+        let elapsed = now.saturating_duration_since(received).as_millis() as u64;
+        time.checked_add_millis(elapsed)
+    }
+}
+
+/// Produces TIME frames for a node acting as the time master.
+pub struct TimeProducer;
+
+impl TimeProducer {
+    /// Encodes a TIME frame, or returns `None` if the producer is not enabled
+    /// in [`COB_ID_TIME`].
+    pub fn encode<const ENTRY_COUNT: usize, const RPDO_COUNT: usize, const TPDO_COUNT: usize>(
+        od: &ObjectDictionary<ENTRY_COUNT, RPDO_COUNT, TPDO_COUNT>,
+        time: TimeOfDay,
+    ) -> Option<EncodedCANOpenFrame> {
+        let (id, config) = time_configuration(od);
+        if config & PRODUCE == 0 {
+            return None;
+        }
+        Some(EncodedCANOpenFrame::from_vec_data(
+            id,
+            Vec::from_slice(&time.to_bytes()).unwrap(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_can::Frame;
+    use heapless::Vec;
+
+    use crate::frame::EncodedCANOpenFrame;
+    use crate::node::NodeId;
+    use crate::object_dictionary::{AccessType, ObjectDictionary, Variable, VariableType};
+    use crate::parameter_coder::DefaultU32Coder;
+    use crate::time::Instant;
+
+    use super::{TimeConsumer, TimeOfDay, TimeProducer, COB_ID_TIME};
+
+    fn test_od(cob_id: u32) -> ObjectDictionary<1, 0, 0> {
+        ObjectDictionary::new(
+            0,
+            0,
+            [0; 8],
+            [],
+            [],
+            Vec::from_slice(&[Variable::new(
+                COB_ID_TIME,
+                "COB-ID TIME",
+                VariableType::UInt32(cob_id, &DefaultU32Coder),
+                AccessType::ReadWrite,
+            )])
+            .ok()
+            .unwrap(),
+            NodeId::new(1).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_unix_conversion() {
+        assert_eq!(
+            TimeOfDay::from_unix_millis(441_763_200_000),
+            TimeOfDay::new(0, 0)
+        );
+        assert_eq!(TimeOfDay::from_unix_millis(441_763_199_999), None);
+
+        // 2024-03-01T12:00:00.250Z
+        let time = TimeOfDay::from_unix_millis(1_709_294_400_250).unwrap();
+        assert_eq!(time.days(), 14670);
+        assert_eq!(time.millis(), 43_200_250);
+        assert_eq!(time.to_unix_millis(), 1_709_294_400_250);
+    }
+
+    #[test]
+    fn test_encoding() {
+        let time = TimeOfDay::new(0x1234, 0x04AB_CDEF).unwrap();
+        let bytes = time.to_bytes();
+        assert_eq!(bytes, [0xEF, 0xCD, 0xAB, 0x04, 0x34, 0x12]);
+        assert_eq!(TimeOfDay::from_bytes(bytes), Some(time));
+        assert_eq!(
+            TimeOfDay::from_bytes([0xEF, 0xCD, 0xAB, 0xF4, 0x34, 0x12]),
+            Some(time)
+        );
+        assert_eq!(TimeOfDay::new(0, 86_400_000), None);
+    }
+
+    #[test]
+    fn test_consumer_extrapolates() {
+        let od = test_od(0x8000_0100);
+        let mut consumer = TimeConsumer::new();
+        assert_eq!(consumer.time_of_day(Instant::from_millis(0)), None);
+
+        let time = TimeOfDay::new(100, 86_399_000).unwrap();
+        let frame = EncodedCANOpenFrame::new(0x100, &time.to_bytes());
+        assert_eq!(
+            consumer.process_frame(&od, &frame, Instant::from_millis(5_000)),
+            Some(time)
+        );
+        assert_eq!(
+            consumer.time_of_day(Instant::from_millis(7_500)),
+            TimeOfDay::new(101, 1_500)
+        );
+
+        let disabled = test_od(0x0000_0100);
+        assert_eq!(
+            consumer.process_frame(&disabled, &frame, Instant::from_millis(0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_producer() {
+        let time = TimeOfDay::new(1, 2).unwrap();
+        assert!(TimeProducer::encode(&test_od(0x8000_0100), time).is_none());
+
+        let frame = TimeProducer::encode(&test_od(0x4000_0100), time).unwrap();
+        assert_eq!(frame.id(), EncodedCANOpenFrame::new(0x100, &[]).id());
+        assert_eq!(frame.data(), [2, 0, 0, 0, 1, 0]);
+    }
+}